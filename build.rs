@@ -1,7 +1,22 @@
-use std::error::Error;
+use std::{error::Error, process::Command};
 
 fn main() -> Result<(), Box<dyn Error>> {
     prost_build::compile_protos(&["proto/messages.proto"], &["proto/"])?;
 
+    // Commit the server is built from, reported by ServerInfoRequest
+    let git_hash = Command::new("git")
+        .args(["rev-parse", "--short=12", "HEAD"])
+        .output()
+        .ok()
+        .filter(|output| output.status.success())
+        .and_then(|output| String::from_utf8(output.stdout).ok())
+        .map(|hash| hash.trim().to_string())
+        .filter(|hash| !hash.is_empty())
+        .unwrap_or_else(|| "unknown".to_string());
+    println!("cargo:rustc-env=GIT_HASH={}", git_hash);
+    println!("cargo:rerun-if-changed=proto/messages.proto");
+    println!("cargo:rerun-if-changed=.git/HEAD");
+    println!("cargo:rerun-if-changed=.git/refs");
+
     Ok(())
 }
@@ -0,0 +1,90 @@
+// Importing necessary modules and crates
+use std::{
+    io::{self, ErrorKind},
+    net::TcpListener,
+};
+
+// First descriptor passed by the service manager, the ones before it are stdio
+#[cfg(unix)]
+const LISTEN_FDS_START: i32 = 3;
+
+/// Returns the listening sockets the service manager passed to this process, as systemd does
+/// for socket activated services through `LISTEN_PID` and `LISTEN_FDS`. Empty if the process
+/// was started without any. The variables are removed, so child processes do not take the
+/// sockets for theirs.
+#[cfg(unix)]
+pub fn listeners() -> io::Result<Vec<TcpListener>> {
+    use log::info;
+    use std::{
+        env,
+        os::unix::io::{BorrowedFd, FromRawFd},
+    };
+
+    let pid = env::var("LISTEN_PID").ok();
+    let fds = env::var("LISTEN_FDS").ok();
+    env::remove_var("LISTEN_PID");
+    env::remove_var("LISTEN_FDS");
+    env::remove_var("LISTEN_FDNAMES");
+
+    // The sockets are meant for another process if the id differs, e.g. the parent's
+    let (Some(pid), Some(fds)) = (pid, fds) else {
+        return Ok(Vec::new());
+    };
+    if pid.parse::<u32>().ok() != Some(std::process::id()) {
+        return Ok(Vec::new());
+    }
+    let count: i32 = fds
+        .parse()
+        .ok()
+        .filter(|&count| count >= 0)
+        .ok_or_else(|| invalid(format!("LISTEN_FDS is not a count: {}", fds)))?;
+
+    let mut listeners = Vec::new();
+    for fd in LISTEN_FDS_START..LISTEN_FDS_START + count {
+        // SAFETY: the service manager keeps the descriptor open for this process, it is
+        // only borrowed until its type is known
+        let borrowed = unsafe { BorrowedFd::borrow_raw(fd) };
+        let socket = socket2::SockRef::from(&borrowed);
+        // Querying a descriptor that is no socket at all fails
+        let is_tcp = socket
+            .r#type()
+            .is_ok_and(|kind| kind == socket2::Type::STREAM)
+            && socket
+                .local_addr()
+                .is_ok_and(|addr| addr.as_socket().is_some());
+        if !is_tcp {
+            return Err(invalid(format!("Descriptor {} is no TCP socket", fd)));
+        }
+        // Not inherited by processes this one starts
+        socket.set_cloexec(true)?;
+        // SAFETY: the descriptor is an open TCP listener nothing else in this process owns
+        listeners.push(unsafe { TcpListener::from_raw_fd(fd) });
+    }
+    info!("Inherited {} listening sockets", listeners.len());
+    Ok(listeners)
+}
+
+/// Returns the listening sockets the service manager passed to this process
+#[cfg(not(unix))]
+pub fn listeners() -> io::Result<Vec<TcpListener>> {
+    // There is no service manager passing sockets
+    Ok(Vec::new())
+}
+
+/// Returns the single listening socket passed to this process, `None` if there is none.
+/// Fails if more than one was passed, the server listens on one.
+pub fn listener() -> io::Result<Option<TcpListener>> {
+    let mut listeners = listeners()?;
+    if listeners.len() > 1 {
+        return Err(invalid(format!(
+            "Expected one listening socket, got {}",
+            listeners.len()
+        )));
+    }
+    Ok(listeners.pop())
+}
+
+/// Error for what the service manager passed
+fn invalid(description: String) -> io::Error {
+    io::Error::new(ErrorKind::InvalidInput, description)
+}
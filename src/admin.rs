@@ -0,0 +1,49 @@
+// Importing necessary modules and crates
+use crate::message::{SetLogLevelRequest, SetLogLevelResponse};
+use log::{info, LevelFilter};
+use std::collections::HashMap;
+
+// Request header carrying the token that authorizes admin messages
+pub const ADMIN_TOKEN_HEADER: &str = "admin-token";
+
+/// Returns true when `headers` carry the configured admin token. Without a configured
+/// token admin messages are disabled altogether.
+pub(crate) fn is_authorized(token: Option<&str>, headers: &HashMap<String, String>) -> bool {
+    match (token, headers.get(ADMIN_TOKEN_HEADER)) {
+        (Some(token), Some(presented)) => constant_time_eq(token.as_bytes(), presented.as_bytes()),
+        _ => false,
+    }
+}
+
+/// Changes how verbose the process logs, answering with the level that applied before
+pub(crate) fn set_log_level(request: &SetLogLevelRequest) -> SetLogLevelResponse {
+    let previous_level = log::max_level().to_string().to_lowercase();
+    let rejected = |error: String| SetLogLevelResponse {
+        previous_level: previous_level.clone(),
+        error,
+    };
+
+    // The log facade only keeps a global level, filtering by target is up to the logger
+    if !request.target.is_empty() {
+        return rejected(format!(
+            "Levels cannot be set per target, got {}",
+            request.target
+        ));
+    }
+    let level: LevelFilter = match request.level.parse() {
+        Ok(level) => level,
+        Err(_) => return rejected(format!("Unknown log level: {}", request.level)),
+    };
+
+    log::set_max_level(level);
+    info!("Log level changed from {} to {}", previous_level, level);
+    SetLogLevelResponse {
+        previous_level,
+        error: String::new(),
+    }
+}
+
+/// Compares two byte strings in time independent of where they differ
+fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    a.len() == b.len() && a.iter().zip(b).fold(0, |diff, (x, y)| diff | (x ^ y)) == 0
+}
@@ -0,0 +1,61 @@
+// Importing necessary modules and crates
+use std::ops::{Deref, DerefMut};
+
+// Consecutive reads using at most a quarter of the buffer before it is shrunk
+const SHRINK_AFTER: u32 = 8;
+
+// Read buffer that grows when messages fill it and shrinks when they stay small
+#[derive(Debug)]
+pub struct AdaptiveBuffer {
+    data: Vec<u8>,    // Storage handed out for reads
+    min: usize,       // Smallest size the buffer shrinks to
+    max: usize,       // Largest size the buffer grows to
+    small_reads: u32, // Consecutive reads that used at most a quarter of the buffer
+}
+
+impl AdaptiveBuffer {
+    /// Creates a buffer of `initial` bytes that adapts between `min` and `max`
+    pub fn new(initial: usize, min: usize, max: usize) -> Self {
+        let min = min.max(1);
+        let max = max.max(min);
+        AdaptiveBuffer {
+            data: vec![0; initial.clamp(min, max)],
+            min,
+            max,
+            small_reads: 0,
+        }
+    }
+
+    /// Adjusts the size to a read of `bytes_read` bytes: a full buffer doubles, a long run
+    /// of small reads halves it
+    pub fn adapt(&mut self, bytes_read: usize) {
+        let len = self.data.len();
+        if bytes_read >= len {
+            self.small_reads = 0;
+            self.data.resize((len * 2).min(self.max), 0);
+        } else if bytes_read <= len / 4 {
+            self.small_reads += 1;
+            if self.small_reads == SHRINK_AFTER {
+                self.small_reads = 0;
+                self.data.truncate((len / 2).max(self.min));
+                self.data.shrink_to_fit();
+            }
+        } else {
+            self.small_reads = 0;
+        }
+    }
+}
+
+impl Deref for AdaptiveBuffer {
+    type Target = [u8];
+
+    fn deref(&self) -> &[u8] {
+        &self.data
+    }
+}
+
+impl DerefMut for AdaptiveBuffer {
+    fn deref_mut(&mut self) -> &mut [u8] {
+        &mut self.data
+    }
+}
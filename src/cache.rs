@@ -0,0 +1,182 @@
+// Importing necessary modules and crates
+use crate::clock::{Clock, SystemClock};
+use crate::message::server_message::Message as ServerMessageType;
+use log::warn;
+use std::{
+    collections::HashMap,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc, Mutex,
+    },
+    time::{Duration, Instant},
+};
+
+// Upper bound on the number of cached responses, whatever the TTLs
+const MAX_ENTRIES: usize = 1024;
+
+// A cached response
+struct Entry {
+    response: ServerMessageType, // Response to hand out again
+    expires_at: Instant,         // When the response must no longer be used
+}
+
+// Cache of responses of pure handlers, keyed by message type and encoded request
+pub struct ResponseCache {
+    ttls: HashMap<String, Duration>, // Time to live per cached message type
+    entries: Mutex<HashMap<(&'static str, Vec<u8>), Entry>>, // Cached responses
+    hits: AtomicU64,                 // Requests answered from the cache
+    misses: AtomicU64,               // Requests of cached types that had to be handled
+    clock: Arc<dyn Clock>,           // Time the TTLs are measured against
+}
+
+impl ResponseCache {
+    /// Creates a cache for the given message types, those `is_cacheable` rejects are ignored
+    pub fn new(ttls: &HashMap<String, Duration>, is_cacheable: impl Fn(&str) -> bool) -> Self {
+        ResponseCache::with_clock(ttls, is_cacheable, Arc::new(SystemClock))
+    }
+
+    /// Creates a cache like `new`, measuring the TTLs against `clock`
+    pub fn with_clock(
+        ttls: &HashMap<String, Duration>,
+        is_cacheable: impl Fn(&str) -> bool,
+        clock: Arc<dyn Clock>,
+    ) -> Self {
+        let mut cached = HashMap::new();
+        for (message_type, ttl) in ttls {
+            if is_cacheable(message_type) {
+                cached.insert(message_type.clone(), *ttl);
+            } else {
+                warn!("Responses to {} requests cannot be cached", message_type);
+            }
+        }
+
+        ResponseCache {
+            ttls: cached,
+            entries: Mutex::new(HashMap::new()),
+            hits: AtomicU64::new(0),
+            misses: AtomicU64::new(0),
+            clock,
+        }
+    }
+
+    /// Returns the cached response to `request`, or computes and caches it. `request` must be
+    /// the canonical encoding of the request, as produced by re-encoding the decoded message.
+    /// Requests without a response are not cached.
+    pub fn get_or_compute(
+        &self,
+        message_type: &'static str,
+        request: &[u8],
+        compute: impl FnOnce() -> Option<ServerMessageType>,
+    ) -> Option<ServerMessageType> {
+        let Some(ttl) = self.ttls.get(message_type) else {
+            return compute();
+        };
+
+        let now = self.clock.now();
+        let key = (message_type, request.to_vec());
+        if let Some(entry) = self.entries.lock().unwrap().get(&key) {
+            if entry.expires_at > now {
+                self.hits.fetch_add(1, Ordering::Relaxed);
+                return Some(entry.response.clone());
+            }
+        }
+
+        // Handle the request without holding the lock
+        self.misses.fetch_add(1, Ordering::Relaxed);
+        let response = compute()?;
+
+        let mut entries = self.entries.lock().unwrap();
+        if entries.len() >= MAX_ENTRIES {
+            entries.retain(|_, entry| entry.expires_at > now);
+        }
+        if entries.len() < MAX_ENTRIES {
+            entries.insert(
+                key,
+                Entry {
+                    response: response.clone(),
+                    expires_at: now + *ttl,
+                },
+            );
+        }
+        Some(response)
+    }
+
+    /// Returns how many requests were answered from the cache
+    pub fn hits(&self) -> u64 {
+        self.hits.load(Ordering::Relaxed)
+    }
+
+    /// Returns how many requests of cached types had to be handled
+    pub fn misses(&self) -> u64 {
+        self.misses.load(Ordering::Relaxed)
+    }
+}
+
+// Upper bound on the echoes a connection remembers for deduplication
+const MAX_DEDUP_ENTRIES: usize = 64;
+
+// A remembered echo
+struct EchoEntry {
+    response: Vec<u8>, // Encoded response sent back
+    seen_at: Instant,  // When the echo was handled
+}
+
+// Echo requests a single connection sent recently, so that identical ones arriving shortly
+// after are answered again without being handled, for clients that retry too eagerly
+pub struct EchoDedup {
+    window: Duration,                     // How long a handled echo is remembered
+    entries: HashMap<Vec<u8>, EchoEntry>, // Remembered echoes, by received payload
+    hits: u64,                            // Echoes answered without being handled
+    clock: Arc<dyn Clock>,                // Time the window is measured against
+}
+
+impl EchoDedup {
+    /// Creates an empty set remembering echoes for `window`
+    pub fn new(window: Duration) -> Self {
+        EchoDedup::with_clock(window, Arc::new(SystemClock))
+    }
+
+    /// Creates an empty set like `new`, measuring the window against `clock`
+    pub fn with_clock(window: Duration, clock: Arc<dyn Clock>) -> Self {
+        EchoDedup {
+            window,
+            entries: HashMap::new(),
+            hits: 0,
+            clock,
+        }
+    }
+
+    /// Returns the response to `request` if an identical one was handled within the window
+    pub fn get(&mut self, request: &[u8]) -> Option<Vec<u8>> {
+        let entry = self.entries.get(request)?;
+        if self.clock.now().duration_since(entry.seen_at) > self.window {
+            return None;
+        }
+        self.hits += 1;
+        Some(entry.response.clone())
+    }
+
+    /// Remembers the response sent to a handled echo `request`
+    pub fn insert(&mut self, request: &[u8], response: Vec<u8>) {
+        let now = self.clock.now();
+        if self.entries.len() >= MAX_DEDUP_ENTRIES {
+            let window = self.window;
+            self.entries
+                .retain(|_, entry| now.duration_since(entry.seen_at) <= window);
+        }
+        if self.entries.len() < MAX_DEDUP_ENTRIES {
+            self.entries.insert(
+                request.to_vec(),
+                EchoEntry {
+                    response,
+                    seen_at: now,
+                },
+            );
+        }
+    }
+
+    /// Returns how many echoes were answered without being handled
+    pub fn hits(&self) -> u64 {
+        self.hits
+    }
+}
@@ -0,0 +1,157 @@
+// Importing necessary modules and crates
+use crate::events::CloseReason;
+use crate::message::{ChurnBucket as ChurnBucketMessage, ConnectionChurnResponse};
+use std::{
+    collections::VecDeque,
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+// Time covered by every churn bucket
+pub const CHURN_BUCKET: Duration = Duration::from_secs(60);
+
+// Number of buckets kept, the length of the rolling window
+pub const CHURN_BUCKETS: usize = 60;
+
+// Connections opened and closed during one bucket of time
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ChurnBucket {
+    pub start: SystemTime,                          // Start of the bucket
+    pub connects: u64,                              // Connections accepted
+    pub disconnects: [u64; CloseReason::ALL.len()], // Connections closed, per reason
+}
+
+impl ChurnBucket {
+    /// Returns how many connections were closed for the given reason
+    pub fn disconnects(&self, reason: CloseReason) -> u64 {
+        self.disconnects[reason as usize]
+    }
+
+    /// Returns how many connections were closed, whatever the reason
+    pub fn total_disconnects(&self) -> u64 {
+        self.disconnects.iter().sum()
+    }
+
+    /// Converts the bucket into its wire representation
+    pub fn to_message(&self) -> ChurnBucketMessage {
+        ChurnBucketMessage {
+            start_ms: unix_millis(self.start),
+            connects: self.connects,
+            disconnects: CloseReason::ALL
+                .into_iter()
+                .filter(|&reason| self.disconnects(reason) > 0)
+                .map(|reason| (reason.as_str().to_string(), self.disconnects(reason)))
+                .collect(),
+        }
+    }
+}
+
+// Connects and disconnects over a rolling window of fixed-width time buckets, to make
+// reconnect storms and flapping clients visible
+#[derive(Debug)]
+pub struct ConnectionChurn {
+    width: Duration,                       // Time covered by every bucket
+    capacity: usize,                       // Number of buckets making up the window
+    buckets: VecDeque<(u64, ChurnBucket)>, // Buckets with churn and their index, oldest first
+}
+
+impl Default for ConnectionChurn {
+    fn default() -> Self {
+        ConnectionChurn::new(CHURN_BUCKET, CHURN_BUCKETS)
+    }
+}
+
+impl ConnectionChurn {
+    /// Creates a window of `capacity` buckets, each covering `width` of time
+    pub fn new(width: Duration, capacity: usize) -> Self {
+        ConnectionChurn {
+            width: width.max(Duration::from_millis(1)),
+            capacity: capacity.max(1),
+            buckets: VecDeque::new(),
+        }
+    }
+
+    /// Returns the time covered by every bucket
+    pub fn width(&self) -> Duration {
+        self.width
+    }
+
+    /// Counts a connection accepted at `at`
+    pub fn record_connect(&mut self, at: SystemTime) {
+        self.bucket(at).connects += 1;
+    }
+
+    /// Counts a connection closed at `at` for the given reason
+    pub fn record_disconnect(&mut self, at: SystemTime, reason: CloseReason) {
+        self.bucket(at).disconnects[reason as usize] += 1;
+    }
+
+    /// Returns the buckets of the window ending at `now` that saw churn, oldest first
+    pub fn buckets(&mut self, now: SystemTime) -> Vec<ChurnBucket> {
+        self.expire(self.index(now));
+        self.buckets
+            .iter()
+            .map(|(_, bucket)| bucket.clone())
+            .collect()
+    }
+
+    /// Forgets everything recorded so far
+    pub fn clear(&mut self) {
+        self.buckets.clear();
+    }
+
+    /// Describes the window ending at `now` for a ConnectionChurnRequest
+    pub fn to_response(&mut self, now: SystemTime) -> ConnectionChurnResponse {
+        ConnectionChurnResponse {
+            buckets: self
+                .buckets(now)
+                .iter()
+                .map(ChurnBucket::to_message)
+                .collect(),
+            bucket_secs: self.width.as_secs() as u32,
+            error: String::new(),
+        }
+    }
+
+    /// Returns the bucket `at` falls into, started if needed
+    fn bucket(&mut self, at: SystemTime) -> &mut ChurnBucket {
+        let index = self.index(at);
+        self.expire(index);
+        // Clocks may step back, count such events in the newest bucket rather than losing them
+        if self.buckets.back().is_none_or(|(last, _)| *last < index) {
+            let start = UNIX_EPOCH + Duration::from_millis(index * self.width.as_millis() as u64);
+            self.buckets.push_back((
+                index,
+                ChurnBucket {
+                    start,
+                    connects: 0,
+                    disconnects: [0; CloseReason::ALL.len()],
+                },
+            ));
+        }
+        &mut self.buckets.back_mut().unwrap().1
+    }
+
+    /// Drops the buckets that no longer belong to the window whose newest bucket is `index`
+    fn expire(&mut self, index: u64) {
+        let oldest = index.saturating_sub(self.capacity as u64 - 1);
+        while self
+            .buckets
+            .front()
+            .is_some_and(|(first, _)| *first < oldest)
+        {
+            self.buckets.pop_front();
+        }
+    }
+
+    /// Returns the number of the bucket `at` falls into, counted from the Unix epoch
+    fn index(&self, at: SystemTime) -> u64 {
+        unix_millis(at) / self.width.as_millis() as u64
+    }
+}
+
+/// Converts a point in time into milliseconds since the Unix epoch
+fn unix_millis(at: SystemTime) -> u64 {
+    at.duration_since(UNIX_EPOCH)
+        .map(|elapsed| elapsed.as_millis() as u64)
+        .unwrap_or(0)
+}
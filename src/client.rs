@@ -0,0 +1,509 @@
+// Importing necessary modules and crates
+use crate::framing::{self, DEFAULT_MAX_FRAME_SIZE};
+use crate::impairment::ImpairedStream;
+use crate::message::client_message::Message as ClientMessageType;
+use crate::message::server_message::Message as ServerMessageType;
+use crate::message::{
+    AddRequest, ClientGoodbye, ClientMessage, DivideRequest, EchoMessage, ErrorResponse,
+    MultiplyRequest, PingRequest, PongResponse, ServerInfoRequest, ServerInfoResponse,
+    ServerMessage, SubtractRequest,
+};
+use prost::Message;
+use std::{
+    collections::VecDeque,
+    error::Error,
+    fmt,
+    io::{self, ErrorKind, Read, Write},
+    net::{Shutdown, SocketAddr, TcpStream, ToSocketAddrs},
+    sync::{Arc, Mutex},
+    time::{Duration, Instant, SystemTime, UNIX_EPOCH},
+};
+
+// How long connecting and waiting for a response may take unless configured otherwise
+pub const DEFAULT_TIMEOUT: Duration = Duration::from_secs(5);
+
+// Header the server sets on messages it pushes unasked, such as publications and broadcasts
+pub const PUSHED_HEADER: &str = "pushed";
+
+// Why a client operation failed
+#[derive(Debug)]
+pub enum ClientError {
+    ConnectFailed(io::Error),   // The server could not be reached
+    Timeout,                    // The server did not answer in time
+    ConnectionLost(io::Error),  // The connection broke while in use
+    Decode(prost::DecodeError), // The server sent something that is not a ServerMessage
+    UnexpectedResponse(Option<ServerMessageType>), // The server answered with something else
+    Rejected(ErrorResponse),    // The server could not handle the request at all
+}
+
+impl fmt::Display for ClientError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ClientError::ConnectFailed(e) => write!(f, "Failed to connect: {}", e),
+            ClientError::Timeout => write!(f, "Timed out waiting for the server"),
+            ClientError::ConnectionLost(e) => write!(f, "Connection lost: {}", e),
+            ClientError::Decode(e) => write!(f, "Failed to decode ServerMessage: {}", e),
+            ClientError::UnexpectedResponse(message) => {
+                write!(f, "Unexpected response: {:?}", message)
+            }
+            ClientError::Rejected(error) => {
+                write!(
+                    f,
+                    "Request rejected ({:?}): {}",
+                    error.code(),
+                    error.description
+                )
+            }
+        }
+    }
+}
+
+impl Error for ClientError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            ClientError::ConnectFailed(e) | ClientError::ConnectionLost(e) => Some(e),
+            ClientError::Decode(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl ClientError {
+    /// Classifies an I/O error on an established connection
+    fn from_io(e: io::Error) -> Self {
+        match e.kind() {
+            ErrorKind::WouldBlock | ErrorKind::TimedOut => ClientError::Timeout,
+            _ => ClientError::ConnectionLost(e),
+        }
+    }
+}
+
+// How a client connects and sets up its socket
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ClientOptions {
+    pub timeout: Duration, // How long every connection attempt and response may take
+    pub nodelay: bool,     // Whether Nagle's algorithm is disabled
+    pub keepalive: Option<Duration>, // Idle time before keepalive probes are sent, if enabled
+}
+
+impl Default for ClientOptions {
+    fn default() -> Self {
+        ClientOptions {
+            timeout: DEFAULT_TIMEOUT,
+            nodelay: false,
+            keepalive: None,
+        }
+    }
+}
+
+// What the client and the server settled on for a connection. There is no compression and
+// nothing else to negotiate yet.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SessionInfo {
+    pub connection_id: u64,         // Id the server assigned to the connection
+    pub protocol_version: u32,      // Version of the protocol the server speaks
+    pub max_frame_size: usize,      // Largest payload both sides accept
+    pub server: ServerInfoResponse, // Build of the server, as it describes itself
+}
+
+// Connection a client speaks the protocol over: a TcpStream, or a wrapper around one such
+// as an ImpairedStream
+pub trait Transport: Read + Write {
+    /// Makes reads and writes return right away instead of blocking, or block again
+    fn set_nonblocking(&self, nonblocking: bool) -> io::Result<()>;
+
+    /// Shuts down both directions of the connection
+    fn close(&self) -> io::Result<()>;
+}
+
+impl Transport for TcpStream {
+    fn set_nonblocking(&self, nonblocking: bool) -> io::Result<()> {
+        TcpStream::set_nonblocking(self, nonblocking)
+    }
+
+    fn close(&self) -> io::Result<()> {
+        self.shutdown(Shutdown::Both)
+    }
+}
+
+impl<S: Transport> Transport for ImpairedStream<S> {
+    fn set_nonblocking(&self, nonblocking: bool) -> io::Result<()> {
+        self.get_ref()
+            .map_or(Ok(()), |inner| inner.set_nonblocking(nonblocking))
+    }
+
+    /// Closes the wrapped transport, unless a simulated disconnect dropped it already
+    fn close(&self) -> io::Result<()> {
+        self.get_ref().map_or(Ok(()), Transport::close)
+    }
+}
+
+// Connection to a server, speaking its framed protobuf protocol
+pub struct Client<S: Transport = TcpStream> {
+    stream: S,                         // Connection to the server
+    max_frame_size: usize,             // Largest response accepted
+    owns_connection: bool,             // Whether dropping the client ends the connection
+    session_info: Option<SessionInfo>, // Parameters of the connection, once asked for
+    pushed: VecDeque<ServerMessage>,   // Pushed messages that arrived while awaiting a response
+}
+
+// Receiving half of a split client, for a thread dedicated to consuming messages
+pub struct ClientReader {
+    stream: TcpStream,               // Connection to the server
+    max_frame_size: usize,           // Largest message accepted
+    pushed: VecDeque<ServerMessage>, // Pushed messages the client received before the split
+}
+
+// Sending half of a split client, cheap to clone and safe to share between threads
+#[derive(Clone)]
+pub struct ClientWriter {
+    stream: Arc<Mutex<TcpStream>>, // Connection to the server, locked so frames never interleave
+}
+
+impl Client {
+    /// Connects to the server at `addr`, trying every address it resolves to in turn
+    pub fn connect(addr: impl ToSocketAddrs) -> Result<Self, ClientError> {
+        Self::connect_timeout(addr, DEFAULT_TIMEOUT)
+    }
+
+    /// Connects to the server at `addr`, waiting at most `timeout` for every connection
+    /// attempt and, afterwards, for every response
+    pub fn connect_timeout(
+        addr: impl ToSocketAddrs,
+        timeout: Duration,
+    ) -> Result<Self, ClientError> {
+        let options = ClientOptions {
+            timeout,
+            ..ClientOptions::default()
+        };
+        Self::connect_with(addr, &options)
+    }
+
+    /// Connects to the server at `addr` like `connect_timeout`, setting up the socket as
+    /// `options` say
+    pub fn connect_with(
+        addr: impl ToSocketAddrs,
+        options: &ClientOptions,
+    ) -> Result<Self, ClientError> {
+        let timeout = options.timeout;
+        let mut last_error = None;
+        for addr in addr.to_socket_addrs().map_err(ClientError::ConnectFailed)? {
+            match TcpStream::connect_timeout(&addr, timeout) {
+                Ok(stream) => {
+                    stream
+                        .set_read_timeout(Some(timeout))
+                        .and_then(|_| stream.set_write_timeout(Some(timeout)))
+                        .and_then(|_| stream.set_nodelay(options.nodelay))
+                        .map_err(ClientError::ConnectFailed)?;
+                    if let Some(idle) = options.keepalive {
+                        let keepalive = socket2::TcpKeepalive::new().with_time(idle);
+                        socket2::SockRef::from(&stream)
+                            .set_tcp_keepalive(&keepalive)
+                            .map_err(ClientError::ConnectFailed)?;
+                    }
+                    return Ok(Client::over(stream));
+                }
+                Err(e) => last_error = Some(e),
+            }
+        }
+
+        Err(match last_error {
+            Some(e) if e.kind() == ErrorKind::TimedOut => ClientError::Timeout,
+            Some(e) => ClientError::ConnectFailed(e),
+            None => ClientError::ConnectFailed(io::Error::new(
+                ErrorKind::InvalidInput,
+                "Address did not resolve",
+            )),
+        })
+    }
+
+    /// Returns the address of the server
+    pub fn peer_addr(&self) -> io::Result<SocketAddr> {
+        self.stream.peer_addr()
+    }
+
+    /// Returns the underlying stream, for socket options the client does not cover
+    pub fn stream(&self) -> &TcpStream {
+        &self.stream
+    }
+
+    /// Splits the client into halves that can be used from different threads. The
+    /// connection ends once both halves, and every clone of the writer, are dropped.
+    pub fn split(mut self) -> Result<(ClientReader, ClientWriter), ClientError> {
+        let reader = self
+            .stream
+            .try_clone()
+            .map_err(ClientError::ConnectionLost)?;
+        let writer = self
+            .stream
+            .try_clone()
+            .map_err(ClientError::ConnectionLost)?;
+        self.owns_connection = false;
+        Ok((
+            ClientReader {
+                stream: reader,
+                max_frame_size: self.max_frame_size,
+                pushed: std::mem::take(&mut self.pushed),
+            },
+            ClientWriter {
+                stream: Arc::new(Mutex::new(writer)),
+            },
+        ))
+    }
+}
+
+impl<S: Transport> Client<S> {
+    /// Speaks the protocol over `stream`, connected to the server already. Timeouts and
+    /// socket options are whatever `stream` was set up with. Wrapping the stream, e.g. in
+    /// an ImpairedStream, tests how client and server cope with a degraded connection.
+    pub fn over(stream: S) -> Self {
+        Client {
+            stream,
+            max_frame_size: DEFAULT_MAX_FRAME_SIZE,
+            owns_connection: true,
+            session_info: None,
+            pushed: VecDeque::new(),
+        }
+    }
+
+    /// Returns the transport the client speaks over
+    pub fn transport(&self) -> &S {
+        &self.stream
+    }
+
+    /// Returns the parameters of the connection, asking the server for them the first time
+    pub fn session_info(&mut self) -> Result<&SessionInfo, ClientError> {
+        if self.session_info.is_none() {
+            let request = ClientMessageType::ServerInfoRequest(ServerInfoRequest {});
+            let server = match self.request(request)?.message {
+                Some(ServerMessageType::ServerInfoResponse(server)) => server,
+                other => return Err(ClientError::UnexpectedResponse(other)),
+            };
+            self.session_info = Some(SessionInfo {
+                connection_id: server.session_id,
+                protocol_version: server.protocol_version,
+                max_frame_size: self.max_frame_size.min(server.max_frame_size as usize),
+                server,
+            });
+        }
+        Ok(self
+            .session_info
+            .as_ref()
+            .expect("Session info was just set"))
+    }
+
+    /// Has the server echo `content` and returns what came back
+    pub fn send_echo(&mut self, content: &str) -> Result<String, ClientError> {
+        let message = ClientMessageType::EchoMessage(EchoMessage {
+            content: content.to_string(),
+        });
+        match self.request(message)?.message {
+            Some(ServerMessageType::EchoMessage(echo)) => Ok(echo.content),
+            other => Err(ClientError::UnexpectedResponse(other)),
+        }
+    }
+
+    /// Has the server add two numbers
+    pub fn add(&mut self, a: i32, b: i32) -> Result<i32, ClientError> {
+        match self
+            .request(ClientMessageType::AddRequest(AddRequest { a, b }))?
+            .message
+        {
+            Some(ServerMessageType::AddResponse(add)) => Ok(add.result),
+            other => Err(ClientError::UnexpectedResponse(other)),
+        }
+    }
+
+    /// Has the server subtract `b` from `a`
+    pub fn subtract(&mut self, a: i32, b: i32) -> Result<i32, ClientError> {
+        match self
+            .request(ClientMessageType::SubtractRequest(SubtractRequest { a, b }))?
+            .message
+        {
+            Some(ServerMessageType::SubtractResponse(subtract)) => Ok(subtract.result),
+            other => Err(ClientError::UnexpectedResponse(other)),
+        }
+    }
+
+    /// Has the server multiply two numbers
+    pub fn multiply(&mut self, a: i32, b: i32) -> Result<i32, ClientError> {
+        match self
+            .request(ClientMessageType::MultiplyRequest(MultiplyRequest { a, b }))?
+            .message
+        {
+            Some(ServerMessageType::MultiplyResponse(multiply)) => Ok(multiply.result),
+            other => Err(ClientError::UnexpectedResponse(other)),
+        }
+    }
+
+    /// Has the server divide `a` by `b`, returning the quotient and the remainder
+    pub fn divide(&mut self, a: i32, b: i32) -> Result<(i32, i32), ClientError> {
+        match self
+            .request(ClientMessageType::DivideRequest(DivideRequest { a, b }))?
+            .message
+        {
+            Some(ServerMessageType::DivideResponse(divide)) => {
+                Ok((divide.quotient, divide.remainder))
+            }
+            other => Err(ClientError::UnexpectedResponse(other)),
+        }
+    }
+
+    /// Pings the server and returns how long the answer took
+    pub fn ping(&mut self) -> Result<Duration, ClientError> {
+        let started = Instant::now();
+        let nonce = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |elapsed| elapsed.as_nanos() as u64);
+        match self
+            .request(ClientMessageType::PingRequest(PingRequest { nonce }))?
+            .message
+        {
+            Some(ServerMessageType::PongResponse(pong)) if pong.nonce == nonce => {
+                Ok(started.elapsed())
+            }
+            other => Err(ClientError::UnexpectedResponse(other)),
+        }
+    }
+
+    /// Sends a request and waits for the response, an ErrorResponse fails the request.
+    /// Messages pushed in the meantime are kept for `next_push` and `receive`.
+    pub fn request(&mut self, message: ClientMessageType) -> Result<ServerMessage, ClientError> {
+        self.send(ClientMessage {
+            message: Some(message),
+            ..ClientMessage::default()
+        })?;
+        let response = loop {
+            let message = self.receive_from_server()?;
+            if !is_pushed(&message) {
+                break message;
+            }
+            self.pushed.push_back(message);
+        };
+        match response.message {
+            Some(ServerMessageType::ErrorResponse(error)) => Err(ClientError::Rejected(error)),
+            _ => Ok(response),
+        }
+    }
+
+    /// Waits for the next message the server pushed unasked, e.g. a Publication once
+    /// subscribed to a topic. Anything else arriving meanwhile answers no request and fails
+    /// with `UnexpectedResponse`.
+    pub fn next_push(&mut self) -> Result<ServerMessage, ClientError> {
+        if let Some(message) = self.pushed.pop_front() {
+            return Ok(message);
+        }
+        let message = self.receive_from_server()?;
+        if is_pushed(&message) {
+            Ok(message)
+        } else {
+            Err(ClientError::UnexpectedResponse(message.message))
+        }
+    }
+
+    /// Sends a message without waiting for anything in return
+    pub fn send(&mut self, message: ClientMessage) -> Result<(), ClientError> {
+        send_on(&mut self.stream, &message)
+    }
+
+    /// Waits for the next message from the server, answering its heartbeat pings on the
+    /// way. Pushed messages kept while awaiting a response come first.
+    pub fn receive(&mut self) -> Result<ServerMessage, ClientError> {
+        match self.pushed.pop_front() {
+            Some(message) => Ok(message),
+            None => self.receive_from_server(),
+        }
+    }
+
+    /// Reads the next message off the connection, answering heartbeat pings on the way
+    fn receive_from_server(&mut self) -> Result<ServerMessage, ClientError> {
+        loop {
+            let message = receive_on(&mut self.stream, self.max_frame_size)?;
+            let Some(ServerMessageType::PingRequest(ping)) = message.message else {
+                return Ok(message);
+            };
+            self.send(ClientMessage {
+                message: Some(ClientMessageType::PongResponse(PongResponse {
+                    nonce: ping.nonce,
+                })),
+                ..ClientMessage::default()
+            })?;
+        }
+    }
+}
+
+impl ClientReader {
+    /// Waits for the next message from the server, heartbeat pings included, which the
+    /// writer has to answer
+    pub fn receive(&mut self) -> Result<ServerMessage, ClientError> {
+        match self.pushed.pop_front() {
+            Some(message) => Ok(message),
+            None => receive_on(&mut self.stream, self.max_frame_size),
+        }
+    }
+
+    /// Sets how long `receive` waits, `None` waits for as long as it takes. Shared with
+    /// the writer, whose connection this is as well.
+    pub fn set_read_timeout(&self, timeout: Option<Duration>) -> Result<(), ClientError> {
+        self.stream
+            .set_read_timeout(timeout)
+            .map_err(ClientError::ConnectionLost)
+    }
+}
+
+impl ClientWriter {
+    /// Sends a message without waiting for anything in return
+    pub fn send(&self, message: ClientMessage) -> Result<(), ClientError> {
+        send_on(&mut *self.stream.lock().unwrap(), &message)
+    }
+}
+
+/// Returns true if the server pushed `message` rather than answering a request with it
+fn is_pushed(message: &ServerMessage) -> bool {
+    message.headers.contains_key(PUSHED_HEADER)
+        || matches!(
+            message.message,
+            Some(ServerMessageType::Publication(_) | ServerMessageType::PublishAck(_))
+        )
+}
+
+/// Sends `message` as a single frame
+fn send_on<W: Write>(stream: &mut W, message: &ClientMessage) -> Result<(), ClientError> {
+    framing::write_frame(stream, &message.encode_to_vec())
+        .and_then(|_| stream.flush())
+        .map_err(ClientError::from_io)
+}
+
+/// Reads and decodes the next frame
+fn receive_on<R: Read>(
+    stream: &mut R,
+    max_frame_size: usize,
+) -> Result<ServerMessage, ClientError> {
+    let payload = match framing::read_frame(stream, max_frame_size) {
+        Ok(payload) => payload,
+        Err(e) if e.kind() == ErrorKind::UnexpectedEof => {
+            return Err(ClientError::ConnectionLost(io::Error::new(
+                ErrorKind::ConnectionAborted,
+                "Server disconnected",
+            )));
+        }
+        Err(e) => return Err(ClientError::from_io(e)),
+    };
+    ServerMessage::decode(payload.as_slice()).map_err(ClientError::Decode)
+}
+
+impl<S: Transport> Drop for Client<S> {
+    /// Tells the server the client leaves on purpose, best effort and without blocking
+    fn drop(&mut self) {
+        if !self.owns_connection {
+            return;
+        }
+        let goodbye = ClientMessage {
+            message: Some(ClientMessageType::ClientGoodbye(ClientGoodbye {})),
+            ..ClientMessage::default()
+        };
+        let _ = self.stream.set_nonblocking(true);
+        let _ = framing::write_frame(&mut self.stream, &goodbye.encode_to_vec());
+        let _ = self.stream.close();
+    }
+}
@@ -0,0 +1,76 @@
+// Importing necessary modules and crates
+use std::{
+    fmt,
+    sync::{Arc, Mutex},
+    time::{Duration, Instant, SystemTime},
+};
+
+// Source of the current time for TTLs, windows and other time-dependent state, so tests can
+// control it instead of waiting for it
+pub trait Clock: fmt::Debug + Send + Sync {
+    /// Returns the current monotonic time
+    fn now(&self) -> Instant;
+
+    /// Returns the current wall-clock time
+    fn system_time(&self) -> SystemTime;
+}
+
+// The clock of the operating system
+#[derive(Debug, Clone, Copy, Default)]
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+    fn now(&self) -> Instant {
+        Instant::now()
+    }
+
+    fn system_time(&self) -> SystemTime {
+        SystemTime::now()
+    }
+}
+
+// A clock that only moves when told to. Clones share the same time, so a test can keep one
+// and hand another to the code under test.
+#[derive(Debug, Clone)]
+pub struct MockClock {
+    elapsed: Arc<Mutex<Duration>>, // Time advanced so far
+    started: Instant,              // Monotonic time the clock started at
+    started_system: SystemTime,    // Wall-clock time the clock started at
+}
+
+impl Default for MockClock {
+    fn default() -> Self {
+        MockClock::new()
+    }
+}
+
+impl MockClock {
+    /// Creates a clock standing at the current time
+    pub fn new() -> Self {
+        MockClock {
+            elapsed: Arc::new(Mutex::new(Duration::ZERO)),
+            started: Instant::now(),
+            started_system: SystemTime::now(),
+        }
+    }
+
+    /// Moves the clock, and every clone of it, forward by `duration`
+    pub fn advance(&self, duration: Duration) {
+        *self.elapsed.lock().unwrap() += duration;
+    }
+
+    /// Returns how far the clock was moved since it was created
+    pub fn elapsed(&self) -> Duration {
+        *self.elapsed.lock().unwrap()
+    }
+}
+
+impl Clock for MockClock {
+    fn now(&self) -> Instant {
+        self.started + self.elapsed()
+    }
+
+    fn system_time(&self) -> SystemTime {
+        self.started_system + self.elapsed()
+    }
+}
@@ -0,0 +1,140 @@
+// Importing necessary modules and crates
+use crate::message::Matrix;
+use std::{error::Error, fmt, thread};
+
+// Largest number of floats a single operand or result may hold
+pub const MAX_ELEMENTS: usize = 16 * 1024;
+
+// Multiply-adds below which splitting the work across threads costs more than it saves
+const PARALLEL_THRESHOLD: usize = 64 * 1024;
+
+// Why a numeric request was rejected
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ComputeError {
+    LengthMismatch { a: usize, b: usize }, // The vectors of a dot product differ in length
+    BadShape { rows: u32, cols: u32, values: usize }, // A matrix holds the wrong number of values
+    DimensionMismatch { a_cols: u32, b_rows: u32 }, // The matrices cannot be multiplied
+    TooLarge { elements: usize },          // An operand or the result exceeds MAX_ELEMENTS
+}
+
+impl fmt::Display for ComputeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ComputeError::LengthMismatch { a, b } => {
+                write!(f, "Vectors differ in length: {} and {}", a, b)
+            }
+            ComputeError::BadShape { rows, cols, values } => write!(
+                f,
+                "A {}x{} matrix needs {} values, got {}",
+                rows,
+                cols,
+                *rows as u64 * *cols as u64,
+                values
+            ),
+            ComputeError::DimensionMismatch { a_cols, b_rows } => write!(
+                f,
+                "Cannot multiply a matrix with {} columns by one with {} rows",
+                a_cols, b_rows
+            ),
+            ComputeError::TooLarge { elements } => write!(
+                f,
+                "{} elements exceed the limit of {}",
+                elements, MAX_ELEMENTS
+            ),
+        }
+    }
+}
+
+impl Error for ComputeError {}
+
+/// Returns the dot product of two vectors of equal length
+pub fn dot_product(a: &[f32], b: &[f32]) -> Result<f32, ComputeError> {
+    if a.len() != b.len() {
+        return Err(ComputeError::LengthMismatch {
+            a: a.len(),
+            b: b.len(),
+        });
+    }
+    check_size(a.len())?;
+
+    Ok(a.iter().zip(b).map(|(x, y)| x * y).sum())
+}
+
+/// Returns the product of two matrices, computing bands of rows in parallel when the
+/// matrices are large
+pub fn matrix_multiply(a: &Matrix, b: &Matrix) -> Result<Matrix, ComputeError> {
+    check_shape(a)?;
+    check_shape(b)?;
+    if a.cols != b.rows {
+        return Err(ComputeError::DimensionMismatch {
+            a_cols: a.cols,
+            b_rows: b.rows,
+        });
+    }
+    let (rows, inner, cols) = (a.rows as usize, a.cols as usize, b.cols as usize);
+    check_size(rows * cols)?;
+
+    let mut values = vec![0.0; rows * cols];
+    if cols > 0 {
+        // Every thread fills a band of rows of the result
+        let multiply_rows = |first_row: usize, band: &mut [f32]| {
+            for (offset, out) in band.chunks_mut(cols).enumerate() {
+                let row = &a.values[(first_row + offset) * inner..][..inner];
+                for (k, &x) in row.iter().enumerate() {
+                    let b_row = &b.values[k * cols..][..cols];
+                    for (out, &y) in out.iter_mut().zip(b_row) {
+                        *out += x * y;
+                    }
+                }
+            }
+        };
+        let threads = threads_for(rows * inner * cols).min(rows.max(1));
+        if threads == 1 {
+            multiply_rows(0, &mut values);
+        } else {
+            let band_rows = rows.div_ceil(threads);
+            thread::scope(|scope| {
+                for (band, chunk) in values.chunks_mut(band_rows * cols).enumerate() {
+                    let multiply_rows = &multiply_rows;
+                    scope.spawn(move || multiply_rows(band * band_rows, chunk));
+                }
+            });
+        }
+    }
+
+    Ok(Matrix {
+        rows: a.rows,
+        cols: b.cols,
+        values,
+    })
+}
+
+/// Rejects matrices whose values do not match their dimensions
+fn check_shape(matrix: &Matrix) -> Result<(), ComputeError> {
+    let elements = matrix.rows as u64 * matrix.cols as u64;
+    if elements != matrix.values.len() as u64 {
+        return Err(ComputeError::BadShape {
+            rows: matrix.rows,
+            cols: matrix.cols,
+            values: matrix.values.len(),
+        });
+    }
+    check_size(matrix.values.len())
+}
+
+/// Rejects operands and results beyond MAX_ELEMENTS
+fn check_size(elements: usize) -> Result<(), ComputeError> {
+    if elements > MAX_ELEMENTS {
+        return Err(ComputeError::TooLarge { elements });
+    }
+    Ok(())
+}
+
+/// Returns how many threads `work` multiply-adds are worth
+fn threads_for(work: usize) -> usize {
+    if work < PARALLEL_THRESHOLD {
+        return 1;
+    }
+    let available = thread::available_parallelism().map_or(1, |threads| threads.get());
+    available.min(work / PARALLEL_THRESHOLD).max(1)
+}
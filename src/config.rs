@@ -0,0 +1,276 @@
+// Importing necessary modules and crates
+use crate::server::{HeaderLimits, ServerConfig};
+use log::LevelFilter;
+use std::{env, error::Error, fmt, fs, io, path::Path, path::PathBuf, time::Duration};
+use toml_edit::{DocumentMut, Item};
+
+// Prefix of the environment variables overriding settings, e.g.
+// EMBEDDED_TASK_LIMITS_MAX_CONNECTIONS. Generic prefixes such as SERVER_ clash with variables
+// set by the environment, Kubernetes adds SERVER_SERVICE_HOST for a Service named "server".
+pub const ENV_PREFIX: &str = "EMBEDDED_TASK_";
+
+// Why a configuration could not be loaded
+#[derive(Debug)]
+pub enum ConfigError {
+    Read { path: PathBuf, source: io::Error }, // The file could not be read
+    Parse { message: String },                 // The file is no valid TOML
+    UnknownSetting { key: String },            // No setting has this name
+    InvalidValue { key: String, expected: &'static str }, // The value has the wrong type or range
+}
+
+impl fmt::Display for ConfigError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ConfigError::Read { path, source } => {
+                write!(f, "Failed to read {}: {}", path.display(), source)
+            }
+            ConfigError::Parse { message } => write!(f, "Invalid TOML: {}", message),
+            ConfigError::UnknownSetting { key } => write!(f, "Unknown setting {}", key),
+            ConfigError::InvalidValue { key, expected } => {
+                write!(f, "Setting {} must be {}", key, expected)
+            }
+        }
+    }
+}
+
+impl Error for ConfigError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            ConfigError::Read { source, .. } => Some(source),
+            _ => None,
+        }
+    }
+}
+
+// Settings of a deployment, as read from a file like
+//
+//     address = "0.0.0.0:8080"
+//     log_level = "info"
+//
+//     [limits]
+//     max_connections = 1000
+//
+//     [timeouts]
+//     read = 30.0
+//
+// Settings left out keep the defaults of ServerConfig.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Config {
+    pub address: Option<String>, // Address to bind to, required to start a server
+    pub log_level: Option<LevelFilter>, // Most verbose messages logged
+    pub admin_token: Option<String>, // Token authorizing admin messages
+    pub max_connections: Option<usize>, // limits.max_connections
+    pub max_message_size: Option<usize>, // limits.max_message_size, in bytes
+    pub max_headers: Option<usize>, // limits.max_headers, per request
+    pub max_header_bytes: Option<usize>, // limits.max_header_bytes, per request
+    pub worker_threads: Option<usize>, // limits.worker_threads
+    pub history_capacity: Option<usize>, // limits.history_capacity
+    pub kv_capacity: Option<usize>, // limits.kv_capacity
+    pub topic_retention: Option<usize>, // limits.topic_retention
+    pub read_timeout: Option<Duration>, // timeouts.read, in seconds
+    pub write_timeout: Option<Duration>, // timeouts.write, in seconds
+    pub close_timeout: Option<Duration>, // timeouts.close, in seconds
+    pub heartbeat_interval: Option<Duration>, // timeouts.heartbeat, in seconds
+    pub tls_cert: Option<PathBuf>, // tls.cert, certificate chain in PEM
+    pub tls_key: Option<PathBuf>, // tls.key, private key in PEM
+}
+
+// A setting's value, from either source
+enum Value<'a> {
+    Toml(&'a Item), // Typed value from the file
+    Env(&'a str),   // Text of an environment variable
+}
+
+impl Config {
+    /// Reads the settings from the TOML file at `path`, then applies the environment
+    /// variable overrides
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, ConfigError> {
+        let path = path.as_ref();
+        let contents = fs::read_to_string(path).map_err(|source| ConfigError::Read {
+            path: path.to_path_buf(),
+            source,
+        })?;
+        let mut config = Config::from_toml(&contents)?;
+        config.apply_env()?;
+        Ok(config)
+    }
+
+    /// Parses the settings from TOML text, without looking at the environment
+    pub fn from_toml(contents: &str) -> Result<Self, ConfigError> {
+        let document: DocumentMut =
+            contents
+                .parse()
+                .map_err(|e: toml_edit::TomlError| ConfigError::Parse {
+                    message: e.to_string(),
+                })?;
+        let mut config = Config::default();
+        for (key, item) in document.iter() {
+            if !is_section(key) {
+                config.set(key, Value::Toml(item))?;
+                continue;
+            }
+            let table = item
+                .as_table_like()
+                .ok_or_else(|| invalid(key, "a table"))?;
+            for (name, item) in table.iter() {
+                config.set(&format!("{}.{}", key, name), Value::Toml(item))?;
+            }
+        }
+        Ok(config)
+    }
+
+    /// Overrides settings with the environment variables named after them, e.g.
+    /// EMBEDDED_TASK_ADDRESS or EMBEDDED_TASK_LIMITS_MAX_CONNECTIONS. Unrelated variables
+    /// starting with the prefix are rejected, so typos do not go unnoticed.
+    pub fn apply_env(&mut self) -> Result<(), ConfigError> {
+        self.apply_vars(env::vars())
+    }
+
+    /// Overrides settings with the variables named after them, as `apply_env` does
+    pub fn apply_vars(
+        &mut self,
+        vars: impl IntoIterator<Item = (String, String)>,
+    ) -> Result<(), ConfigError> {
+        for (name, value) in vars {
+            let Some(setting) = name.strip_prefix(ENV_PREFIX) else {
+                continue;
+            };
+            let key = KEYS
+                .iter()
+                .find(|key| key.replace('.', "_").eq_ignore_ascii_case(setting))
+                .ok_or(ConfigError::UnknownSetting { key: name.clone() })?;
+            self.set(key, Value::Env(&value))?;
+        }
+        Ok(())
+    }
+
+    /// Returns the server configuration these settings describe
+    pub fn server_config(&self) -> ServerConfig {
+        let defaults = ServerConfig::default();
+        ServerConfig {
+            admin_token: self.admin_token.clone(),
+            max_connections: self.max_connections,
+            max_message_size: self.max_message_size.unwrap_or(defaults.max_message_size),
+            header_limits: HeaderLimits {
+                max_count: self.max_headers.unwrap_or(defaults.header_limits.max_count),
+                max_bytes: self
+                    .max_header_bytes
+                    .unwrap_or(defaults.header_limits.max_bytes),
+            },
+            worker_threads: self.worker_threads,
+            history_capacity: self.history_capacity.unwrap_or(defaults.history_capacity),
+            kv_capacity: self.kv_capacity.unwrap_or(defaults.kv_capacity),
+            topic_retention: self.topic_retention.unwrap_or(defaults.topic_retention),
+            read_timeout: self.read_timeout,
+            write_timeout: self.write_timeout,
+            close_timeout: self.close_timeout.unwrap_or(defaults.close_timeout),
+            heartbeat_interval: self.heartbeat_interval,
+            ..defaults
+        }
+    }
+
+    /// Applies one setting
+    fn set(&mut self, key: &str, value: Value<'_>) -> Result<(), ConfigError> {
+        match key {
+            "address" => self.address = Some(value.string(key)?),
+            "log_level" => {
+                let level = value.string(key)?;
+                self.log_level = Some(level.parse().map_err(|_| ConfigError::InvalidValue {
+                    key: key.to_string(),
+                    expected: "off, error, warn, info, debug or trace",
+                })?);
+            }
+            "admin_token" => self.admin_token = Some(value.string(key)?),
+            "limits.max_connections" => self.max_connections = Some(value.count(key)?),
+            "limits.max_message_size" => self.max_message_size = Some(value.count(key)?),
+            "limits.max_headers" => self.max_headers = Some(value.count(key)?),
+            "limits.max_header_bytes" => self.max_header_bytes = Some(value.count(key)?),
+            "limits.worker_threads" => self.worker_threads = Some(value.count(key)?),
+            "limits.history_capacity" => self.history_capacity = Some(value.count(key)?),
+            "limits.kv_capacity" => self.kv_capacity = Some(value.count(key)?),
+            "limits.topic_retention" => self.topic_retention = Some(value.count(key)?),
+            "timeouts.read" => self.read_timeout = Some(value.seconds(key)?),
+            "timeouts.write" => self.write_timeout = Some(value.seconds(key)?),
+            "timeouts.close" => self.close_timeout = Some(value.seconds(key)?),
+            "timeouts.heartbeat" => self.heartbeat_interval = Some(value.seconds(key)?),
+            "tls.cert" => self.tls_cert = Some(value.string(key)?.into()),
+            "tls.key" => self.tls_key = Some(value.string(key)?.into()),
+            _ => {
+                return Err(ConfigError::UnknownSetting {
+                    key: key.to_string(),
+                })
+            }
+        }
+        Ok(())
+    }
+}
+
+// Names of every setting, as written in the file
+const KEYS: [&str; 17] = [
+    "address",
+    "log_level",
+    "admin_token",
+    "limits.max_connections",
+    "limits.max_message_size",
+    "limits.max_headers",
+    "limits.max_header_bytes",
+    "limits.worker_threads",
+    "limits.history_capacity",
+    "limits.kv_capacity",
+    "limits.topic_retention",
+    "timeouts.read",
+    "timeouts.write",
+    "timeouts.close",
+    "timeouts.heartbeat",
+    "tls.cert",
+    "tls.key",
+];
+
+/// Returns true if `key` names a table of settings rather than a setting
+fn is_section(key: &str) -> bool {
+    KEYS.iter().any(|setting| {
+        setting
+            .split_once('.')
+            .is_some_and(|(table, _)| table == key)
+    })
+}
+
+impl Value<'_> {
+    /// Returns the value as text
+    fn string(&self, key: &str) -> Result<String, ConfigError> {
+        match self {
+            Value::Toml(item) => item.as_str().map(str::to_string),
+            Value::Env(text) => Some(text.to_string()),
+        }
+        .ok_or_else(|| invalid(key, "a string"))
+    }
+
+    /// Returns the value as a non-negative number
+    fn count(&self, key: &str) -> Result<usize, ConfigError> {
+        match self {
+            Value::Toml(item) => item.as_integer().and_then(|n| usize::try_from(n).ok()),
+            Value::Env(text) => text.trim().parse().ok(),
+        }
+        .ok_or_else(|| invalid(key, "a non-negative integer"))
+    }
+
+    /// Returns the value as a duration given in seconds, whole or fractional
+    fn seconds(&self, key: &str) -> Result<Duration, ConfigError> {
+        match self {
+            Value::Toml(item) => item
+                .as_float()
+                .or_else(|| item.as_integer().map(|n| n as f64)),
+            Value::Env(text) => text.trim().parse().ok(),
+        }
+        .and_then(|seconds| Duration::try_from_secs_f64(seconds).ok())
+        .ok_or_else(|| invalid(key, "a non-negative number of seconds"))
+    }
+}
+
+/// Error for a value of the wrong type or range
+fn invalid(key: &str, expected: &'static str) -> ConfigError {
+    ConfigError::InvalidValue {
+        key: key.to_string(),
+        expected,
+    }
+}
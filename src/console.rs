@@ -0,0 +1,72 @@
+// Importing necessary modules and crates
+use crate::metrics::Metrics;
+use crate::pool::ThreadOptions;
+use crate::session::Session;
+use std::{
+    fmt::Write as _,
+    io::{self, Write},
+    sync::Arc,
+    thread::{self, JoinHandle},
+    time::Duration,
+};
+
+// Clears the terminal and moves the cursor to the top left corner
+const CLEAR_SCREEN: &str = "\x1b[2J\x1b[H";
+
+/// Redraws the frame returned by `frame` on `writer` every `interval`, until `frame` returns
+/// `None` or writing fails. Runs on a "console" thread spawned as `threads` says.
+pub fn spawn<F, W>(
+    mut frame: F,
+    mut writer: W,
+    interval: Duration,
+    threads: &ThreadOptions,
+) -> io::Result<JoinHandle<io::Result<()>>>
+where
+    F: FnMut() -> Option<String> + Send + 'static,
+    W: Write + Send + 'static,
+{
+    threads.spawn("console", move || {
+        while let Some(frame) = frame() {
+            writer.write_all(CLEAR_SCREEN.as_bytes())?;
+            writer.write_all(frame.as_bytes())?;
+            writer.flush()?;
+            thread::sleep(interval);
+        }
+        Ok(())
+    })
+}
+
+/// Renders a table of the given sessions, oldest connection first
+pub fn render(mut sessions: Vec<Arc<Session>>, metrics: &Metrics) -> String {
+    sessions.sort_by_key(|session| session.id());
+
+    let mut table = format!(
+        "{} connected, {} accepted, {} abandoned\n\n",
+        sessions.len(),
+        metrics.connections_accepted(),
+        metrics.connections_abandoned()
+    );
+    let _ = writeln!(
+        table,
+        "{:>6}  {:<22} {:>8} {:>9} {:>8} {:>7} {:>10} {:>10}  LAST",
+        "ID", "ADDRESS", "AGE", "MESSAGES", "RATE/S", "ERRORS", "IN", "OUT"
+    );
+    for session in sessions {
+        let age = session.connected_at().elapsed();
+        let rate = session.messages() as f64 / age.as_secs_f64().max(1.0);
+        let _ = writeln!(
+            table,
+            "{:>6}  {:<22} {:>7}s {:>9} {:>8.1} {:>7} {:>10} {:>10}  {}",
+            session.id(),
+            session.addr().to_string(),
+            age.as_secs(),
+            session.messages(),
+            rate,
+            session.errors(),
+            session.bytes_received(),
+            session.bytes_sent(),
+            session.last_message().unwrap_or("-")
+        );
+    }
+    table
+}
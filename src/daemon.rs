@@ -0,0 +1,139 @@
+// Importing necessary modules and crates
+use log::{info, warn};
+use std::{
+    fs::{self, OpenOptions},
+    io::{self, ErrorKind, Write},
+    path::{Path, PathBuf},
+    process,
+};
+
+// File holding the id of the running server, removed again when dropped
+#[derive(Debug)]
+pub struct PidFile {
+    path: PathBuf, // Where the id was written
+}
+
+impl PidFile {
+    /// Writes the id of this process to `path`. Fails with `AlreadyExists` while the process
+    /// named by an existing file is still running, a file left behind by a dead one is
+    /// replaced.
+    pub fn create(path: impl AsRef<Path>) -> io::Result<Self> {
+        let path = path.as_ref();
+        let mut file = match OpenOptions::new().write(true).create_new(true).open(path) {
+            Ok(file) => file,
+            Err(e) if e.kind() == ErrorKind::AlreadyExists => {
+                if let Some(pid) = read_pid(path)?.filter(|&pid| is_running(pid)) {
+                    return Err(io::Error::new(
+                        ErrorKind::AlreadyExists,
+                        format!("{} names running process {}", path.display(), pid),
+                    ));
+                }
+                warn!("Replacing stale pid file {}", path.display());
+                OpenOptions::new().write(true).truncate(true).open(path)?
+            }
+            Err(e) => return Err(e),
+        };
+        writeln!(file, "{}", process::id())?;
+        file.sync_all()?;
+
+        info!("Wrote pid file {}", path.display());
+        Ok(PidFile {
+            path: path.to_path_buf(),
+        })
+    }
+
+    /// Returns where the id was written
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+}
+
+impl Drop for PidFile {
+    /// Removes the file, unless another process has taken it over
+    fn drop(&mut self) {
+        if let Ok(Some(pid)) = read_pid(&self.path) {
+            if pid == process::id() {
+                let _ = fs::remove_file(&self.path);
+            }
+        }
+    }
+}
+
+/// Returns the id stored in the pid file at `path`, `None` if it holds none
+fn read_pid(path: &Path) -> io::Result<Option<u32>> {
+    let contents = fs::read_to_string(path)?;
+    Ok(contents.trim().parse().ok().filter(|&pid| pid > 0))
+}
+
+/// Returns true if a process with id `pid` exists
+#[cfg(unix)]
+fn is_running(pid: u32) -> bool {
+    let Ok(pid) = libc::pid_t::try_from(pid) else {
+        return false;
+    };
+    // SAFETY: signal 0 only checks whether the process exists
+    if unsafe { libc::kill(pid, 0) } == 0 {
+        return true;
+    }
+    // The process exists but belongs to someone else
+    io::Error::last_os_error().raw_os_error() == Some(libc::EPERM)
+}
+
+/// Returns true if a process with id `pid` exists
+#[cfg(not(unix))]
+fn is_running(_pid: u32) -> bool {
+    // Without a way to check, an existing file is assumed to be in use
+    true
+}
+
+/// Detaches the process from its terminal and runs it in the background: the calling
+/// process exits, a grandchild in a new session continues with `/` as working directory and
+/// stdin, stdout and stderr on `/dev/null`. Has to be called before any thread is spawned,
+/// only the calling thread survives.
+#[cfg(unix)]
+pub fn detach() -> io::Result<()> {
+    use std::os::unix::io::AsRawFd;
+
+    // The first child leads a new session, without a controlling terminal
+    fork_and_exit_parent()?;
+    // SAFETY: plain system call without pointers
+    if unsafe { libc::setsid() } < 0 {
+        return Err(io::Error::last_os_error());
+    }
+    // The grandchild is no session leader, so it can never acquire a terminal again
+    fork_and_exit_parent()?;
+
+    std::env::set_current_dir("/")?;
+    let null = OpenOptions::new()
+        .read(true)
+        .write(true)
+        .open("/dev/null")?;
+    for fd in [libc::STDIN_FILENO, libc::STDOUT_FILENO, libc::STDERR_FILENO] {
+        // SAFETY: both descriptors are open for as long as the call runs
+        if unsafe { libc::dup2(null.as_raw_fd(), fd) } < 0 {
+            return Err(io::Error::last_os_error());
+        }
+    }
+    Ok(())
+}
+
+/// Detaches the process from its terminal and runs it in the background
+#[cfg(not(unix))]
+pub fn detach() -> io::Result<()> {
+    Err(io::Error::new(
+        ErrorKind::Unsupported,
+        "Detaching is only supported on Unix",
+    ))
+}
+
+/// Forks, the parent exits right away and the child returns
+#[cfg(unix)]
+fn fork_and_exit_parent() -> io::Result<()> {
+    // SAFETY: the child only continues on the calling thread, as `detach` documents
+    match unsafe { libc::fork() } {
+        -1 => Err(io::Error::last_os_error()),
+        0 => Ok(()),
+        // SAFETY: ends the parent without running destructors meant for the child
+        _ => unsafe { libc::_exit(0) },
+    }
+}
@@ -0,0 +1,163 @@
+// Importing necessary modules and crates
+use crate::client::{Client, ClientError, DEFAULT_TIMEOUT};
+use std::{
+    io::{self, ErrorKind},
+    net::SocketAddr,
+    time::{Duration, Instant},
+};
+
+// How endpoints are probed and chosen between
+#[derive(Debug, Clone)]
+pub struct EndpointOptions {
+    pub probe_interval: Duration, // Age after which round trip times are measured again
+    pub switch_margin: f64, // How much faster another endpoint must be to be preferred, 0.2 is 20%
+    pub timeout: Duration,  // How long connecting to and pinging an endpoint may take
+}
+
+impl Default for EndpointOptions {
+    fn default() -> Self {
+        EndpointOptions {
+            probe_interval: Duration::from_secs(30),
+            switch_margin: 0.2,
+            timeout: DEFAULT_TIMEOUT,
+        }
+    }
+}
+
+// An endpoint and how it answered the last probe
+#[derive(Debug, Clone)]
+struct Endpoint {
+    addr: SocketAddr,      // Address of the server
+    rtt: Option<Duration>, // Round trip time of the last ping, None if unhealthy or unprobed
+}
+
+// Servers a client may connect to, preferring the one with the lowest round trip time.
+// The preference only changes when another endpoint is faster by the switch margin, or the
+// preferred one fails, so similar endpoints do not flap.
+#[derive(Debug, Clone)]
+pub struct Endpoints {
+    endpoints: Vec<Endpoint>,   // Known endpoints, in the order given
+    preferred: Option<usize>,   // Index of the endpoint new connections go to first
+    probed_at: Option<Instant>, // When the endpoints were last probed
+    options: EndpointOptions,   // How endpoints are probed and chosen between
+}
+
+impl Endpoints {
+    /// Creates a set of endpoints, none of them probed yet
+    pub fn new(addrs: impl IntoIterator<Item = SocketAddr>) -> Self {
+        Endpoints::with_options(addrs, EndpointOptions::default())
+    }
+
+    /// Creates a set of endpoints, probed and chosen between as `options` say
+    pub fn with_options(
+        addrs: impl IntoIterator<Item = SocketAddr>,
+        options: EndpointOptions,
+    ) -> Self {
+        Endpoints {
+            endpoints: addrs
+                .into_iter()
+                .map(|addr| Endpoint { addr, rtt: None })
+                .collect(),
+            preferred: None,
+            probed_at: None,
+            options,
+        }
+    }
+
+    /// Returns the endpoint new connections go to first, once one answered a probe
+    pub fn preferred(&self) -> Option<SocketAddr> {
+        self.preferred.map(|index| self.endpoints[index].addr)
+    }
+
+    /// Returns the round trip time `addr` answered its last probe with, `None` if it did
+    /// not or is unknown
+    pub fn rtt(&self, addr: SocketAddr) -> Option<Duration> {
+        self.endpoints
+            .iter()
+            .find(|endpoint| endpoint.addr == addr)
+            .and_then(|endpoint| endpoint.rtt)
+    }
+
+    /// Pings every endpoint on a connection of its own and chooses between them again
+    pub fn probe(&mut self) {
+        self.probed_at = Some(Instant::now());
+        for index in 0..self.endpoints.len() {
+            let addr = self.endpoints[index].addr;
+            let rtt = Client::connect_timeout(addr, self.options.timeout)
+                .and_then(|mut client| client.ping())
+                .ok();
+            self.record(addr, rtt);
+        }
+    }
+
+    /// Records the round trip time measured for `addr`, `None` if it failed to answer, and
+    /// chooses between the endpoints again
+    pub fn record(&mut self, addr: SocketAddr, rtt: Option<Duration>) {
+        let Some(index) = self.endpoints.iter().position(|e| e.addr == addr) else {
+            return;
+        };
+        self.endpoints[index].rtt = rtt;
+
+        let Some((fastest, fastest_rtt)) = self.fastest() else {
+            self.preferred = None;
+            return;
+        };
+        let current_rtt = self.preferred.and_then(|index| self.endpoints[index].rtt);
+        match current_rtt {
+            // Stay unless the fastest one is clearly faster
+            Some(current_rtt)
+                if fastest_rtt.mul_f64(1.0 + self.options.switch_margin) >= current_rtt => {}
+            _ => self.preferred = Some(fastest),
+        }
+    }
+
+    /// Connects to the preferred endpoint, falling back to the others from the fastest to
+    /// the unprobed ones. Endpoints are probed first if they never were or the probe
+    /// interval is up.
+    pub fn connect(&mut self) -> Result<Client, ClientError> {
+        if self
+            .probed_at
+            .is_none_or(|probed_at| probed_at.elapsed() >= self.options.probe_interval)
+        {
+            self.probe();
+        }
+
+        let mut order: Vec<usize> = (0..self.endpoints.len()).collect();
+        order.sort_by_key(|&index| {
+            let endpoint = &self.endpoints[index];
+            (
+                Some(index) != self.preferred,
+                endpoint.rtt.is_none(),
+                endpoint.rtt,
+            )
+        });
+
+        let mut last_error = None;
+        for index in order {
+            let addr = self.endpoints[index].addr;
+            match Client::connect_timeout(addr, self.options.timeout) {
+                Ok(client) => return Ok(client),
+                Err(e) => {
+                    // Unhealthy until it answers a probe again
+                    self.record(addr, None);
+                    last_error = Some(e);
+                }
+            }
+        }
+        Err(last_error.unwrap_or_else(|| {
+            ClientError::ConnectFailed(io::Error::new(
+                ErrorKind::InvalidInput,
+                "No endpoints to connect to",
+            ))
+        }))
+    }
+
+    /// Returns the index and round trip time of the fastest healthy endpoint
+    fn fastest(&self) -> Option<(usize, Duration)> {
+        self.endpoints
+            .iter()
+            .enumerate()
+            .filter_map(|(index, endpoint)| endpoint.rtt.map(|rtt| (index, rtt)))
+            .min_by_key(|&(_, rtt)| rtt)
+    }
+}
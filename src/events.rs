@@ -0,0 +1,212 @@
+// Importing necessary modules and crates
+use crate::resources::ResourceUsage;
+use log::error;
+use std::{
+    fmt, io,
+    net::SocketAddr,
+    panic::{self, AssertUnwindSafe},
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        mpsc::{self, Receiver, Sender},
+        Arc, Mutex,
+    },
+    time::{Duration, SystemTime},
+};
+
+// Why a client connection was terminated
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum CloseReason {
+    ClientEof,         // The client closed its end of the connection
+    ClientGoodbye,     // The client announced it was leaving, then closed its end
+    ReadError,         // Reading from the socket failed
+    WriteError,        // Writing a response to the socket failed
+    ProtocolViolation, // The client sent data the server refuses to process
+    IdleTimeout,       // The client stayed silent for too long
+    Kicked,            // The connection was dropped on request of the server owner
+    Shutdown,          // The server is shutting down
+    ResourcePressure,  // The connection was shed to relieve resource pressure
+    PeerUnresponsive,  // Keepalive probes or pings of an idle connection went unanswered
+}
+
+impl CloseReason {
+    /// Every close reason, in the order used for metrics
+    pub const ALL: [CloseReason; 10] = [
+        CloseReason::ClientEof,
+        CloseReason::ClientGoodbye,
+        CloseReason::ReadError,
+        CloseReason::WriteError,
+        CloseReason::ProtocolViolation,
+        CloseReason::IdleTimeout,
+        CloseReason::Kicked,
+        CloseReason::Shutdown,
+        CloseReason::ResourcePressure,
+        CloseReason::PeerUnresponsive,
+    ];
+
+    /// Returns a stable, machine-friendly name for the reason
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            CloseReason::ClientEof => "client_eof",
+            CloseReason::ClientGoodbye => "client_goodbye",
+            CloseReason::ReadError => "read_error",
+            CloseReason::WriteError => "write_error",
+            CloseReason::ProtocolViolation => "protocol_violation",
+            CloseReason::IdleTimeout => "idle_timeout",
+            CloseReason::Kicked => "kicked",
+            CloseReason::Shutdown => "shutdown",
+            CloseReason::ResourcePressure => "resource_pressure",
+            CloseReason::PeerUnresponsive => "peer_unresponsive",
+        }
+    }
+
+    /// Returns true when the server decided to close the connection, the client can then
+    /// still be told about it
+    pub fn is_server_initiated(&self) -> bool {
+        matches!(
+            self,
+            CloseReason::ProtocolViolation
+                | CloseReason::IdleTimeout
+                | CloseReason::Kicked
+                | CloseReason::Shutdown
+                | CloseReason::ResourcePressure
+        )
+    }
+}
+
+impl fmt::Display for CloseReason {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+// Events published by the server while it is running
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ServerEvent {
+    Connected {
+        addr: SocketAddr,
+    },
+    Disconnected {
+        addr: SocketAddr,
+        reason: CloseReason,
+    },
+    ResourcePressure {
+        usage: ResourceUsage, // Usage that crossed a threshold, new connections wait meanwhile
+    },
+    ResourcePressureRelieved {
+        usage: ResourceUsage, // Usage back within the thresholds
+    },
+    AcceptLoopFailed {
+        errors: u32,         // Number of consecutive accept errors
+        kind: io::ErrorKind, // Kind of the last error
+    },
+    MessageHandled {
+        message: HandledMessage, // The message and how handling it went
+        timestamp: SystemTime,   // When handling it finished, by the server clock
+    },
+}
+
+// Fan-out of server events to any number of subscribers. MessageHandled events only reach
+// the subscribers asking for them, there is one for every message.
+#[derive(Default)]
+pub struct EventBus {
+    subscribers: Mutex<Vec<(Sender<ServerEvent>, bool)>>, // Sender of every subscriber, and whether it wants message events
+    message_subscribers: AtomicUsize, // Subscribers wanting message events, to skip the lock when there are none
+}
+
+impl EventBus {
+    /// Registers a new subscriber and returns the receiving end of its channel
+    pub fn subscribe(&self) -> Receiver<ServerEvent> {
+        self.subscribe_with(false)
+    }
+
+    /// Registers a new subscriber that also receives a MessageHandled event for every
+    /// message, and returns the receiving end of its channel
+    pub fn subscribe_with_messages(&self) -> Receiver<ServerEvent> {
+        self.subscribe_with(true)
+    }
+
+    /// Registers a new subscriber, with or without message events
+    fn subscribe_with(&self, messages: bool) -> Receiver<ServerEvent> {
+        let (sender, receiver) = mpsc::channel();
+        let mut subscribers = self.subscribers.lock().unwrap();
+        subscribers.push((sender, messages));
+        if messages {
+            self.message_subscribers.fetch_add(1, Ordering::SeqCst);
+        }
+        receiver
+    }
+
+    /// Returns true if a subscriber wants message events
+    pub fn wants_messages(&self) -> bool {
+        self.message_subscribers.load(Ordering::SeqCst) > 0
+    }
+
+    /// Delivers an event to every subscriber wanting it, forgetting the ones that went away
+    pub fn emit(&self, event: ServerEvent) {
+        let is_message = matches!(event, ServerEvent::MessageHandled { .. });
+        let mut subscribers = self.subscribers.lock().unwrap();
+        subscribers.retain(|(subscriber, messages)| {
+            if is_message && !messages {
+                return true;
+            }
+            let delivered = subscriber.send(event.clone()).is_ok();
+            if !delivered && *messages {
+                self.message_subscribers.fetch_sub(1, Ordering::SeqCst);
+            }
+            delivered
+        });
+    }
+}
+
+// A message the server handled on a client connection
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct HandledMessage {
+    pub addr: SocketAddr,           // Address of the client that sent it
+    pub session_id: u64,            // Session of the connection it arrived on
+    pub message_type: &'static str, // Type of the message, as named in the statistics
+    pub size: usize,                // Bytes it took on the wire, frame header included
+    pub elapsed: Duration,          // Time spent handling it, answering included
+    pub failed: bool,               // Whether it was answered with an error
+}
+
+// Callbacks run by the server on the threads serving the connections, as things happen.
+// They should return quickly, the client waits meanwhile; a panicking callback is logged
+// and does not affect the connection.
+pub trait ConnectionObserver: fmt::Debug + Send + Sync {
+    /// Called once a client connected, before any of its messages are handled
+    fn on_connect(&self, _addr: SocketAddr) {}
+
+    /// Called once the connection of a client was torn down
+    fn on_disconnect(&self, _addr: SocketAddr, _reason: CloseReason) {}
+
+    /// Called after every message a client sent was handled
+    fn on_message(&self, _message: &HandledMessage) {}
+}
+
+// Observers registered on a server, run in the order they were added
+#[derive(Debug, Clone, Default)]
+pub struct Observers {
+    observers: Vec<Arc<dyn ConnectionObserver>>, // Registered observers
+}
+
+impl Observers {
+    /// Adds an observer, run after the ones added before
+    pub fn add(&mut self, observer: Arc<dyn ConnectionObserver>) {
+        self.observers.push(observer);
+    }
+
+    /// Returns true if no observer was added
+    pub fn is_empty(&self) -> bool {
+        self.observers.is_empty()
+    }
+
+    /// Runs `callback` on every observer, containing the panics of each
+    pub fn notify(&self, callback: impl Fn(&dyn ConnectionObserver)) {
+        for observer in &self.observers {
+            let result = panic::catch_unwind(AssertUnwindSafe(|| callback(observer.as_ref())));
+            if result.is_err() {
+                error!("Connection observer {:?} panicked", observer);
+            }
+        }
+    }
+}
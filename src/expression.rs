@@ -0,0 +1,208 @@
+// Importing necessary modules and crates
+use std::{error::Error, fmt};
+
+// Longest expression accepted, in bytes
+pub const MAX_EXPRESSION_LEN: usize = 4096;
+
+// Deepest nesting of parentheses and signs accepted, so evaluation cannot overflow the stack
+pub const MAX_DEPTH: usize = 64;
+
+// Why an expression could not be evaluated
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ExpressionError {
+    Unexpected { position: usize, found: char }, // A character that does not belong there
+    UnexpectedEnd,                               // The expression stops in the middle
+    InvalidNumber { position: usize },           // A number such as "1.2.3"
+    DivisionByZero { position: usize },          // A division whose divisor evaluates to 0
+    NotFinite,                                   // The result is out of range
+    TooLong { length: usize },                   // The expression exceeds MAX_EXPRESSION_LEN
+    TooDeep { position: usize },                 // Nesting exceeds MAX_DEPTH
+}
+
+impl fmt::Display for ExpressionError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ExpressionError::Unexpected { position, found } => {
+                write!(f, "Unexpected '{}' at position {}", found, position)
+            }
+            ExpressionError::UnexpectedEnd => write!(f, "Unexpected end of expression"),
+            ExpressionError::InvalidNumber { position } => {
+                write!(f, "Invalid number at position {}", position)
+            }
+            ExpressionError::DivisionByZero { position } => {
+                write!(f, "Division by zero at position {}", position)
+            }
+            ExpressionError::NotFinite => write!(f, "Result is out of range"),
+            ExpressionError::TooLong { length } => write!(
+                f,
+                "Expression of {} bytes exceeds the limit of {}",
+                length, MAX_EXPRESSION_LEN
+            ),
+            ExpressionError::TooDeep { position } => write!(
+                f,
+                "Nesting at position {} exceeds the limit of {}",
+                position, MAX_DEPTH
+            ),
+        }
+    }
+}
+
+impl Error for ExpressionError {}
+
+/// Evaluates an arithmetic expression of decimal numbers, `+`, `-`, `*`, `/` and
+/// parentheses, with the usual precedence. Positions in errors count bytes from 0.
+pub fn evaluate(expression: &str) -> Result<f64, ExpressionError> {
+    if expression.len() > MAX_EXPRESSION_LEN {
+        return Err(ExpressionError::TooLong {
+            length: expression.len(),
+        });
+    }
+
+    let mut parser = Parser {
+        input: expression.as_bytes(),
+        position: 0,
+        depth: 0,
+    };
+    let result = parser.sum()?;
+    parser.skip_whitespace();
+    if let Some(found) = parser.peek() {
+        return Err(parser.unexpected(found));
+    }
+    if !result.is_finite() {
+        return Err(ExpressionError::NotFinite);
+    }
+    Ok(result)
+}
+
+// Recursive descent over the bytes of an expression
+struct Parser<'a> {
+    input: &'a [u8], // The expression
+    position: usize, // Next byte to look at
+    depth: usize,    // Nesting of the term being parsed
+}
+
+impl Parser<'_> {
+    /// sum := product (('+' | '-') product)*
+    fn sum(&mut self) -> Result<f64, ExpressionError> {
+        let mut value = self.product()?;
+        loop {
+            self.skip_whitespace();
+            match self.peek() {
+                Some(b'+') => {
+                    self.position += 1;
+                    value += self.product()?;
+                }
+                Some(b'-') => {
+                    self.position += 1;
+                    value -= self.product()?;
+                }
+                _ => return Ok(value),
+            }
+        }
+    }
+
+    /// product := factor (('*' | '/') factor)*
+    fn product(&mut self) -> Result<f64, ExpressionError> {
+        let mut value = self.factor()?;
+        loop {
+            self.skip_whitespace();
+            match self.peek() {
+                Some(b'*') => {
+                    self.position += 1;
+                    value *= self.factor()?;
+                }
+                Some(b'/') => {
+                    self.position += 1;
+                    self.skip_whitespace();
+                    let position = self.position;
+                    let divisor = self.factor()?;
+                    if divisor == 0.0 {
+                        return Err(ExpressionError::DivisionByZero { position });
+                    }
+                    value /= divisor;
+                }
+                _ => return Ok(value),
+            }
+        }
+    }
+
+    /// factor := ('+' | '-') factor | '(' sum ')' | number
+    fn factor(&mut self) -> Result<f64, ExpressionError> {
+        self.skip_whitespace();
+        let found = self.peek().ok_or(ExpressionError::UnexpectedEnd)?;
+        match found {
+            b'+' | b'-' => {
+                self.position += 1;
+                let value = self.nested(Self::factor)?;
+                Ok(if found == b'-' { -value } else { value })
+            }
+            b'(' => {
+                self.position += 1;
+                let value = self.nested(Self::sum)?;
+                self.skip_whitespace();
+                match self.peek() {
+                    Some(b')') => {
+                        self.position += 1;
+                        Ok(value)
+                    }
+                    Some(found) => Err(self.unexpected(found)),
+                    None => Err(ExpressionError::UnexpectedEnd),
+                }
+            }
+            b'0'..=b'9' | b'.' => self.number(),
+            _ => Err(self.unexpected(found)),
+        }
+    }
+
+    /// number := [0-9.]+
+    fn number(&mut self) -> Result<f64, ExpressionError> {
+        let start = self.position;
+        while matches!(self.peek(), Some(b'0'..=b'9' | b'.')) {
+            self.position += 1;
+        }
+        // Only ASCII digits and dots were consumed, so this is valid UTF-8
+        let digits = std::str::from_utf8(&self.input[start..self.position]).unwrap_or_default();
+        digits
+            .parse()
+            .map_err(|_| ExpressionError::InvalidNumber { position: start })
+    }
+
+    /// Parses a nested term, one level deeper
+    fn nested(
+        &mut self,
+        parse: fn(&mut Self) -> Result<f64, ExpressionError>,
+    ) -> Result<f64, ExpressionError> {
+        if self.depth == MAX_DEPTH {
+            return Err(ExpressionError::TooDeep {
+                position: self.position,
+            });
+        }
+        self.depth += 1;
+        let value = parse(self);
+        self.depth -= 1;
+        value
+    }
+
+    fn peek(&self) -> Option<u8> {
+        self.input.get(self.position).copied()
+    }
+
+    fn skip_whitespace(&mut self) {
+        while self.peek().is_some_and(|byte| byte.is_ascii_whitespace()) {
+            self.position += 1;
+        }
+    }
+
+    /// Describes the unexpected byte `found` at the current position, as the character it
+    /// starts
+    fn unexpected(&self, found: u8) -> ExpressionError {
+        let found = std::str::from_utf8(&self.input[self.position..])
+            .ok()
+            .and_then(|rest| rest.chars().next())
+            .unwrap_or(char::from(found));
+        ExpressionError::Unexpected {
+            position: self.position,
+            found,
+        }
+    }
+}
@@ -0,0 +1,279 @@
+// Importing necessary modules and crates
+use crate::handler::{Handler, RequestContext};
+use crate::message::client_message::Message as ClientMessageType;
+use crate::message::server_message::Message as ServerMessageType;
+use crate::message::*;
+use std::{
+    any::{self, Any, TypeId},
+    collections::HashMap,
+    fmt,
+    marker::PhantomData,
+    net::SocketAddr,
+    ops::Deref,
+    sync::Arc,
+};
+
+// Result of a handler function, rejected requests are answered with the ErrorResponse
+pub type Result<T, E = ErrorResponse> = std::result::Result<T, E>;
+
+// Request a handler function takes as its first parameter
+pub trait FromMessage: Sized {
+    /// Returns the request, `None` if `message` is of another type
+    fn from_message(message: ClientMessageType) -> Option<Self>;
+}
+
+// Further parameters of a handler function, built from the request context
+pub trait FromContext: Sized {
+    /// Builds the parameter, or the error the request is rejected with
+    fn from_context(context: &RequestContext<'_>) -> Result<Self>;
+}
+
+// What a handler function returns
+pub trait IntoResponse {
+    /// Returns the response to send, if there is one
+    fn into_response(self) -> Option<ServerMessageType>;
+}
+
+// Values handed to handler functions through `State`, one per type
+#[derive(Clone, Default)]
+pub struct StateMap {
+    values: HashMap<TypeId, Arc<dyn Any + Send + Sync>>, // Values, by their type
+}
+
+impl fmt::Debug for StateMap {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("StateMap")
+            .field("values", &self.values.len())
+            .finish()
+    }
+}
+
+impl StateMap {
+    /// Creates a map without any values
+    pub fn new() -> Self {
+        StateMap::default()
+    }
+
+    /// Stores `value`, replacing any previous value of its type
+    pub fn insert<T: Send + Sync + 'static>(&mut self, value: T) {
+        self.values.insert(TypeId::of::<T>(), Arc::new(value));
+    }
+
+    /// Returns the value of type `T`, if one was stored
+    pub fn get<T: Send + Sync + 'static>(&self) -> Option<Arc<T>> {
+        let value = Arc::clone(self.values.get(&TypeId::of::<T>())?);
+        value.downcast().ok()
+    }
+}
+
+// Shared state of type `T`, as given to ServerBuilder::state
+#[derive(Debug)]
+pub struct State<T>(pub Arc<T>);
+
+impl<T> Deref for State<T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        &self.0
+    }
+}
+
+impl<T: Send + Sync + 'static> FromContext for State<T> {
+    fn from_context(context: &RequestContext<'_>) -> Result<Self> {
+        context.state.get().map(State).ok_or_else(|| ErrorResponse {
+            code: ErrorCode::HandlerFailed as i32,
+            description: format!("No state of type {}", any::type_name::<T>()),
+        })
+    }
+}
+
+// Connection a request arrived on
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Peer {
+    pub session_id: u64,  // Id the server assigned to the connection
+    pub addr: SocketAddr, // Address of the client
+}
+
+impl FromContext for Peer {
+    fn from_context(context: &RequestContext<'_>) -> Result<Self> {
+        Ok(Peer {
+            session_id: context.session.id(),
+            addr: context.session.addr(),
+        })
+    }
+}
+
+// Headers the request carried
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Headers(pub HashMap<String, String>);
+
+impl FromContext for Headers {
+    fn from_context(context: &RequestContext<'_>) -> Result<Self> {
+        Ok(Headers(context.headers.clone()))
+    }
+}
+
+impl FromMessage for ClientMessageType {
+    fn from_message(message: ClientMessageType) -> Option<Self> {
+        Some(message)
+    }
+}
+
+// Every request message extracts itself from its variant
+macro_rules! from_message {
+    ($($message:ident),* $(,)?) => {
+        $(
+            impl FromMessage for $message {
+                fn from_message(message: ClientMessageType) -> Option<Self> {
+                    match message {
+                        ClientMessageType::$message(message) => Some(message),
+                        _ => None,
+                    }
+                }
+            }
+        )*
+    };
+}
+
+from_message!(
+    EchoMessage,
+    AddRequest,
+    SubtractRequest,
+    MultiplyRequest,
+    DivideRequest,
+    CalculateRequest,
+    HistoryRequest,
+    TopMessagesRequest,
+    ClientGoodbye,
+    DotProductRequest,
+    MatrixMultiplyRequest,
+    SetLogLevelRequest,
+    RecentErrorsRequest,
+    RunHistoryRequest,
+    ConnectionChurnRequest,
+    SubscribeRequest,
+    UnsubscribeRequest,
+    PublishRequest,
+    ServerInfoRequest,
+    PingRequest,
+    PongResponse,
+    SetRequest,
+    GetRequest,
+    DeleteRequest,
+);
+
+impl IntoResponse for ServerMessageType {
+    fn into_response(self) -> Option<ServerMessageType> {
+        Some(self)
+    }
+}
+
+impl IntoResponse for () {
+    fn into_response(self) -> Option<ServerMessageType> {
+        None
+    }
+}
+
+impl<T: IntoResponse> IntoResponse for Option<T> {
+    fn into_response(self) -> Option<ServerMessageType> {
+        self.and_then(IntoResponse::into_response)
+    }
+}
+
+impl<T: IntoResponse, E: IntoResponse> IntoResponse for Result<T, E> {
+    fn into_response(self) -> Option<ServerMessageType> {
+        match self {
+            Ok(response) => response.into_response(),
+            Err(error) => error.into_response(),
+        }
+    }
+}
+
+// Every response message wraps itself in its variant
+macro_rules! into_response {
+    ($($message:ident),* $(,)?) => {
+        $(
+            impl IntoResponse for $message {
+                fn into_response(self) -> Option<ServerMessageType> {
+                    Some(ServerMessageType::$message(self))
+                }
+            }
+        )*
+    };
+}
+
+into_response!(
+    EchoMessage,
+    AddResponse,
+    SubtractResponse,
+    MultiplyResponse,
+    DivideResponse,
+    CalculateResponse,
+    HistoryResponse,
+    TopMessagesResponse,
+    DotProductResponse,
+    MatrixMultiplyResponse,
+    SetLogLevelResponse,
+    RecentErrorsResponse,
+    RunHistoryResponse,
+    ConnectionChurnResponse,
+    ErrorResponse,
+    SubscribeResponse,
+    UnsubscribeResponse,
+    PublishResponse,
+    ServerInfoResponse,
+    PongResponse,
+    SetResponse,
+    GetResponse,
+    DeleteResponse,
+);
+
+// Handler calling a function whose parameters are extracted from the request
+pub struct FnHandler<F, Args> {
+    function: F,                           // Function handling the requests
+    parameters: PhantomData<fn() -> Args>, // Types of its parameters
+}
+
+/// Turns a function taking a request and any number of `FromContext` parameters, up to
+/// four, into a handler, e.g. `fn(AddRequest, State<Db>, Peer) -> Result<AddResponse>`.
+/// Requests of another type than the first parameter are not answered.
+pub fn from_fn<F, Args>(function: F) -> FnHandler<F, Args> {
+    FnHandler {
+        function,
+        parameters: PhantomData,
+    }
+}
+
+macro_rules! fn_handler {
+    ($($parameter:ident),*) => {
+        impl<F, M, R, $($parameter,)*> Handler for FnHandler<F, (M, $($parameter,)*)>
+        where
+            F: Fn(M, $($parameter,)*) -> R + Send + Sync,
+            M: FromMessage,
+            R: IntoResponse,
+            $($parameter: FromContext,)*
+        {
+            #[allow(non_snake_case, unused_variables)]
+            fn handle(
+                &self,
+                request: ClientMessageType,
+                context: &RequestContext<'_>,
+            ) -> Option<ServerMessageType> {
+                let message = M::from_message(request)?;
+                $(
+                    let $parameter = match $parameter::from_context(context) {
+                        Ok(parameter) => parameter,
+                        Err(error) => return error.into_response(),
+                    };
+                )*
+                (self.function)(message, $($parameter,)*).into_response()
+            }
+        }
+    };
+}
+
+fn_handler!();
+fn_handler!(A);
+fn_handler!(A, B);
+fn_handler!(A, B, C);
+fn_handler!(A, B, C, D);
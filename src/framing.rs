@@ -0,0 +1,123 @@
+// Importing necessary modules and crates
+use std::{
+    error::Error,
+    fmt,
+    io::{self, Read, Write},
+};
+
+// Size of the big-endian length prefix in front of every message
+pub const HEADER_LEN: usize = 4;
+
+// Largest payload a frame may carry unless configured otherwise
+pub const DEFAULT_MAX_FRAME_SIZE: usize = 64 * 1024;
+
+// Why a byte stream could not be split into frames
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum FrameError {
+    TooLarge { len: usize, max: usize }, // The announced payload exceeds the limit
+}
+
+impl fmt::Display for FrameError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            FrameError::TooLarge { len, max } => {
+                write!(f, "Frame of {} bytes exceeds the limit of {}", len, max)
+            }
+        }
+    }
+}
+
+impl Error for FrameError {}
+
+impl From<FrameError> for io::Error {
+    fn from(e: FrameError) -> Self {
+        io::Error::new(io::ErrorKind::InvalidData, e)
+    }
+}
+
+/// Prefixes `payload` with its length
+pub fn encode_frame(payload: &[u8]) -> Vec<u8> {
+    let len = u32::try_from(payload.len()).expect("Payload too large for a frame");
+    let mut frame = Vec::with_capacity(HEADER_LEN + payload.len());
+    frame.extend_from_slice(&len.to_be_bytes());
+    frame.extend_from_slice(payload);
+    frame
+}
+
+/// Writes `payload` as a single frame, in one piece so that it is not split needlessly
+pub fn write_frame<W: Write>(writer: &mut W, payload: &[u8]) -> io::Result<()> {
+    writer.write_all(&encode_frame(payload))
+}
+
+/// Reads a single frame and returns its payload, blocking until it is complete
+pub fn read_frame<R: Read>(reader: &mut R, max_frame_size: usize) -> io::Result<Vec<u8>> {
+    let mut header = [0; HEADER_LEN];
+    reader.read_exact(&mut header)?;
+    let len = u32::from_be_bytes(header) as usize;
+    if len > max_frame_size {
+        return Err(FrameError::TooLarge {
+            len,
+            max: max_frame_size,
+        }
+        .into());
+    }
+
+    let mut payload = vec![0; len];
+    reader.read_exact(&mut payload)?;
+    Ok(payload)
+}
+
+// Accumulates bytes as they arrive and splits them into frames, however the transport
+// coalesced or split them
+#[derive(Debug)]
+pub struct FrameDecoder {
+    buffer: Vec<u8>,       // Bytes received but not handed out yet
+    max_frame_size: usize, // Largest payload accepted
+}
+
+impl Default for FrameDecoder {
+    fn default() -> Self {
+        FrameDecoder::new(DEFAULT_MAX_FRAME_SIZE)
+    }
+}
+
+impl FrameDecoder {
+    /// Creates a decoder rejecting payloads larger than `max_frame_size`
+    pub fn new(max_frame_size: usize) -> Self {
+        FrameDecoder {
+            buffer: Vec::new(),
+            max_frame_size,
+        }
+    }
+
+    /// Appends received bytes
+    pub fn extend(&mut self, data: &[u8]) {
+        self.buffer.extend_from_slice(data);
+    }
+
+    /// Returns the number of bytes waiting to complete a frame
+    pub fn buffered(&self) -> usize {
+        self.buffer.len()
+    }
+
+    /// Returns the payload of the next complete frame, `None` until enough bytes arrived
+    pub fn next_frame(&mut self) -> Result<Option<Vec<u8>>, FrameError> {
+        let Some(header) = self.buffer.first_chunk::<HEADER_LEN>() else {
+            return Ok(None);
+        };
+        let len = u32::from_be_bytes(*header) as usize;
+        if len > self.max_frame_size {
+            return Err(FrameError::TooLarge {
+                len,
+                max: self.max_frame_size,
+            });
+        }
+        if self.buffer.len() < HEADER_LEN + len {
+            return Ok(None);
+        }
+
+        let payload = self.buffer[HEADER_LEN..HEADER_LEN + len].to_vec();
+        self.buffer.drain(..HEADER_LEN + len);
+        Ok(Some(payload))
+    }
+}
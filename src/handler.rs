@@ -0,0 +1,349 @@
+// Importing necessary modules and crates
+use crate::expression;
+use crate::extract::StateMap;
+use crate::history::EchoHistory;
+use crate::kv::KvStore;
+use crate::message::client_message::Message as ClientMessageType;
+use crate::message::server_message::Message as ServerMessageType;
+use crate::message::{
+    AddResponse, CalculateResponse, DeleteResponse, DivideResponse, ErrorCode, ErrorResponse,
+    GetResponse, MultiplyResponse, SetResponse, SubtractResponse,
+};
+use crate::session::Session;
+use log::info;
+use std::{
+    collections::{HashMap, HashSet},
+    fmt,
+    sync::{Arc, Mutex},
+};
+
+// What a handler gets to know about the request it handles
+pub struct RequestContext<'a> {
+    pub message_type: &'static str, // Name of the message type, as used in statistics
+    pub headers: &'a HashMap<String, String>, // Headers the request carried
+    pub session: &'a Session,       // Connection the request arrived on
+    pub state: &'a StateMap,        // Values given to ServerBuilder::state
+}
+
+// Handles the requests of one ClientMessage variant
+pub trait Handler: Send + Sync {
+    /// Handles `request`, returning the response to send if there is one
+    fn handle(
+        &self,
+        request: ClientMessageType,
+        context: &RequestContext<'_>,
+    ) -> Option<ServerMessageType>;
+}
+
+impl<F> Handler for F
+where
+    F: Fn(ClientMessageType, &RequestContext<'_>) -> Option<ServerMessageType> + Send + Sync,
+{
+    fn handle(
+        &self,
+        request: ClientMessageType,
+        context: &RequestContext<'_>,
+    ) -> Option<ServerMessageType> {
+        self(request, context)
+    }
+}
+
+// Handlers by the name of the message type they handle, e.g. "add" or "echo"
+#[derive(Clone, Default)]
+pub struct Router {
+    handlers: HashMap<String, Arc<dyn Handler>>, // Registered handlers, by message type
+    cacheable: HashSet<String>,                  // Message types whose responses may be reused
+}
+
+impl fmt::Debug for Router {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_set().entries(self.handlers.keys()).finish()
+    }
+}
+
+impl Router {
+    /// Creates a router without any handlers
+    pub fn new() -> Self {
+        Router::default()
+    }
+
+    /// Routes requests of `message_type` to `handler`, replacing any previous handler
+    pub fn register(&mut self, message_type: &str, handler: impl Handler + 'static) {
+        self.cacheable.remove(message_type);
+        self.handlers
+            .insert(message_type.to_string(), Arc::new(handler));
+    }
+
+    /// Routes requests of `message_type` to `handler` like `register`, declaring that its
+    /// responses only depend on the request, so that the server may cache them
+    pub fn register_cacheable(&mut self, message_type: &str, handler: impl Handler + 'static) {
+        self.register(message_type, handler);
+        self.cacheable.insert(message_type.to_string());
+    }
+
+    /// Adds every handler of `other`, which take precedence over the ones already registered
+    pub fn merge(&mut self, other: Router) {
+        for message_type in other.handlers.keys() {
+            if other.cacheable.contains(message_type) {
+                self.cacheable.insert(message_type.clone());
+            } else {
+                self.cacheable.remove(message_type);
+            }
+        }
+        self.handlers.extend(other.handlers);
+    }
+
+    /// Returns true if the handler of `message_type` was registered as cacheable
+    pub fn is_cacheable(&self, message_type: &str) -> bool {
+        self.cacheable.contains(message_type)
+    }
+
+    /// Returns the handler of `message_type`, if one is registered
+    pub fn get(&self, message_type: &str) -> Option<&dyn Handler> {
+        self.handlers.get(message_type).map(|handler| &**handler)
+    }
+
+    /// Returns the message types that have a handler
+    pub fn message_types(&self) -> Vec<&str> {
+        let mut message_types: Vec<&str> = self.handlers.keys().map(String::as_str).collect();
+        message_types.sort_unstable();
+        message_types
+    }
+}
+
+/// Builds the response to a request that could not be handled at all
+pub fn error_response(code: ErrorCode, description: String) -> ServerMessageType {
+    ServerMessageType::ErrorResponse(ErrorResponse {
+        code: code as i32,
+        description,
+    })
+}
+
+// Built-in handler of AddRequest
+#[derive(Debug, Clone, Copy, Default)]
+pub struct AddHandler;
+
+impl Handler for AddHandler {
+    fn handle(
+        &self,
+        request: ClientMessageType,
+        _context: &RequestContext<'_>,
+    ) -> Option<ServerMessageType> {
+        let ClientMessageType::AddRequest(add_request) = request else {
+            return None;
+        };
+        // Perform the addition, a result out of range is an error rather than a wrapped value
+        match add_request.a.checked_add(add_request.b) {
+            Some(result) => Some(ServerMessageType::AddResponse(AddResponse { result })),
+            None => Some(overflow(add_request.a, "+", add_request.b)),
+        }
+    }
+}
+
+// Built-in handler of SubtractRequest
+#[derive(Debug, Clone, Copy, Default)]
+pub struct SubtractHandler;
+
+impl Handler for SubtractHandler {
+    fn handle(
+        &self,
+        request: ClientMessageType,
+        _context: &RequestContext<'_>,
+    ) -> Option<ServerMessageType> {
+        let ClientMessageType::SubtractRequest(request) = request else {
+            return None;
+        };
+        match request.a.checked_sub(request.b) {
+            Some(result) => Some(ServerMessageType::SubtractResponse(SubtractResponse {
+                result,
+            })),
+            None => Some(overflow(request.a, "-", request.b)),
+        }
+    }
+}
+
+// Built-in handler of MultiplyRequest
+#[derive(Debug, Clone, Copy, Default)]
+pub struct MultiplyHandler;
+
+impl Handler for MultiplyHandler {
+    fn handle(
+        &self,
+        request: ClientMessageType,
+        _context: &RequestContext<'_>,
+    ) -> Option<ServerMessageType> {
+        let ClientMessageType::MultiplyRequest(request) = request else {
+            return None;
+        };
+        match request.a.checked_mul(request.b) {
+            Some(result) => Some(ServerMessageType::MultiplyResponse(MultiplyResponse {
+                result,
+            })),
+            None => Some(overflow(request.a, "*", request.b)),
+        }
+    }
+}
+
+// Built-in handler of DivideRequest
+#[derive(Debug, Clone, Copy, Default)]
+pub struct DivideHandler;
+
+impl Handler for DivideHandler {
+    fn handle(
+        &self,
+        request: ClientMessageType,
+        _context: &RequestContext<'_>,
+    ) -> Option<ServerMessageType> {
+        let ClientMessageType::DivideRequest(request) = request else {
+            return None;
+        };
+        if request.b == 0 {
+            return Some(error_response(
+                ErrorCode::DivisionByZero,
+                format!("{} / 0 is undefined", request.a),
+            ));
+        }
+        // Only i32::MIN / -1 overflows, the remainder is then 0 but does not exist either
+        match (
+            request.a.checked_div(request.b),
+            request.a.checked_rem(request.b),
+        ) {
+            (Some(quotient), Some(remainder)) => {
+                Some(ServerMessageType::DivideResponse(DivideResponse {
+                    quotient,
+                    remainder,
+                }))
+            }
+            _ => Some(overflow(request.a, "/", request.b)),
+        }
+    }
+}
+
+// Built-in handler of CalculateRequest
+#[derive(Debug, Clone, Copy, Default)]
+pub struct CalculateHandler;
+
+impl Handler for CalculateHandler {
+    fn handle(
+        &self,
+        request: ClientMessageType,
+        _context: &RequestContext<'_>,
+    ) -> Option<ServerMessageType> {
+        let ClientMessageType::CalculateRequest(request) = request else {
+            return None;
+        };
+        let response = match expression::evaluate(&request.expression) {
+            Ok(result) => CalculateResponse {
+                result,
+                error: String::new(),
+            },
+            Err(e) => CalculateResponse {
+                result: 0.0,
+                error: e.to_string(),
+            },
+        };
+        Some(ServerMessageType::CalculateResponse(response))
+    }
+}
+
+// Built-in handler of SetRequest, GetRequest and DeleteRequest
+#[derive(Debug, Clone)]
+pub struct KvHandler {
+    store: Arc<KvStore>, // Store shared by all connections
+}
+
+impl KvHandler {
+    /// Creates a handler operating on `store`
+    pub fn new(store: Arc<KvStore>) -> Self {
+        KvHandler { store }
+    }
+}
+
+impl Handler for KvHandler {
+    fn handle(
+        &self,
+        request: ClientMessageType,
+        _context: &RequestContext<'_>,
+    ) -> Option<ServerMessageType> {
+        let response = match request {
+            ClientMessageType::SetRequest(request) => {
+                let response = match self.store.set(&request.key, request.value) {
+                    Ok(previous) => SetResponse {
+                        replaced: previous.is_some(),
+                        error: String::new(),
+                    },
+                    Err(e) => SetResponse {
+                        replaced: false,
+                        error: e.to_string(),
+                    },
+                };
+                ServerMessageType::SetResponse(response)
+            }
+            ClientMessageType::GetRequest(request) => {
+                let response = match self.store.get(&request.key) {
+                    Ok(value) => GetResponse {
+                        found: value.is_some(),
+                        value: value.unwrap_or_default(),
+                        error: String::new(),
+                    },
+                    Err(e) => GetResponse {
+                        error: e.to_string(),
+                        ..GetResponse::default()
+                    },
+                };
+                ServerMessageType::GetResponse(response)
+            }
+            ClientMessageType::DeleteRequest(request) => {
+                let response = match self.store.delete(&request.key) {
+                    Ok(previous) => DeleteResponse {
+                        deleted: previous.is_some(),
+                        error: String::new(),
+                    },
+                    Err(e) => DeleteResponse {
+                        deleted: false,
+                        error: e.to_string(),
+                    },
+                };
+                ServerMessageType::DeleteResponse(response)
+            }
+            _ => return None,
+        };
+        Some(response)
+    }
+}
+
+/// Builds the rejection of an operation whose result does not fit an i32
+fn overflow(a: i32, operator: &str, b: i32) -> ServerMessageType {
+    error_response(
+        ErrorCode::ArithmeticOverflow,
+        format!("{} {} {} overflows", a, operator, b),
+    )
+}
+
+// Built-in handler of EchoMessage, which records the echo and sends it back unchanged
+#[derive(Clone)]
+pub struct EchoHandler {
+    history: Arc<Mutex<EchoHistory>>, // Recent echo messages, for HistoryRequest
+}
+
+impl EchoHandler {
+    /// Creates a handler recording echoes in `history`
+    pub fn new(history: Arc<Mutex<EchoHistory>>) -> Self {
+        EchoHandler { history }
+    }
+}
+
+impl Handler for EchoHandler {
+    fn handle(
+        &self,
+        request: ClientMessageType,
+        _context: &RequestContext<'_>,
+    ) -> Option<ServerMessageType> {
+        let ClientMessageType::EchoMessage(echo_message) = request else {
+            return None;
+        };
+        info!("Received: {}", echo_message.content);
+        self.history.lock().unwrap().record(echo_message.clone());
+        Some(ServerMessageType::EchoMessage(echo_message))
+    }
+}
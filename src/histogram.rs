@@ -0,0 +1,173 @@
+// Importing necessary modules and crates
+use crate::message::ExponentialHistogram as ExponentialHistogramMessage;
+
+// Finest and coarsest bucket scales accepted, the same range OpenTelemetry allows
+pub const MIN_SCALE: i32 = -10;
+pub const MAX_SCALE: i32 = 20;
+
+// Scale used unless configured otherwise, eight buckets per power of two
+pub const DEFAULT_SCALE: i32 = 3;
+
+// Maximum number of buckets, the scale is lowered whenever more would be needed
+pub const MAX_BUCKETS: usize = 160;
+
+// Histogram with exponentially growing buckets, following the OpenTelemetry exponential
+// histogram data model: bucket `i` covers (base^i, base^(i+1)] with base = 2^(2^-scale)
+#[derive(Debug, Clone)]
+pub struct ExponentialHistogram {
+    scale: i32,       // Resolution, each power of two is split into 2^scale buckets
+    offset: i32,      // Index of the first bucket in `counts`
+    counts: Vec<u64>, // Number of values per bucket, starting at `offset`
+    zero_count: u64,  // Number of zero values, which fall into no bucket
+    count: u64,       // Number of recorded values
+    sum: u64,         // Sum of the recorded values
+    min: u64,         // Smallest recorded value
+    max: u64,         // Largest recorded value
+}
+
+impl ExponentialHistogram {
+    /// Creates an empty histogram, `scale` being clamped to the supported range
+    pub fn new(scale: i32) -> Self {
+        ExponentialHistogram {
+            scale: scale.clamp(MIN_SCALE, MAX_SCALE),
+            offset: 0,
+            counts: Vec::new(),
+            zero_count: 0,
+            count: 0,
+            sum: 0,
+            min: u64::MAX,
+            max: 0,
+        }
+    }
+
+    /// Records a single value
+    pub fn record(&mut self, value: u64) {
+        self.count += 1;
+        self.sum = self.sum.saturating_add(value);
+        self.min = self.min.min(value);
+        self.max = self.max.max(value);
+
+        if value == 0 {
+            self.zero_count += 1;
+            return;
+        }
+
+        let mut index = bucket_index(value, self.scale);
+        while !self.counts.is_empty() && self.scale > MIN_SCALE {
+            let first = index.min(self.offset);
+            let last = index.max(self.offset + self.counts.len() as i32 - 1);
+            if ((last - first) as usize) < MAX_BUCKETS {
+                break;
+            }
+            self.downscale();
+            index = bucket_index(value, self.scale);
+        }
+
+        if self.counts.is_empty() {
+            self.offset = index;
+        } else if index < self.offset {
+            // Grow towards smaller values
+            let missing = (self.offset - index) as usize;
+            self.counts.splice(0..0, std::iter::repeat_n(0, missing));
+            self.offset = index;
+        }
+        let position = (index - self.offset) as usize;
+        if position >= self.counts.len() {
+            self.counts.resize(position + 1, 0);
+        }
+        self.counts[position] += 1;
+    }
+
+    /// Halves the resolution, merging every pair of neighbouring buckets
+    fn downscale(&mut self) {
+        let offset = self.offset >> 1;
+        let last = (self.offset + self.counts.len() as i32 - 1) >> 1;
+        let mut counts = vec![0; (last - offset + 1) as usize];
+        for (i, count) in self.counts.iter().enumerate() {
+            counts[(((self.offset + i as i32) >> 1) - offset) as usize] += count;
+        }
+
+        self.scale -= 1;
+        self.offset = offset;
+        self.counts = counts;
+    }
+
+    /// Returns the current bucket scale, which may be lower than requested
+    pub fn scale(&self) -> i32 {
+        self.scale
+    }
+
+    /// Returns the number of recorded values
+    pub fn count(&self) -> u64 {
+        self.count
+    }
+
+    /// Estimates the `quantile` (between 0 and 1) of the recorded values from the upper
+    /// bound of the bucket holding it, `None` if nothing was recorded
+    pub fn quantile(&self, quantile: f64) -> Option<f64> {
+        if self.count == 0 {
+            return None;
+        }
+
+        let rank = ((quantile.clamp(0.0, 1.0) * self.count as f64).ceil() as u64).max(1);
+        if rank <= self.zero_count {
+            return Some(0.0);
+        }
+
+        let mut seen = self.zero_count;
+        for (i, count) in self.counts.iter().enumerate() {
+            seen += count;
+            if seen >= rank {
+                let upper = bucket_lower_bound(self.offset + i as i32 + 1, self.scale);
+                return Some(upper.clamp(self.min as f64, self.max as f64));
+            }
+        }
+        Some(self.max as f64)
+    }
+
+    /// Converts the histogram into its wire representation
+    pub fn to_message(&self) -> ExponentialHistogramMessage {
+        ExponentialHistogramMessage {
+            scale: self.scale,
+            count: self.count,
+            sum: self.sum,
+            zero_count: self.zero_count,
+            offset: self.offset,
+            bucket_counts: self.counts.clone(),
+            min: if self.count == 0 { 0 } else { self.min },
+            max: self.max,
+        }
+    }
+}
+
+impl Default for ExponentialHistogram {
+    fn default() -> Self {
+        Self::new(DEFAULT_SCALE)
+    }
+}
+
+/// Returns the index of the bucket holding `value`, which must not be zero
+pub fn bucket_index(value: u64, scale: i32) -> i32 {
+    let exponent = 63 - value.leading_zeros() as i32; // Position of the highest set bit
+
+    // Powers of two sit exactly on a bucket boundary, the upper bound being inclusive they
+    // belong to the bucket below. Computing them without logarithms avoids rounding errors.
+    if value.is_power_of_two() {
+        return if scale <= 0 {
+            (exponent - 1) >> -scale
+        } else {
+            (exponent << scale) - 1
+        };
+    }
+
+    if scale <= 0 {
+        exponent >> -scale
+    } else {
+        ((value as f64).log2() * f64::from(scale).exp2()).ceil() as i32 - 1
+    }
+}
+
+/// Returns the exclusive lower bound of bucket `index`
+pub fn bucket_lower_bound(index: i32, scale: i32) -> f64 {
+    (f64::from(index) / f64::from(scale).exp2()).exp2()
+}
@@ -0,0 +1,73 @@
+// Importing necessary modules and crates
+use crate::message::{EchoMessage, HistoryResponse};
+use std::collections::VecDeque;
+
+// Maximum number of entries returned at once, larger results are split into pages
+pub const MAX_PAGE_SIZE: usize = 100;
+
+// Bounded ring of the most recent echo messages seen by the server
+pub struct EchoHistory {
+    capacity: usize, // Maximum number of entries retained, 0 disables recording
+    entries: VecDeque<EchoMessage>, // Retained entries, oldest first
+    evicted: u64,    // Number of entries dropped so far, the sequence number of the oldest entry
+}
+
+impl EchoHistory {
+    /// Creates a new history retaining at most `capacity` entries
+    pub fn new(capacity: usize) -> Self {
+        EchoHistory {
+            capacity,
+            entries: VecDeque::with_capacity(capacity),
+            evicted: 0,
+        }
+    }
+
+    /// Records an echo message, evicting the oldest entry when full
+    pub fn record(&mut self, message: EchoMessage) {
+        if self.capacity == 0 {
+            return;
+        }
+
+        if self.entries.len() == self.capacity {
+            self.entries.pop_front();
+            self.evicted += 1;
+        }
+        self.entries.push_back(message);
+    }
+
+    /// Returns up to `limit` entries (a full page if `limit` is 0), the most recent ones if
+    /// `continuation_token` is empty or else those older than the page it was handed out
+    /// with. Unknown tokens yield an empty page.
+    pub fn page(&self, limit: usize, continuation_token: &str) -> HistoryResponse {
+        let total = self.entries.len();
+        let limit = if limit == 0 {
+            MAX_PAGE_SIZE
+        } else {
+            limit.min(MAX_PAGE_SIZE)
+        };
+
+        // The token is the sequence number of the oldest entry handed out so far
+        let end = if continuation_token.is_empty() {
+            total
+        } else {
+            match continuation_token.parse::<u64>() {
+                Ok(sequence) => sequence.saturating_sub(self.evicted).min(total as u64) as usize,
+                Err(_) => 0,
+            }
+        };
+        let start = end.saturating_sub(limit);
+
+        // Only hand out a token while there is something left to continue with
+        let continuation_token = if start > 0 {
+            (self.evicted + start as u64).to_string()
+        } else {
+            String::new()
+        };
+
+        HistoryResponse {
+            entries: self.entries.range(start..end).cloned().collect(),
+            total: total as u32,
+            continuation_token,
+        }
+    }
+}
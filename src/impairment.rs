@@ -0,0 +1,117 @@
+// Importing necessary modules and crates
+use std::{
+    io::{self, Read, Write},
+    thread,
+    time::Duration,
+};
+
+// Adverse network conditions to simulate
+#[derive(Debug, Clone, Default)]
+pub struct Impairment {
+    pub latency: Duration,                  // Delay added to every read and write
+    pub bytes_per_second: Option<u64>,      // Bandwidth cap on writes
+    pub max_write_size: Option<usize>, // Largest write passed on at once, forcing partial writes
+    pub disconnect_after: Option<u64>, // Bytes written before the connection is cut
+    pub disconnect_after_read: Option<u64>, // Bytes read before the connection is cut
+}
+
+// Wraps a transport such as a TcpStream and degrades it according to an Impairment, to
+// test both ends of a connection under adverse conditions
+pub struct ImpairedStream<S> {
+    inner: Option<S>,       // The wrapped transport, dropped once disconnected
+    impairment: Impairment, // Conditions being simulated
+    written: u64,           // Bytes passed on so far
+    read: u64,              // Bytes handed out so far
+}
+
+impl<S> ImpairedStream<S> {
+    /// Wraps `inner`, degrading it as described by `impairment`
+    pub fn new(inner: S, impairment: Impairment) -> Self {
+        ImpairedStream {
+            inner: Some(inner),
+            impairment,
+            written: 0,
+            read: 0,
+        }
+    }
+
+    /// Returns true once the simulated disconnect happened
+    pub fn is_disconnected(&self) -> bool {
+        self.inner.is_none()
+    }
+
+    /// Returns the wrapped transport, unless it was dropped by a simulated disconnect
+    pub fn get_ref(&self) -> Option<&S> {
+        self.inner.as_ref()
+    }
+
+    fn inner(&mut self) -> io::Result<&mut S> {
+        self.inner
+            .as_mut()
+            .ok_or_else(|| io::Error::new(io::ErrorKind::NotConnected, "Simulated disconnect"))
+    }
+
+    fn delay(&self) {
+        if !self.impairment.latency.is_zero() {
+            thread::sleep(self.impairment.latency);
+        }
+    }
+}
+
+impl<S: Read> Read for ImpairedStream<S> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        self.delay();
+
+        let mut len = buf.len();
+        if let Some(limit) = self.impairment.disconnect_after_read {
+            len = len.min(limit.saturating_sub(self.read) as usize);
+        }
+
+        let read = self.inner()?.read(&mut buf[..len])?;
+        self.read += read as u64;
+        if self
+            .impairment
+            .disconnect_after_read
+            .is_some_and(|limit| self.read >= limit)
+        {
+            // Cut the connection, possibly in the middle of a message
+            self.inner = None;
+        }
+        Ok(read)
+    }
+}
+
+impl<S: Write> Write for ImpairedStream<S> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.delay();
+
+        let mut len = buf.len();
+        if let Some(max_write_size) = self.impairment.max_write_size {
+            len = len.min(max_write_size.max(1));
+        }
+        if let Some(limit) = self.impairment.disconnect_after {
+            len = len.min(limit.saturating_sub(self.written) as usize);
+        }
+
+        let written = self.inner()?.write(&buf[..len])?;
+        self.written += written as u64;
+        if self
+            .impairment
+            .disconnect_after
+            .is_some_and(|limit| self.written >= limit)
+        {
+            // Cut the connection, possibly in the middle of a message
+            self.inner = None;
+        }
+
+        if let Some(bytes_per_second) = self.impairment.bytes_per_second {
+            let seconds = written as f64 / bytes_per_second.max(1) as f64;
+            thread::sleep(Duration::from_secs_f64(seconds));
+        }
+        Ok(written)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.inner()?.flush()
+    }
+}
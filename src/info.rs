@@ -0,0 +1,75 @@
+// Importing necessary modules and crates
+use crate::message::ServerInfoResponse;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+// Version of the crate the server was built from
+pub const VERSION: &str = env!("CARGO_PKG_VERSION");
+
+// Commit the server was built from, "unknown" when built outside a git checkout
+pub const GIT_HASH: &str = env!("GIT_HASH");
+
+// Version of the wire protocol, bumped on incompatible changes
+pub const PROTOCOL_VERSION: u32 = 1;
+
+/// Returns the cargo features the server was built with
+pub fn features() -> Vec<&'static str> {
+    let mut features = Vec::new();
+    if cfg!(feature = "metrics") {
+        features.push("metrics");
+    }
+    if cfg!(feature = "server") {
+        features.push("server");
+    }
+    if cfg!(feature = "console") {
+        features.push("console");
+    }
+    if cfg!(feature = "daemon") {
+        features.push("daemon");
+    }
+    if cfg!(feature = "systemd") {
+        features.push("systemd");
+    }
+    if cfg!(feature = "config") {
+        features.push("config");
+    }
+    features
+}
+
+/// Describes this build of the server, which started running at `started_at_ms` since the
+/// Unix epoch and accepts payloads of up to `max_frame_size` bytes
+pub fn server_info(started_at_ms: u64, max_frame_size: usize) -> ServerInfoResponse {
+    ServerInfoResponse {
+        version: VERSION.to_string(),
+        git_hash: GIT_HASH.to_string(),
+        protocol_version: PROTOCOL_VERSION,
+        features: features().into_iter().map(String::from).collect(),
+        started_at_ms,
+        max_frame_size: max_frame_size as u32,
+        session_id: 0,
+        uptime_ms: if started_at_ms == 0 {
+            0
+        } else {
+            unix_time_ms().saturating_sub(started_at_ms)
+        },
+        previous_run_crashed: false,
+    }
+}
+
+/// Returns the current time in milliseconds since the Unix epoch
+pub(crate) fn unix_time_ms() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|elapsed| elapsed.as_millis() as u64)
+        .unwrap_or(0)
+}
+
+/// Returns the line logged when the server starts
+pub fn banner() -> String {
+    format!(
+        "embedded-recruitment-task {} ({}), protocol version {}, features: {}",
+        VERSION,
+        GIT_HASH,
+        PROTOCOL_VERSION,
+        features().join(", ")
+    )
+}
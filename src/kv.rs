@@ -0,0 +1,167 @@
+// Importing necessary modules and crates
+use crate::message::KvSnapshot;
+use log::info;
+use prost::Message;
+use std::{
+    collections::HashMap,
+    error::Error,
+    fmt,
+    fs::{self, File},
+    io::{self, ErrorKind, Write},
+    path::{Path, PathBuf},
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Mutex,
+    },
+    time::Duration,
+};
+
+// Longest key accepted, in bytes
+pub const MAX_KEY_LEN: usize = 256;
+
+// Why a key-value request was rejected
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum KvError {
+    EmptyKey,                     // Keys must not be empty
+    KeyTooLong { length: usize }, // The key exceeds MAX_KEY_LEN
+    Full { capacity: usize },     // The store holds as many keys as it may
+}
+
+impl fmt::Display for KvError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            KvError::EmptyKey => write!(f, "Key must not be empty"),
+            KvError::KeyTooLong { length } => write!(
+                f,
+                "Key of {} bytes exceeds the limit of {}",
+                length, MAX_KEY_LEN
+            ),
+            KvError::Full { capacity } => write!(f, "Store is full with {} keys", capacity),
+        }
+    }
+}
+
+impl Error for KvError {}
+
+// Where and how often the key-value store is persisted
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct KvPersistence {
+    pub path: PathBuf,      // Snapshot file, loaded when the server is created
+    pub interval: Duration, // How often a changed store is written to it
+}
+
+// In-memory key-value store shared by every connection of a server
+#[derive(Debug)]
+pub struct KvStore {
+    entries: Mutex<HashMap<String, Vec<u8>>>, // Values, by key
+    capacity: usize,                          // Most keys held at once
+    version: AtomicU64,                       // Number of changes so far
+}
+
+impl KvStore {
+    /// Creates an empty store holding at most `capacity` keys
+    pub fn new(capacity: usize) -> Self {
+        KvStore {
+            entries: Mutex::new(HashMap::new()),
+            capacity,
+            version: AtomicU64::new(0),
+        }
+    }
+
+    /// Creates a store holding what the snapshot at `path` holds, or an empty one if there
+    /// is no snapshot yet. Keys beyond `capacity` are kept, new ones are rejected until
+    /// enough are deleted.
+    pub fn load(path: &Path, capacity: usize) -> io::Result<Self> {
+        let store = KvStore::new(capacity);
+        let contents = match fs::read(path) {
+            Ok(contents) => contents,
+            Err(e) if e.kind() == ErrorKind::NotFound => return Ok(store),
+            Err(e) => return Err(e),
+        };
+        let snapshot = KvSnapshot::decode(contents.as_slice())
+            .map_err(|e| io::Error::new(ErrorKind::InvalidData, e))?;
+        info!(
+            "Loaded {} keys from {}",
+            snapshot.entries.len(),
+            path.display()
+        );
+        *store.entries.lock().unwrap() = snapshot.entries;
+        Ok(store)
+    }
+
+    /// Writes the contents of the store to `path`. The snapshot is written next to it first
+    /// and then renamed, so a crash never leaves a partial snapshot behind.
+    pub fn save(&self, path: &Path) -> io::Result<()> {
+        let snapshot = KvSnapshot {
+            entries: self.entries.lock().unwrap().clone(),
+        };
+        write_atomically(path, &snapshot.encode_to_vec())
+    }
+
+    /// Returns the number of changes made so far, to tell whether a snapshot is outdated
+    pub fn version(&self) -> u64 {
+        self.version.load(Ordering::SeqCst)
+    }
+
+    /// Stores `value` under `key`, returning the value it replaces. Replacing works even
+    /// when the store is full.
+    pub fn set(&self, key: &str, value: Vec<u8>) -> Result<Option<Vec<u8>>, KvError> {
+        check_key(key)?;
+        let mut entries = self.entries.lock().unwrap();
+        if entries.len() >= self.capacity && !entries.contains_key(key) {
+            return Err(KvError::Full {
+                capacity: self.capacity,
+            });
+        }
+        self.version.fetch_add(1, Ordering::SeqCst);
+        Ok(entries.insert(key.to_string(), value))
+    }
+
+    /// Returns the value stored under `key`
+    pub fn get(&self, key: &str) -> Result<Option<Vec<u8>>, KvError> {
+        check_key(key)?;
+        Ok(self.entries.lock().unwrap().get(key).cloned())
+    }
+
+    /// Removes `key`, returning the value it held
+    pub fn delete(&self, key: &str) -> Result<Option<Vec<u8>>, KvError> {
+        check_key(key)?;
+        let previous = self.entries.lock().unwrap().remove(key);
+        if previous.is_some() {
+            self.version.fetch_add(1, Ordering::SeqCst);
+        }
+        Ok(previous)
+    }
+
+    /// Returns the number of keys stored
+    pub fn len(&self) -> usize {
+        self.entries.lock().unwrap().len()
+    }
+
+    /// Returns true if no key is stored
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+/// Replaces the file at `path` with `contents`, which are written next to it first and then
+/// renamed, so that a crash leaves either the old or the new contents behind
+pub(crate) fn write_atomically(path: &Path, contents: &[u8]) -> io::Result<()> {
+    let mut temporary = path.as_os_str().to_owned();
+    temporary.push(".tmp");
+    let mut file = File::create(&temporary)?;
+    file.write_all(contents)?;
+    file.sync_all()?;
+    fs::rename(&temporary, path)
+}
+
+/// Rejects keys the store does not accept
+fn check_key(key: &str) -> Result<(), KvError> {
+    if key.is_empty() {
+        return Err(KvError::EmptyKey);
+    }
+    if key.len() > MAX_KEY_LEN {
+        return Err(KvError::KeyTooLong { length: key.len() });
+    }
+    Ok(())
+}
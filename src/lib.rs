@@ -1,4 +1,72 @@
+#[cfg(feature = "systemd")]
+pub mod activation;
+#[cfg(feature = "server")]
+pub mod admin;
+#[cfg(feature = "server")]
+pub mod buffer;
+#[cfg(feature = "server")]
+pub mod cache;
+#[cfg(feature = "metrics")]
+pub mod churn;
+#[cfg(feature = "client")]
+pub mod client;
+pub mod clock;
+#[cfg(feature = "server")]
+pub mod compute;
+#[cfg(feature = "config")]
+pub mod config;
+#[cfg(feature = "console")]
+pub mod console;
+#[cfg(feature = "daemon")]
+pub mod daemon;
+#[cfg(feature = "client")]
+pub mod endpoints;
+#[cfg(any(feature = "server", feature = "metrics"))]
+pub mod events;
+#[cfg(feature = "server")]
+pub mod expression;
+#[cfg(feature = "server")]
+pub mod extract;
+pub mod framing;
+#[cfg(feature = "server")]
+pub mod handler;
+#[cfg(feature = "metrics")]
+pub mod histogram;
+#[cfg(feature = "server")]
+pub mod history;
+pub mod impairment;
+#[cfg(feature = "server")]
+pub mod info;
+#[cfg(feature = "kv")]
+pub mod kv;
+#[cfg(feature = "metrics")]
+pub mod metrics;
+#[cfg(feature = "server")]
+pub mod ndjson;
+#[cfg(feature = "server")]
+pub mod peer;
+#[cfg(feature = "server")]
+pub mod pool;
+#[cfg(feature = "server")]
+pub mod privileges;
+#[cfg(feature = "pubsub")]
+pub mod pubsub;
+#[cfg(feature = "server")]
+pub mod recent_errors;
+#[cfg(any(feature = "server", feature = "metrics"))]
+pub mod resources;
+#[cfg(feature = "server")]
+pub mod rng;
+#[cfg(feature = "server")]
+pub mod run_history;
+#[cfg(feature = "server")]
+pub mod self_test;
+#[cfg(feature = "server")]
 pub mod server;
+#[cfg(feature = "server")]
+pub mod session;
+#[cfg(feature = "metrics")]
+pub mod stats;
 
 pub mod message {
     include!(concat!(env!("OUT_DIR"), "/messages.rs"));
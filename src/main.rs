@@ -0,0 +1,321 @@
+// Importing necessary modules and crates
+#[cfg(feature = "config")]
+use embedded_recruitment_task::config::Config;
+#[cfg(feature = "daemon")]
+use embedded_recruitment_task::daemon::{self, PidFile};
+#[cfg(not(feature = "config"))]
+use embedded_recruitment_task::server::ServerConfig;
+use embedded_recruitment_task::{info, server::Server};
+use log::{error, info, LevelFilter, Log, Metadata, Record};
+use std::{
+    env,
+    path::PathBuf,
+    process::ExitCode,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+    thread,
+    time::Duration,
+};
+
+// Shown for --help and after invalid arguments
+const USAGE: &str = "\
+Usage: embedded-recruitment-task [OPTIONS]
+
+Options:
+  --addr <HOST>         Address to listen on [default: 127.0.0.1]
+  --port <PORT>         Port to listen on, 0 picks a free one [default: 8080]
+  --max-conns <N>       Connections served at once [default: unlimited]
+  --log-level <LEVEL>   off, error, warn, info, debug or trace [default: info]
+  --config <PATH>       TOML file with further settings, overridden by the options above
+  --self-test           Run the built-in smoke test against a loopback server and exit
+  --version             Print the version and exit
+  --help                Print this help and exit";
+
+// Options of builds with the daemon feature, listed after the others
+#[cfg(feature = "daemon")]
+const DAEMON_USAGE: &str = "
+  --daemonize           Detach from the terminal and keep running in the background
+  --pid-file <PATH>     Write the id of the server process to PATH while it runs";
+
+// Options of builds without the daemon feature, none
+#[cfg(not(feature = "daemon"))]
+const DAEMON_USAGE: &str = "";
+
+// Exit code for invalid arguments, as is customary for command line tools
+const USAGE_ERROR: u8 = 2;
+
+// How often the signal flag is checked
+const SIGNAL_POLL_INTERVAL: Duration = Duration::from_millis(50);
+
+// Set by the signal handler once SIGINT or SIGTERM arrived
+static STOP_REQUESTED: AtomicBool = AtomicBool::new(false);
+
+// Options given on the command line
+#[derive(Debug, Default)]
+struct Args {
+    addr: Option<String>,           // Host to listen on
+    port: Option<u16>,              // Port to listen on
+    max_conns: Option<usize>,       // Connections served at once
+    log_level: Option<LevelFilter>, // Most verbose messages logged
+    config: Option<PathBuf>,        // Settings file
+    #[cfg(feature = "daemon")]
+    daemonize: bool, // Whether to run in the background
+    #[cfg(feature = "daemon")]
+    pid_file: Option<PathBuf>, // Where to write the process id
+}
+
+// What the command line asks for
+enum Command {
+    Serve(Args), // Run the server
+    SelfTest,    // Run the self-test
+    Help,        // Print the usage
+    Version,     // Print the version
+}
+
+impl Args {
+    /// Parses the arguments following the program name
+    fn parse(mut args: impl Iterator<Item = String>) -> Result<Command, String> {
+        let mut parsed = Args::default();
+        while let Some(arg) = args.next() {
+            // Both --name value and --name=value are accepted
+            let (name, inline) = match arg.split_once('=') {
+                Some((name, value)) => (name.to_string(), Some(value.to_string())),
+                None => (arg, None),
+            };
+            let mut value = || {
+                inline
+                    .clone()
+                    .or_else(|| args.next())
+                    .ok_or_else(|| format!("{} needs a value", name))
+            };
+            match name.as_str() {
+                "--help" | "-h" => return Ok(Command::Help),
+                "--version" | "-V" => return Ok(Command::Version),
+                "--self-test" => return Ok(Command::SelfTest),
+                "--addr" => parsed.addr = Some(value()?),
+                "--port" => parsed.port = Some(parse(&name, &value()?)?),
+                "--max-conns" => parsed.max_conns = Some(parse(&name, &value()?)?),
+                "--log-level" => parsed.log_level = Some(parse(&name, &value()?)?),
+                "--config" => parsed.config = Some(value()?.into()),
+                #[cfg(feature = "daemon")]
+                "--daemonize" => parsed.daemonize = true,
+                #[cfg(feature = "daemon")]
+                "--pid-file" => parsed.pid_file = Some(value()?.into()),
+                _ => return Err(format!("Unknown argument {}", name)),
+            }
+        }
+        Ok(Command::Serve(parsed))
+    }
+}
+
+/// Parses the value of the option `name`
+fn parse<T: std::str::FromStr>(name: &str, value: &str) -> Result<T, String> {
+    value
+        .parse()
+        .map_err(|_| format!("Invalid value for {}: {}", name, value))
+}
+
+// Writes log messages to stderr
+struct StderrLogger;
+
+impl Log for StderrLogger {
+    fn enabled(&self, metadata: &Metadata<'_>) -> bool {
+        metadata.level() <= log::max_level()
+    }
+
+    fn log(&self, record: &Record<'_>) {
+        if self.enabled(record.metadata()) {
+            eprintln!("{:<5} {}", record.level(), record.args());
+        }
+    }
+
+    fn flush(&self) {}
+}
+
+/// Replaces the host and port of `address` with the ones given, keeping the others. IPv6
+/// hosts are bracketed, as in "[::1]:8080".
+fn override_address(address: &str, host: Option<&str>, port: Option<u16>) -> String {
+    let (default_host, default_port) = address.rsplit_once(':').unwrap_or((address, "8080"));
+    let port = port.map_or(default_port.to_string(), |port| port.to_string());
+    match host {
+        Some(host) if host.contains(':') && !host.starts_with('[') => {
+            format!("[{}]:{}", host, port)
+        }
+        host => format!("{}:{}", host.unwrap_or(default_host), port),
+    }
+}
+
+/// Creates the server the arguments describe, from the settings file and the environment
+#[cfg(feature = "config")]
+fn start(args: &Args) -> Result<Server, String> {
+    let mut config = match &args.config {
+        Some(path) => Config::load(path).map_err(|e| e.to_string())?,
+        None => {
+            let mut config = Config::default();
+            config.apply_env().map_err(|e| e.to_string())?;
+            config
+        }
+    };
+    let address = config.address.as_deref().unwrap_or("127.0.0.1:8080");
+    config.address = Some(override_address(address, args.addr.as_deref(), args.port));
+    config.max_connections = args.max_conns.or(config.max_connections);
+    config.log_level = args.log_level.or(config.log_level);
+    let address = config.address.clone().unwrap_or_default();
+    Server::from_config(config).map_err(|e| format!("Failed to start on {}: {}", address, e))
+}
+
+/// Creates the server the arguments describe
+#[cfg(not(feature = "config"))]
+fn start(args: &Args) -> Result<Server, String> {
+    if args.config.is_some() {
+        return Err("--config needs a build with the config feature".to_string());
+    }
+    let address = override_address("127.0.0.1:8080", args.addr.as_deref(), args.port);
+    let config = ServerConfig {
+        max_connections: args.max_conns,
+        ..ServerConfig::default()
+    };
+    Server::with_config(&address, config)
+        .map_err(|e| format!("Failed to start on {}: {}", address, e))
+}
+
+/// Moves the server to the background if asked to and writes its pid file. Has to run
+/// before any thread is spawned, only the calling thread survives detaching.
+#[cfg(feature = "daemon")]
+fn daemonize(args: &Args) -> Result<Option<PidFile>, String> {
+    // Resolved first, detaching changes the working directory to /
+    let pid_file = args
+        .pid_file
+        .as_ref()
+        .map(|path| {
+            std::path::absolute(path)
+                .map_err(|e| format!("Invalid pid file {}: {}", path.display(), e))
+        })
+        .transpose()?;
+    if args.daemonize {
+        daemon::detach().map_err(|e| format!("Failed to detach: {}", e))?;
+    }
+    // Written after detaching, the id is the one of the process that keeps running
+    pid_file
+        .map(|path| {
+            PidFile::create(&path)
+                .map_err(|e| format!("Failed to write pid file {}: {}", path.display(), e))
+        })
+        .transpose()
+}
+
+/// Sets the stop flag, a second signal ends the process right away
+#[cfg(unix)]
+extern "C" fn on_signal(_: libc::c_int) {
+    if STOP_REQUESTED.swap(true, Ordering::SeqCst) {
+        // SAFETY: _exit is async-signal-safe
+        unsafe { libc::_exit(130) };
+    }
+}
+
+/// Stops the server gracefully on SIGINT, i.e. Ctrl-C, and SIGTERM
+#[cfg(unix)]
+fn install_signal_handlers() {
+    for signal in [libc::SIGINT, libc::SIGTERM] {
+        // SAFETY: the handler only touches an atomic and calls _exit
+        unsafe { libc::signal(signal, on_signal as *const () as libc::sighandler_t) };
+    }
+}
+
+/// Stops the server gracefully on signals, where there are any
+#[cfg(not(unix))]
+fn install_signal_handlers() {
+    // Ctrl-C ends the process without a graceful stop
+}
+
+/// Runs the self-test and prints its report, failing unless every check passed
+fn self_test() -> ExitCode {
+    match Server::self_test() {
+        Ok(report) => {
+            println!("{}", report);
+            if report.passed() {
+                ExitCode::SUCCESS
+            } else {
+                ExitCode::FAILURE
+            }
+        }
+        Err(e) => {
+            eprintln!("Failed to run the self-test: {}", e);
+            ExitCode::FAILURE
+        }
+    }
+}
+
+fn main() -> ExitCode {
+    let args = match Args::parse(env::args().skip(1)) {
+        Ok(Command::Serve(args)) => args,
+        Ok(Command::Help) => {
+            println!("{}{}", USAGE, DAEMON_USAGE);
+            return ExitCode::SUCCESS;
+        }
+        Ok(Command::Version) => {
+            println!("{}", info::banner());
+            return ExitCode::SUCCESS;
+        }
+        Ok(Command::SelfTest) => return self_test(),
+        Err(e) => {
+            eprintln!("{}\n\n{}{}", e, USAGE, DAEMON_USAGE);
+            return ExitCode::from(USAGE_ERROR);
+        }
+    };
+    // Only fails if a logger is installed already, which nothing else does
+    let _ = log::set_logger(&StderrLogger);
+    log::set_max_level(args.log_level.unwrap_or(LevelFilter::Info));
+
+    // Before the pid file is written, a signal sent right after must still stop gracefully
+    install_signal_handlers();
+    let server = match start(&args) {
+        Ok(server) => Arc::new(server),
+        Err(e) => {
+            error!("{}", e);
+            return ExitCode::FAILURE;
+        }
+    };
+    // Bound already, so that a busy port is still reported on the terminal
+    #[cfg(feature = "daemon")]
+    let _pid_file = match daemonize(&args) {
+        Ok(pid_file) => pid_file,
+        Err(e) => {
+            error!("{}", e);
+            return ExitCode::FAILURE;
+        }
+    };
+
+    let watcher = {
+        let watched = Arc::clone(&server);
+        server.thread_options().spawn("signals", move || {
+            while !STOP_REQUESTED.load(Ordering::SeqCst) {
+                thread::sleep(SIGNAL_POLL_INTERVAL);
+            }
+            info!("Stopping the server");
+            watched.stop();
+        })
+    };
+    let watcher = match watcher {
+        Ok(watcher) => watcher,
+        Err(e) => {
+            error!("Failed to spawn the signal watcher: {}", e);
+            return ExitCode::FAILURE;
+        }
+    };
+
+    let result = server.run();
+    drop(watcher);
+    match result {
+        Ok(()) => {
+            info!("Server stopped");
+            ExitCode::SUCCESS
+        }
+        Err(e) => {
+            error!("Server failed: {}", e);
+            ExitCode::FAILURE
+        }
+    }
+}
@@ -0,0 +1,235 @@
+// Importing necessary modules and crates
+use crate::churn::{ChurnBucket, ConnectionChurn};
+use crate::clock::{Clock, SystemClock};
+use crate::events::CloseReason;
+use crate::histogram::{ExponentialHistogram, DEFAULT_SCALE};
+use std::{
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc, Mutex,
+    },
+    time::Duration,
+};
+
+// Counters describing the activity of a server
+pub struct Metrics {
+    connections_accepted: AtomicU64, // Total number of accepted connections
+    connections_closed: [AtomicU64; CloseReason::ALL.len()], // Closed connections, per reason
+    connections_abandoned: AtomicU64, // Connections closed before their first byte arrived
+    time_to_first_byte: Mutex<ExponentialHistogram>, // Microseconds from accept to first byte
+    time_to_first_byte_since_scrape: Mutex<ExponentialHistogram>, // Same, since the last scrape
+    histogram_scale: i32,            // Bucket scale new histograms start with
+    epoch: AtomicU64,                // Number of resets so far
+    last_scrape: Mutex<MetricsSnapshot>, // Cumulative values handed out by the last scrape
+    churn: Mutex<ConnectionChurn>,   // Connects and disconnects over the last hour, per minute
+    clock: Arc<dyn Clock>,           // Time churn is bucketed by
+}
+
+// Values of the metrics at one point in time, or their change between two points
+#[derive(Debug, Clone, Default)]
+pub struct MetricsSnapshot {
+    pub epoch: u64, // Number of resets before the snapshot, a change means counters restarted
+    pub connections_accepted: u64, // Accepted connections
+    pub connections_closed: [u64; CloseReason::ALL.len()], // Closed connections, per reason
+    pub connections_abandoned: u64, // Connections closed before their first byte arrived
+    pub time_to_first_byte: ExponentialHistogram, // Microseconds from accept to first byte
+}
+
+impl MetricsSnapshot {
+    /// Returns how many connections were closed for the given reason
+    pub fn connections_closed(&self, reason: CloseReason) -> u64 {
+        self.connections_closed[reason as usize]
+    }
+}
+
+// Result of a scrape, for exporters of both cumulative counters and deltas
+#[derive(Debug, Clone)]
+pub struct Scrape {
+    pub cumulative: MetricsSnapshot, // Values since the server started, or was last reset
+    pub delta: MetricsSnapshot,      // Change since the previous scrape
+}
+
+impl Default for Metrics {
+    fn default() -> Self {
+        Metrics::new(DEFAULT_SCALE)
+    }
+}
+
+impl Metrics {
+    /// Creates empty metrics with latency histograms of the given bucket scale
+    pub fn new(histogram_scale: i32) -> Self {
+        Metrics::with_clock(histogram_scale, Arc::new(SystemClock))
+    }
+
+    /// Creates empty metrics like `new`, bucketing churn by the time of `clock`
+    pub fn with_clock(histogram_scale: i32, clock: Arc<dyn Clock>) -> Self {
+        Metrics {
+            connections_accepted: AtomicU64::new(0),
+            connections_closed: Default::default(),
+            connections_abandoned: AtomicU64::new(0),
+            time_to_first_byte: Mutex::new(ExponentialHistogram::new(histogram_scale)),
+            time_to_first_byte_since_scrape: Mutex::new(ExponentialHistogram::new(histogram_scale)),
+            histogram_scale,
+            epoch: AtomicU64::new(0),
+            last_scrape: Mutex::new(MetricsSnapshot::default()),
+            churn: Mutex::new(ConnectionChurn::default()),
+            clock,
+        }
+    }
+
+    /// Counts a newly accepted connection
+    pub fn record_connect(&self) {
+        self.connections_accepted.fetch_add(1, Ordering::Relaxed);
+        self.churn
+            .lock()
+            .unwrap()
+            .record_connect(self.clock.system_time());
+    }
+
+    /// Counts a terminated connection under its close reason
+    pub fn record_close(&self, reason: CloseReason) {
+        self.connections_closed[reason as usize].fetch_add(1, Ordering::Relaxed);
+        self.churn
+            .lock()
+            .unwrap()
+            .record_disconnect(self.clock.system_time(), reason);
+    }
+
+    /// Counts a connection that was closed before the client sent anything
+    pub fn record_abandoned(&self) {
+        self.connections_abandoned.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// Records how long a client took to send its first byte after being accepted
+    pub fn record_first_byte(&self, elapsed: Duration) {
+        let micros = elapsed.as_micros() as u64;
+        self.time_to_first_byte.lock().unwrap().record(micros);
+        self.time_to_first_byte_since_scrape
+            .lock()
+            .unwrap()
+            .record(micros);
+    }
+
+    /// Returns the total number of accepted connections
+    pub fn connections_accepted(&self) -> u64 {
+        self.connections_accepted.load(Ordering::Relaxed)
+    }
+
+    /// Returns how many connections were closed for the given reason
+    pub fn connections_closed(&self, reason: CloseReason) -> u64 {
+        self.connections_closed[reason as usize].load(Ordering::Relaxed)
+    }
+
+    /// Returns how many connections were closed before the client sent anything
+    pub fn connections_abandoned(&self) -> u64 {
+        self.connections_abandoned.load(Ordering::Relaxed)
+    }
+
+    /// Returns the share of accepted connections that were never used, 0 without connections
+    pub fn establishment_error_rate(&self) -> f64 {
+        let accepted = self.connections_accepted();
+        if accepted == 0 {
+            return 0.0;
+        }
+        self.connections_abandoned() as f64 / accepted as f64
+    }
+
+    /// Returns the per-minute connects and disconnects of the last hour, oldest first.
+    /// Minutes without churn are left out.
+    pub fn churn(&self) -> Vec<ChurnBucket> {
+        self.churn.lock().unwrap().buckets(self.clock.system_time())
+    }
+
+    /// Locks the churn window, to describe it for a ConnectionChurnRequest
+    #[cfg(feature = "server")]
+    pub(crate) fn churn_window(&self) -> std::sync::MutexGuard<'_, ConnectionChurn> {
+        self.churn.lock().unwrap()
+    }
+
+    /// Returns the distribution of the time to first byte, in microseconds
+    pub fn time_to_first_byte(&self) -> ExponentialHistogram {
+        self.time_to_first_byte.lock().unwrap().clone()
+    }
+
+    /// Returns the median time to first byte, `None` before any client sent something
+    pub fn time_to_first_byte_p50(&self) -> Option<Duration> {
+        self.time_to_first_byte_quantile(0.5)
+    }
+
+    /// Returns the 99th percentile of the time to first byte
+    pub fn time_to_first_byte_p99(&self) -> Option<Duration> {
+        self.time_to_first_byte_quantile(0.99)
+    }
+
+    fn time_to_first_byte_quantile(&self, quantile: f64) -> Option<Duration> {
+        let micros = self.time_to_first_byte.lock().unwrap().quantile(quantile)?;
+        Some(Duration::from_micros(micros as u64))
+    }
+
+    /// Returns the current values of all metrics
+    pub fn snapshot(&self) -> MetricsSnapshot {
+        MetricsSnapshot {
+            epoch: self.epoch.load(Ordering::Relaxed),
+            connections_accepted: self.connections_accepted(),
+            connections_closed: CloseReason::ALL.map(|reason| self.connections_closed(reason)),
+            connections_abandoned: self.connections_abandoned(),
+            time_to_first_byte: self.time_to_first_byte(),
+        }
+    }
+
+    /// Returns the current values together with their change since the previous scrape. Every
+    /// recorded value shows up in exactly one delta, so deltas can be summed up safely.
+    pub fn scrape(&self) -> Scrape {
+        let mut last_scrape = self.last_scrape.lock().unwrap();
+        let cumulative = self.snapshot();
+        let time_to_first_byte = std::mem::replace(
+            &mut *self.time_to_first_byte_since_scrape.lock().unwrap(),
+            ExponentialHistogram::new(self.histogram_scale),
+        );
+
+        let change = |current: u64, previous: u64| current.saturating_sub(previous);
+        let mut connections_closed = cumulative.connections_closed;
+        for (closed, previous) in connections_closed
+            .iter_mut()
+            .zip(last_scrape.connections_closed)
+        {
+            *closed = change(*closed, previous);
+        }
+        let delta = MetricsSnapshot {
+            epoch: cumulative.epoch,
+            connections_accepted: change(
+                cumulative.connections_accepted,
+                last_scrape.connections_accepted,
+            ),
+            connections_closed,
+            connections_abandoned: change(
+                cumulative.connections_abandoned,
+                last_scrape.connections_abandoned,
+            ),
+            time_to_first_byte,
+        };
+
+        *last_scrape = cumulative.clone();
+        Scrape { cumulative, delta }
+    }
+
+    /// Sets all metrics back to zero and starts a new epoch, for example on a configuration
+    /// reload. Values recorded since the last scrape are discarded along with the rest.
+    pub fn reset(&self) {
+        let mut last_scrape = self.last_scrape.lock().unwrap();
+        self.connections_accepted.store(0, Ordering::Relaxed);
+        for closed in &self.connections_closed {
+            closed.store(0, Ordering::Relaxed);
+        }
+        self.connections_abandoned.store(0, Ordering::Relaxed);
+        self.churn.lock().unwrap().clear();
+        *self.time_to_first_byte.lock().unwrap() = ExponentialHistogram::new(self.histogram_scale);
+        *self.time_to_first_byte_since_scrape.lock().unwrap() =
+            ExponentialHistogram::new(self.histogram_scale);
+        let epoch = self.epoch.fetch_add(1, Ordering::Relaxed) + 1;
+        *last_scrape = MetricsSnapshot {
+            epoch,
+            ..MetricsSnapshot::default()
+        };
+    }
+}
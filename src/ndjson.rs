@@ -0,0 +1,123 @@
+// Importing necessary modules and crates
+use crate::events::ServerEvent;
+use crate::pool::ThreadOptions;
+use std::{
+    fmt::Write as _,
+    io::{self, Write},
+    sync::mpsc::Receiver,
+    thread::JoinHandle,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+/// Forwards every event received on `events` to `writer` as newline-delimited JSON, one
+/// object per line, until the server goes away or writing fails. Works with anything that
+/// implements `Write`, such as a file or a socket to a log shipper. Runs on an "events"
+/// thread spawned as `threads` says.
+pub fn spawn_sink<W>(
+    events: Receiver<ServerEvent>,
+    mut writer: W,
+    threads: &ThreadOptions,
+) -> io::Result<JoinHandle<io::Result<()>>>
+where
+    W: Write + Send + 'static,
+{
+    threads.spawn("events", move || {
+        for event in events {
+            let mut line = to_json(&event);
+            line.push('\n');
+            writer.write_all(line.as_bytes())?;
+            writer.flush()?;
+        }
+        Ok(())
+    })
+}
+
+/// Serializes an event as a single-line JSON object
+pub fn to_json(event: &ServerEvent) -> String {
+    // Events that record when they happened keep that time, the others happened just now
+    let timestamp = match event {
+        ServerEvent::MessageHandled { timestamp, .. } => *timestamp,
+        _ => SystemTime::now(),
+    };
+    let timestamp_ms = timestamp
+        .duration_since(UNIX_EPOCH)
+        .map(|elapsed| elapsed.as_millis())
+        .unwrap_or(0);
+    let mut json = format!("{{\"timestamp_ms\":{}", timestamp_ms);
+
+    match event {
+        ServerEvent::Connected { addr } => {
+            field(&mut json, "event", "connected");
+            field(&mut json, "addr", &addr.to_string());
+        }
+        ServerEvent::Disconnected { addr, reason } => {
+            field(&mut json, "event", "disconnected");
+            field(&mut json, "addr", &addr.to_string());
+            field(&mut json, "reason", reason.as_str());
+        }
+        ServerEvent::ResourcePressure { usage }
+        | ServerEvent::ResourcePressureRelieved { usage } => {
+            let name = match event {
+                ServerEvent::ResourcePressure { .. } => "resource_pressure",
+                _ => "resource_pressure_relieved",
+            };
+            field(&mut json, "event", name);
+            number(&mut json, "open_fds", usage.open_fds);
+            number(&mut json, "rss_bytes", usage.rss_bytes);
+        }
+        ServerEvent::AcceptLoopFailed { errors, kind } => {
+            field(&mut json, "event", "accept_loop_failed");
+            number(&mut json, "errors", Some(u64::from(*errors)));
+            field(&mut json, "kind", &format!("{:?}", kind));
+        }
+        ServerEvent::MessageHandled { message, .. } => {
+            field(&mut json, "event", "message");
+            field(&mut json, "addr", &message.addr.to_string());
+            number(&mut json, "session_id", Some(message.session_id));
+            field(&mut json, "message_type", message.message_type);
+            number(&mut json, "size", Some(message.size as u64));
+            number(
+                &mut json,
+                "latency_us",
+                Some(message.elapsed.as_micros() as u64),
+            );
+            flag(&mut json, "failed", message.failed);
+        }
+    }
+
+    json.push('}');
+    json
+}
+
+/// Appends a string member to a JSON object under construction
+fn field(json: &mut String, name: &str, value: &str) {
+    let _ = write!(json, ",\"{}\":\"", name);
+    for c in value.chars() {
+        match c {
+            '"' => json.push_str("\\\""),
+            '\\' => json.push_str("\\\\"),
+            c if c.is_control() => {
+                let _ = write!(json, "\\u{:04x}", c as u32);
+            }
+            c => json.push(c),
+        }
+    }
+    json.push('"');
+}
+
+/// Appends a boolean member
+fn flag(json: &mut String, name: &str, value: bool) {
+    let _ = write!(json, ",\"{}\":{}", name, value);
+}
+
+/// Appends a numeric member, `null` if the value is unknown
+fn number(json: &mut String, name: &str, value: Option<u64>) {
+    match value {
+        Some(value) => {
+            let _ = write!(json, ",\"{}\":{}", name, value);
+        }
+        None => {
+            let _ = write!(json, ",\"{}\":null", name);
+        }
+    }
+}
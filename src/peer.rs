@@ -0,0 +1,104 @@
+// Importing necessary modules and crates
+use std::{
+    fmt,
+    net::{IpAddr, SocketAddr},
+    sync::OnceLock,
+};
+
+// Who is on the other end of a connection, in the one representation used for logs and
+// access decisions
+#[derive(Debug)]
+pub struct PeerIdentity {
+    addr: SocketAddr, // Address of the peer, IPv4-mapped addresses as IPv4
+    hostname: OnceLock<Option<String>>, // Name found by reverse DNS, once looked up
+}
+
+impl PeerIdentity {
+    /// Creates the identity of the peer at `addr`
+    pub fn new(addr: SocketAddr) -> Self {
+        PeerIdentity {
+            addr: normalize(addr),
+            hostname: OnceLock::new(),
+        }
+    }
+
+    /// Returns the normalized address of the peer
+    pub fn addr(&self) -> SocketAddr {
+        self.addr
+    }
+
+    /// Returns the normalized IP address of the peer
+    pub fn ip(&self) -> IpAddr {
+        self.addr.ip()
+    }
+
+    /// Returns the name of the peer, `None` until it was resolved or if it has none
+    pub fn hostname(&self) -> Option<&str> {
+        self.hostname.get()?.as_deref()
+    }
+
+    /// Returns whether the reverse DNS lookup finished
+    pub fn is_resolved(&self) -> bool {
+        self.hostname.get().is_some()
+    }
+
+    /// Looks the name of the peer up by reverse DNS, blocking until the resolver answers.
+    /// Only the first lookup queries the resolver.
+    pub fn resolve(&self) -> Option<&str> {
+        self.hostname
+            .get_or_init(|| reverse_lookup(self.ip()))
+            .as_deref()
+    }
+}
+
+impl fmt::Display for PeerIdentity {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.hostname() {
+            Some(hostname) => write!(f, "{} ({})", hostname, self.addr),
+            None => write!(f, "{}", self.addr),
+        }
+    }
+}
+
+/// Turns IPv4 addresses that a dual-stack listener reports as IPv4-mapped IPv6 addresses
+/// back into IPv4 ones, leaving every other address alone
+pub fn normalize(addr: SocketAddr) -> SocketAddr {
+    match addr {
+        SocketAddr::V6(v6) => match v6.ip().to_ipv4_mapped() {
+            Some(v4) => SocketAddr::new(IpAddr::V4(v4), v6.port()),
+            None => addr,
+        },
+        SocketAddr::V4(_) => addr,
+    }
+}
+
+/// Returns the name registered for `ip`, if any
+#[cfg(unix)]
+fn reverse_lookup(ip: IpAddr) -> Option<String> {
+    let addr = socket2::SockAddr::from(SocketAddr::new(ip, 0));
+    let mut host = [0 as libc::c_char; libc::NI_MAXHOST as usize];
+    // SAFETY: the address and buffer are valid for the lengths passed along with them
+    let result = unsafe {
+        libc::getnameinfo(
+            addr.as_ptr(),
+            addr.len(),
+            host.as_mut_ptr(),
+            host.len() as libc::socklen_t,
+            std::ptr::null_mut(),
+            0,
+            libc::NI_NAMEREQD,
+        )
+    };
+    if result != 0 {
+        return None;
+    }
+    // SAFETY: getnameinfo succeeded, so the buffer holds a NUL-terminated name
+    let host = unsafe { std::ffi::CStr::from_ptr(host.as_ptr()) };
+    Some(host.to_string_lossy().into_owned())
+}
+
+/// Returns the name registered for `ip`, if any
+#[cfg(not(unix))]
+fn reverse_lookup(_ip: IpAddr) -> Option<String> {
+    None
+}
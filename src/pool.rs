@@ -0,0 +1,149 @@
+// Importing necessary modules and crates
+use std::{
+    io,
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        mpsc::{self, Receiver, Sender},
+        Arc, Mutex,
+    },
+    thread::{self, JoinHandle},
+};
+
+// Unit of work handed to the pool
+pub type Job = Box<dyn FnOnce() + Send + 'static>;
+
+// How threads are spawned, e.g. small stacks for deployments running hundreds of them
+#[derive(Debug, Clone)]
+pub struct ThreadOptions {
+    pub name_prefix: String, // Start of every thread name, followed by the thread's role
+    pub stack_size: Option<usize>, // Stack size of every thread in bytes, the platform default if unset
+}
+
+impl Default for ThreadOptions {
+    fn default() -> Self {
+        ThreadOptions {
+            name_prefix: "server".to_string(),
+            stack_size: None,
+        }
+    }
+}
+
+impl ThreadOptions {
+    /// Spawns a thread named "<prefix>-<role>" running `f`
+    pub fn spawn<F, T>(&self, role: &str, f: F) -> io::Result<JoinHandle<T>>
+    where
+        F: FnOnce() -> T + Send + 'static,
+        T: Send + 'static,
+    {
+        let mut builder = thread::Builder::new().name(format!("{}-{}", self.name_prefix, role));
+        if let Some(stack_size) = self.stack_size {
+            builder = builder.stack_size(stack_size);
+        }
+        builder.spawn(f)
+    }
+}
+
+// Fixed set of worker threads running jobs in the order they were submitted, queuing them
+// while every worker is busy
+pub struct WorkerPool {
+    handle: Option<PoolHandle>, // Submits jobs, dropped with the pool to close the queue
+    workers: Vec<JoinHandle<()>>, // Threads running the jobs
+}
+
+// Submits jobs to a pool, cloned into jobs that want to queue follow-up work
+#[derive(Clone)]
+pub struct PoolHandle {
+    sender: Sender<Job>,      // Queue of submitted jobs
+    queued: Arc<AtomicUsize>, // Jobs submitted but not picked up by a worker yet
+}
+
+impl WorkerPool {
+    /// Starts a pool of `size` worker threads, at least one
+    pub fn new(size: usize) -> Self {
+        Self::with_threads(size, &ThreadOptions::default()).expect("Failed to spawn worker thread")
+    }
+
+    /// Starts a pool of `size` worker threads, at least one, spawned as `threads` says.
+    /// Fails if a worker cannot be spawned.
+    pub fn with_threads(size: usize, threads: &ThreadOptions) -> io::Result<Self> {
+        let (sender, receiver) = mpsc::channel::<Job>();
+        let receiver = Arc::new(Mutex::new(receiver));
+        let queued = Arc::new(AtomicUsize::new(0));
+
+        // Workers spawned before a failure exit once the sender is dropped
+        let workers = (0..size.max(1))
+            .map(|index| {
+                let receiver = Arc::clone(&receiver);
+                let queued = Arc::clone(&queued);
+                threads.spawn(&format!("worker-{}", index), move || {
+                    Self::work(&receiver, &queued)
+                })
+            })
+            .collect::<io::Result<_>>()?;
+
+        Ok(WorkerPool {
+            handle: Some(PoolHandle { sender, queued }),
+            workers,
+        })
+    }
+
+    /// Returns the number of worker threads
+    pub fn size(&self) -> usize {
+        self.workers.len()
+    }
+
+    /// Returns the number of jobs waiting for a worker
+    pub fn queued(&self) -> usize {
+        self.handle().queued()
+    }
+
+    /// Submits a job, run as soon as a worker is free
+    pub fn execute(&self, job: Job) {
+        self.handle().execute(job);
+    }
+
+    /// Returns a handle submitting jobs to this pool
+    pub fn handle(&self) -> &PoolHandle {
+        self.handle.as_ref().expect("Pool handle taken before drop")
+    }
+
+    /// Runs jobs from the queue until it is closed
+    fn work(receiver: &Mutex<Receiver<Job>>, queued: &AtomicUsize) {
+        loop {
+            // Only hold the lock while waiting, not while running the job
+            let job = receiver.lock().unwrap().recv();
+            match job {
+                Ok(job) => {
+                    queued.fetch_sub(1, Ordering::SeqCst);
+                    job();
+                }
+                Err(_) => break,
+            }
+        }
+    }
+}
+
+impl PoolHandle {
+    /// Returns the number of jobs waiting for a worker
+    pub fn queued(&self) -> usize {
+        self.queued.load(Ordering::SeqCst)
+    }
+
+    /// Submits a job, run as soon as a worker is free
+    pub fn execute(&self, job: Job) {
+        self.queued.fetch_add(1, Ordering::SeqCst);
+        // The workers only exit once every handle is gone
+        let _ = self.sender.send(job);
+    }
+}
+
+impl Drop for WorkerPool {
+    /// Lets the workers finish every submitted job, including those queued by jobs in turn,
+    /// then waits for them to exit
+    fn drop(&mut self) {
+        drop(self.handle.take());
+        for worker in self.workers.drain(..) {
+            let _ = worker.join();
+        }
+    }
+}
@@ -0,0 +1,172 @@
+// Importing necessary modules and crates
+use log::info;
+use std::{io, path::PathBuf};
+
+// Privileges the server gives up once its listener is bound
+#[derive(Debug, Clone, Default)]
+pub struct PrivilegeDrop {
+    pub user: Option<String>,    // User to switch to
+    pub group: Option<String>,   // Group to switch to, defaults to the user's primary group
+    pub chroot: Option<PathBuf>, // Directory to confine the process to
+}
+
+impl PrivilegeDrop {
+    /// Returns true when there is nothing to drop
+    pub fn is_empty(&self) -> bool {
+        self.user.is_none() && self.group.is_none() && self.chroot.is_none()
+    }
+
+    /// Applies the privilege drop to the whole process
+    #[cfg(unix)]
+    pub fn apply(&self) -> io::Result<()> {
+        if self.is_empty() {
+            return Ok(());
+        }
+
+        // Resolve names first, the user database is usually unreachable after the chroot
+        let user = self.user.as_deref().map(unix::lookup_user).transpose()?;
+        let gid = match self.group.as_deref() {
+            Some(group) => Some(unix::lookup_group(group)?),
+            None => user.map(|(_, gid)| gid),
+        };
+
+        if let Some(path) = &self.chroot {
+            unix::chroot(path)?;
+            info!("Changed root directory to {}", path.display());
+        }
+        // The group has to change while we are still allowed to do so
+        if let Some(gid) = gid {
+            unix::set_group(gid)?;
+            info!("Switched to group id {}", gid);
+        }
+        if let Some((uid, _)) = user {
+            unix::set_user(uid)?;
+            info!("Switched to user id {}", uid);
+        }
+
+        Ok(())
+    }
+
+    /// Applies the privilege drop to the whole process
+    #[cfg(not(unix))]
+    pub fn apply(&self) -> io::Result<()> {
+        if self.is_empty() {
+            return Ok(());
+        }
+
+        Err(io::Error::new(
+            io::ErrorKind::Unsupported,
+            "Dropping privileges is only supported on Unix",
+        ))
+    }
+}
+
+#[cfg(unix)]
+mod unix {
+    use std::{
+        ffi::{CStr, CString},
+        io,
+        os::unix::ffi::OsStrExt,
+        path::Path,
+        ptr,
+    };
+
+    // Size of the scratch buffer handed to the reentrant user database lookups
+    const LOOKUP_BUFFER_SIZE: usize = 16 * 1024;
+
+    fn to_cstring(bytes: &[u8]) -> io::Result<CString> {
+        CString::new(bytes).map_err(|e| io::Error::new(io::ErrorKind::InvalidInput, e))
+    }
+
+    fn not_found(kind: &str, name: &str) -> io::Error {
+        io::Error::new(
+            io::ErrorKind::NotFound,
+            format!("Unknown {}: {}", kind, name),
+        )
+    }
+
+    /// Returns the uid and primary gid of a user
+    pub fn lookup_user(name: &str) -> io::Result<(libc::uid_t, libc::gid_t)> {
+        let c_name = to_cstring(name.as_bytes())?;
+        let mut passwd: libc::passwd = unsafe { std::mem::zeroed() };
+        let mut buffer = vec![0 as libc::c_char; LOOKUP_BUFFER_SIZE];
+        let mut result = ptr::null_mut();
+
+        // SAFETY: every pointer refers to live, correctly sized storage
+        let err = unsafe {
+            libc::getpwnam_r(
+                c_name.as_ptr(),
+                &mut passwd,
+                buffer.as_mut_ptr(),
+                buffer.len(),
+                &mut result,
+            )
+        };
+        if err != 0 {
+            return Err(io::Error::from_raw_os_error(err));
+        }
+        if result.is_null() {
+            return Err(not_found("user", name));
+        }
+
+        Ok((passwd.pw_uid, passwd.pw_gid))
+    }
+
+    /// Returns the gid of a group
+    pub fn lookup_group(name: &str) -> io::Result<libc::gid_t> {
+        let c_name = to_cstring(name.as_bytes())?;
+        let mut group: libc::group = unsafe { std::mem::zeroed() };
+        let mut buffer = vec![0 as libc::c_char; LOOKUP_BUFFER_SIZE];
+        let mut result = ptr::null_mut();
+
+        // SAFETY: every pointer refers to live, correctly sized storage
+        let err = unsafe {
+            libc::getgrnam_r(
+                c_name.as_ptr(),
+                &mut group,
+                buffer.as_mut_ptr(),
+                buffer.len(),
+                &mut result,
+            )
+        };
+        if err != 0 {
+            return Err(io::Error::from_raw_os_error(err));
+        }
+        if result.is_null() {
+            return Err(not_found("group", name));
+        }
+
+        Ok(group.gr_gid)
+    }
+
+    fn check(ret: libc::c_int) -> io::Result<()> {
+        if ret == 0 {
+            Ok(())
+        } else {
+            Err(io::Error::last_os_error())
+        }
+    }
+
+    /// Confines the process to `path`
+    pub fn chroot(path: &Path) -> io::Result<()> {
+        let c_path = to_cstring(path.as_os_str().as_bytes())?;
+        let root: &CStr = c"/";
+
+        // SAFETY: both arguments are valid NUL-terminated strings
+        check(unsafe { libc::chroot(c_path.as_ptr()) })?;
+        check(unsafe { libc::chdir(root.as_ptr()) })
+    }
+
+    /// Switches the process to `gid`, dropping all supplementary groups
+    pub fn set_group(gid: libc::gid_t) -> io::Result<()> {
+        // SAFETY: the group list points to a single valid gid
+        check(unsafe { libc::setgroups(1, &gid) })?;
+        check(unsafe { libc::setgid(gid) })
+    }
+
+    /// Switches the process to `uid`
+    pub fn set_user(uid: libc::uid_t) -> io::Result<()> {
+        // SAFETY: plain system call without pointers
+        check(unsafe { libc::setuid(uid) })
+    }
+}
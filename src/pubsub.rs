@@ -0,0 +1,273 @@
+// Importing necessary modules and crates
+use crate::message::server_message::Message as ServerMessageType;
+use crate::message::{Publication, PublishAck, ServerMessage};
+use std::{
+    collections::{HashMap, HashSet, VecDeque},
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        mpsc::{SyncSender, TrySendError},
+        Mutex,
+    },
+};
+
+// Messages that may wait in a connection's outbound queue, further ones are dropped until
+// the connection catches up
+pub const OUTBOUND_CAPACITY: usize = 256;
+
+// Outcome of a subscription
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Subscribed {
+    pub is_new: bool,     // Whether the session was not subscribed to the topic already
+    pub replayed: usize,  // Retained messages queued for the subscriber
+    pub next_offset: u64, // Offset the next message published to the topic gets
+}
+
+// Outcome of a publication
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Published {
+    pub queued: usize, // Subscribers the publication was queued for
+    pub offset: u64,   // Offset the publication got within its topic
+}
+
+// Acknowledgement owed to a publisher, until every copy of its publication is settled
+struct Receipt {
+    publisher: SyncSender<ServerMessage>, // Outbound queue of the publisher
+    waiting: HashSet<u64>,                // Subscribers whose copy is queued, by session id
+    ack: PublishAck,                      // Copies settled so far
+}
+
+impl Receipt {
+    /// Queues the acknowledgement for the publisher, dropped if its queue is full
+    fn send(self) {
+        let _ = self.publisher.try_send(ServerMessage {
+            message: Some(ServerMessageType::PublishAck(self.ack)),
+            ..ServerMessage::default()
+        });
+    }
+}
+
+// A topic: who listens to it and what was published to it recently
+#[derive(Default)]
+struct Topic {
+    subscribers: HashMap<u64, SyncSender<ServerMessage>>, // Outbound queues, by session id
+    retained: VecDeque<ServerMessage>,                    // Latest publications, oldest first
+    next_offset: u64, // Offset the next publication gets, counting from 0
+}
+
+impl Topic {
+    /// Returns true if the topic has subscribers or retained messages
+    fn is_active(&self) -> bool {
+        !self.subscribers.is_empty() || !self.retained.is_empty()
+    }
+}
+
+// Subscribers of every topic, by session id. Messages are queued on the subscribers'
+// outbound channels, so publishing never blocks on a slow subscriber. Topics are kept once
+// used, so that their offsets never repeat.
+#[derive(Default)]
+pub struct Topics {
+    topics: Mutex<HashMap<String, Topic>>, // Every topic subscribed or published to
+    retention: usize,                      // Publications kept per topic for replay
+    receipts: Mutex<HashMap<(String, u64), Receipt>>, // Owed acknowledgements, by topic and offset
+    pending_receipts: AtomicUsize, // Number of receipts, to skip the lock when there are none
+}
+
+impl Topics {
+    /// Creates a registry without any subscriptions, which retains nothing
+    pub fn new() -> Self {
+        Topics::default()
+    }
+
+    /// Creates a registry keeping the last `retention` publications of every topic, for
+    /// subscribers replaying what they missed
+    pub fn with_retention(retention: usize) -> Self {
+        Topics {
+            topics: Mutex::new(HashMap::new()),
+            retention,
+            receipts: Mutex::new(HashMap::new()),
+            pending_receipts: AtomicUsize::new(0),
+        }
+    }
+
+    /// Delivers messages published to `topic` to `outbound`, returns false if the session
+    /// was subscribed already
+    pub fn subscribe(
+        &self,
+        topic: &str,
+        session_id: u64,
+        outbound: SyncSender<ServerMessage>,
+    ) -> bool {
+        self.subscribe_from(topic, session_id, outbound, None)
+            .is_new
+    }
+
+    /// Delivers messages published to `topic` to `outbound`, first queueing the retained
+    /// ones from `from_offset` on, if given
+    pub fn subscribe_from(
+        &self,
+        topic: &str,
+        session_id: u64,
+        outbound: SyncSender<ServerMessage>,
+        from_offset: Option<u64>,
+    ) -> Subscribed {
+        let mut topics = self.topics.lock().unwrap();
+        let topic = topics.entry(topic.to_string()).or_default();
+
+        // Replaying under the lock keeps publications from slipping through or in between
+        let mut replayed = 0;
+        if let Some(from_offset) = from_offset {
+            let first_offset = topic.next_offset - topic.retained.len() as u64;
+            let skip = from_offset.saturating_sub(first_offset) as usize;
+            for message in topic.retained.iter().skip(skip) {
+                if outbound.try_send(message.clone()).is_err() {
+                    break;
+                }
+                replayed += 1;
+            }
+        }
+
+        Subscribed {
+            is_new: topic.subscribers.insert(session_id, outbound).is_none(),
+            replayed,
+            next_offset: topic.next_offset,
+        }
+    }
+
+    /// Stops delivering messages published to `topic` to the session, returns whether it
+    /// was subscribed
+    pub fn unsubscribe(&self, topic: &str, session_id: u64) -> bool {
+        let mut topics = self.topics.lock().unwrap();
+        topics
+            .get_mut(topic)
+            .is_some_and(|topic| topic.subscribers.remove(&session_id).is_some())
+    }
+
+    /// Removes every subscription of the session, once its connection is gone
+    pub fn unsubscribe_all(&self, session_id: u64) {
+        let mut topics = self.topics.lock().unwrap();
+        for topic in topics.values_mut() {
+            topic.subscribers.remove(&session_id);
+        }
+    }
+
+    /// Numbers `publication` with the next offset of its topic, retains it and queues it for
+    /// every subscriber. Returns how many subscribers it was queued for, those whose queue is
+    /// full miss it.
+    pub fn publish(&self, publication: Publication) -> usize {
+        self.publish_with_receipt(publication, None).queued
+    }
+
+    /// Publishes like `publish`. With a `receipt` queue, a PublishAck is queued on it once
+    /// every copy was written to its subscriber by `settle`, or missed.
+    pub fn publish_with_receipt(
+        &self,
+        mut publication: Publication,
+        receipt: Option<SyncSender<ServerMessage>>,
+    ) -> Published {
+        let mut topics = self.topics.lock().unwrap();
+        let name = publication.topic.clone();
+        let topic = topics.entry(name.clone()).or_default();
+        let offset = topic.next_offset;
+        publication.offset = offset;
+        topic.next_offset += 1;
+        let message = ServerMessage {
+            message: Some(ServerMessageType::Publication(publication)),
+            ..ServerMessage::default()
+        };
+
+        // Held until the receipt is in place, so subscribers settle nothing before
+        let mut receipts = self.receipts.lock().unwrap();
+        let mut receipt = receipt.map(|publisher| {
+            self.pending_receipts.fetch_add(1, Ordering::SeqCst);
+            Receipt {
+                publisher,
+                waiting: HashSet::new(),
+                ack: PublishAck {
+                    topic: name.clone(),
+                    offset,
+                    ..PublishAck::default()
+                },
+            }
+        });
+
+        let mut queued = 0;
+        topic.subscribers.retain(|&session_id, outbound| {
+            let sent = outbound.try_send(message.clone());
+            if let Some(receipt) = receipt.as_mut() {
+                match sent {
+                    Ok(()) => _ = receipt.waiting.insert(session_id),
+                    Err(_) => receipt.ack.failed += 1,
+                }
+            }
+            match sent {
+                Ok(()) => {
+                    queued += 1;
+                    true
+                }
+                Err(TrySendError::Full(_)) => true,
+                Err(TrySendError::Disconnected(_)) => false,
+            }
+        });
+        if let Some(receipt) = receipt {
+            if receipt.waiting.is_empty() {
+                self.pending_receipts.fetch_sub(1, Ordering::SeqCst);
+                receipt.send();
+            } else {
+                receipts.insert((name.clone(), offset), receipt);
+            }
+        }
+        drop(receipts);
+
+        if self.retention > 0 {
+            if topic.retained.len() == self.retention {
+                topic.retained.pop_front();
+            }
+            topic.retained.push_back(message);
+        }
+        Published { queued, offset }
+    }
+
+    /// Records whether the copy of `publication` queued for the session was written to its
+    /// connection, queueing the publisher's PublishAck once it was the last copy outstanding.
+    /// Copies replayed to late subscribers were never waited for and are ignored.
+    pub fn settle(&self, publication: &Publication, session_id: u64, delivered: bool) {
+        if self.pending_receipts.load(Ordering::SeqCst) == 0 {
+            return;
+        }
+        let mut receipts = self.receipts.lock().unwrap();
+        let key = (publication.topic.clone(), publication.offset);
+        let Some(receipt) = receipts.get_mut(&key) else {
+            return;
+        };
+        if !receipt.waiting.remove(&session_id) {
+            return;
+        }
+        if delivered {
+            receipt.ack.delivered += 1;
+        } else {
+            receipt.ack.failed += 1;
+        }
+        if receipt.waiting.is_empty() {
+            self.pending_receipts.fetch_sub(1, Ordering::SeqCst);
+            receipts.remove(&key).unwrap().send();
+        }
+    }
+
+    /// Returns the number of subscribers of `topic`
+    pub fn subscribers(&self, topic: &str) -> usize {
+        let topics = self.topics.lock().unwrap();
+        topics.get(topic).map_or(0, |topic| topic.subscribers.len())
+    }
+
+    /// Returns the topics with subscribers or retained messages, sorted
+    pub fn topics(&self) -> Vec<String> {
+        let topics = self.topics.lock().unwrap();
+        let mut topics: Vec<String> = topics
+            .iter()
+            .filter(|(_, topic)| topic.is_active())
+            .map(|(name, _)| name.clone())
+            .collect();
+        topics.sort_unstable();
+        topics
+    }
+}
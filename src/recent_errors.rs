@@ -0,0 +1,54 @@
+// Importing necessary modules and crates
+use crate::message::RecordedError;
+use std::{
+    collections::VecDeque,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+// Bounded ring of the most recent errors met while serving clients
+pub struct RecentErrors {
+    capacity: usize, // Maximum number of errors retained, 0 disables recording
+    entries: VecDeque<RecordedError>, // Retained errors, oldest first
+}
+
+impl RecentErrors {
+    /// Creates a new ring retaining at most `capacity` errors
+    pub fn new(capacity: usize) -> Self {
+        RecentErrors {
+            capacity,
+            entries: VecDeque::with_capacity(capacity),
+        }
+    }
+
+    /// Records an error met on the connection of session `session_id`, evicting the oldest
+    /// one when full
+    pub fn record(&mut self, session_id: u64, kind: &str, message: String) {
+        if self.capacity == 0 {
+            return;
+        }
+
+        if self.entries.len() == self.capacity {
+            self.entries.pop_front();
+        }
+        let timestamp_ms = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|elapsed| elapsed.as_millis() as u64)
+            .unwrap_or(0);
+        self.entries.push_back(RecordedError {
+            timestamp_ms,
+            session_id,
+            kind: kind.to_string(),
+            message,
+        });
+    }
+
+    /// Returns up to `limit` errors (all of them if `limit` is 0), newest first
+    pub fn recent(&self, limit: usize) -> Vec<RecordedError> {
+        let limit = if limit == 0 {
+            self.entries.len()
+        } else {
+            limit
+        };
+        self.entries.iter().rev().take(limit).cloned().collect()
+    }
+}
@@ -0,0 +1,84 @@
+// Importing necessary modules and crates
+use std::time::Duration;
+
+// Resource consumption of the process, `None` where the platform cannot tell
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ResourceUsage {
+    pub open_fds: Option<u64>,  // Number of open file descriptors
+    pub rss_bytes: Option<u64>, // Resident set size
+}
+
+impl ResourceUsage {
+    /// Measures the current resource consumption of the process
+    pub fn sample() -> Self {
+        ResourceUsage {
+            open_fds: open_fds(),
+            rss_bytes: rss_bytes(),
+        }
+    }
+}
+
+// Thresholds beyond which the server stops accepting connections
+#[derive(Debug, Clone)]
+pub struct ResourceLimits {
+    pub max_open_fds: Option<u64>, // Open file descriptors the process may hold
+    pub max_rss_bytes: Option<u64>, // Resident set size the process may reach
+    pub shed_idle: bool,           // Close the oldest idle connection while under pressure
+    pub check_interval: Duration,  // How often resource usage is measured
+}
+
+impl Default for ResourceLimits {
+    fn default() -> Self {
+        ResourceLimits {
+            max_open_fds: None,
+            max_rss_bytes: None,
+            shed_idle: false,
+            check_interval: Duration::from_secs(1),
+        }
+    }
+}
+
+impl ResourceLimits {
+    /// Returns true when no threshold is configured
+    pub fn is_empty(&self) -> bool {
+        self.max_open_fds.is_none() && self.max_rss_bytes.is_none()
+    }
+
+    /// Returns true when `usage` crosses any of the thresholds
+    pub fn exceeded_by(&self, usage: &ResourceUsage) -> bool {
+        let exceeds = |value: Option<u64>, max: Option<u64>| match (value, max) {
+            (Some(value), Some(max)) => value > max,
+            _ => false,
+        };
+        exceeds(usage.open_fds, self.max_open_fds) || exceeds(usage.rss_bytes, self.max_rss_bytes)
+    }
+}
+
+#[cfg(target_os = "linux")]
+fn open_fds() -> Option<u64> {
+    // The directory listing holds a descriptor of its own while it is being read
+    let entries = std::fs::read_dir("/proc/self/fd").ok()?.count() as u64;
+    Some(entries.saturating_sub(1))
+}
+
+#[cfg(not(target_os = "linux"))]
+fn open_fds() -> Option<u64> {
+    None
+}
+
+#[cfg(target_os = "linux")]
+fn rss_bytes() -> Option<u64> {
+    // The second field is the resident set size, in pages
+    let statm = std::fs::read_to_string("/proc/self/statm").ok()?;
+    let pages: u64 = statm.split_whitespace().nth(1)?.parse().ok()?;
+    // SAFETY: sysconf has no preconditions
+    let page_size = unsafe { libc::sysconf(libc::_SC_PAGESIZE) };
+    u64::try_from(page_size)
+        .ok()
+        .map(|page_size| pages * page_size)
+}
+
+#[cfg(not(target_os = "linux"))]
+fn rss_bytes() -> Option<u64> {
+    None
+}
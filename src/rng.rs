@@ -0,0 +1,65 @@
+// Importing necessary modules and crates
+use std::{
+    collections::hash_map::RandomState,
+    hash::{BuildHasher, Hasher},
+    ops::Range,
+};
+
+// Increment of the SplitMix64 sequence, also used to spread stream numbers apart
+const GOLDEN_GAMMA: u64 = 0x9E37_79B9_7F4A_7C15;
+
+/// Returns the seed of random stream `stream`, derived from `seed` when one is configured so
+/// that runs can be reproduced, and from per-process randomness otherwise
+pub fn stream_seed(seed: Option<u64>, stream: u64) -> u64 {
+    match seed {
+        Some(seed) => mix(seed ^ stream.wrapping_mul(GOLDEN_GAMMA)),
+        None => {
+            let mut hasher = RandomState::new().build_hasher();
+            hasher.write_u64(stream);
+            hasher.finish()
+        }
+    }
+}
+
+// Small, fast, seedable generator (SplitMix64), not suitable for cryptography
+#[derive(Debug, Clone)]
+pub struct Rng {
+    state: u64, // Position in the sequence
+}
+
+impl Rng {
+    /// Creates a generator whose whole sequence is determined by `seed`
+    pub fn new(seed: u64) -> Self {
+        Rng { state: seed }
+    }
+
+    /// Returns the next random number
+    pub fn next_u64(&mut self) -> u64 {
+        self.state = self.state.wrapping_add(GOLDEN_GAMMA);
+        mix(self.state)
+    }
+
+    /// Returns a random number in [0, 1)
+    pub fn next_f64(&mut self) -> f64 {
+        // The top 53 bits fill the mantissa exactly
+        (self.next_u64() >> 11) as f64 / (1u64 << 53) as f64
+    }
+
+    /// Returns a random number in `range`, which must not be empty
+    pub fn gen_range(&mut self, range: Range<u64>) -> u64 {
+        assert!(!range.is_empty(), "Empty range");
+        range.start + self.next_u64() % (range.end - range.start)
+    }
+
+    /// Returns true with the given probability
+    pub fn chance(&mut self, probability: f64) -> bool {
+        self.next_f64() < probability
+    }
+}
+
+/// Scrambles the bits of `z`, the output function of SplitMix64
+fn mix(mut z: u64) -> u64 {
+    z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+    z ^ (z >> 31)
+}
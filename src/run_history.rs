@@ -0,0 +1,93 @@
+// Importing necessary modules and crates
+use crate::kv;
+use crate::message::{RunLog, RunRecord};
+use prost::Message;
+use std::{
+    fs,
+    io::{self, ErrorKind},
+    path::{Path, PathBuf},
+};
+
+// Runs kept in the history, the oldest are forgotten beyond it
+pub const MAX_RUNS: usize = 32;
+
+// When the server started and stopped, kept in a file so that it outlives the process. A
+// run still open when the next one starts ended without a clean stop, i.e. it crashed.
+#[derive(Debug, Default)]
+pub struct RunHistory {
+    path: Option<PathBuf>, // File the history is kept in, in memory only if unset
+    runs: Vec<RunRecord>,  // Recorded runs, oldest first
+}
+
+impl RunHistory {
+    /// Creates a history kept in memory, covering this process only
+    pub fn new() -> Self {
+        RunHistory::default()
+    }
+
+    /// Creates a history kept in `path`, holding what it holds already. A missing file is
+    /// an empty history, a corrupt one is not silently replaced.
+    pub fn load(path: &Path) -> io::Result<Self> {
+        let runs = match fs::read(path) {
+            Ok(contents) => {
+                RunLog::decode(contents.as_slice())
+                    .map_err(|e| io::Error::new(ErrorKind::InvalidData, e))?
+                    .runs
+            }
+            Err(e) if e.kind() == ErrorKind::NotFound => Vec::new(),
+            Err(e) => return Err(e),
+        };
+        Ok(RunHistory {
+            path: Some(path.to_path_buf()),
+            runs,
+        })
+    }
+
+    /// Records a run starting at `started_at_ms`, marking the previous run as crashed if it
+    /// never stopped, and writes the history to its file
+    pub fn record_start(&mut self, started_at_ms: u64) -> io::Result<()> {
+        if let Some(previous) = self.runs.last_mut() {
+            previous.crashed |= previous.stopped_at_ms.is_none();
+        }
+        self.runs.push(RunRecord {
+            started_at_ms,
+            stopped_at_ms: None,
+            crashed: false,
+        });
+        if self.runs.len() > MAX_RUNS {
+            self.runs.drain(..self.runs.len() - MAX_RUNS);
+        }
+        self.save()
+    }
+
+    /// Records that the current run stopped cleanly at `stopped_at_ms`, and writes the
+    /// history to its file
+    pub fn record_stop(&mut self, stopped_at_ms: u64) -> io::Result<()> {
+        if let Some(current) = self.runs.last_mut() {
+            current.stopped_at_ms.get_or_insert(stopped_at_ms);
+        }
+        self.save()
+    }
+
+    /// Returns true if the run before the last one ended without stopping cleanly
+    pub fn previous_run_crashed(&self) -> bool {
+        self.runs.iter().rev().nth(1).is_some_and(|run| run.crashed)
+    }
+
+    /// Returns up to `limit` runs (all of them if `limit` is 0), newest first
+    pub fn recent(&self, limit: usize) -> Vec<RunRecord> {
+        let limit = if limit == 0 { self.runs.len() } else { limit };
+        self.runs.iter().rev().take(limit).cloned().collect()
+    }
+
+    /// Writes the history to its file, if it has one
+    fn save(&self) -> io::Result<()> {
+        let Some(path) = &self.path else {
+            return Ok(());
+        };
+        let log = RunLog {
+            runs: self.runs.clone(),
+        };
+        kv::write_atomically(path, &log.encode_to_vec())
+    }
+}
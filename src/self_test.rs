@@ -0,0 +1,158 @@
+// Importing necessary modules and crates
+use crate::framing::{self, DEFAULT_MAX_FRAME_SIZE};
+use crate::message::client_message::Message as ClientMessageType;
+use crate::message::server_message::Message as ServerMessageType;
+use crate::message::{AddRequest, ClientMessage, EchoMessage, ErrorCode, ServerMessage};
+use prost::Message;
+use std::{
+    fmt,
+    io::ErrorKind,
+    net::{SocketAddr, TcpStream},
+    time::{Duration, Instant},
+};
+
+// How long the self-test waits for the server before a check fails
+const RESPONSE_TIMEOUT: Duration = Duration::from_secs(1);
+
+// Payload no message type can be decoded from: a tag whose varint never ends
+const MALFORMED_PAYLOAD: [u8; 4] = [0xff; 4];
+
+// A check run over an established connection, failing with a description of what went wrong
+type Check = fn(&mut TcpStream) -> Result<(), String>;
+
+// Outcome of a single self-test check
+#[derive(Debug, Clone)]
+pub struct SelfTestCheck {
+    pub name: &'static str,         // What was checked
+    pub result: Result<(), String>, // Why the check failed, if it did
+    pub duration: Duration,         // How long the check took
+}
+
+// Outcome of a whole self-test run
+#[derive(Debug, Clone)]
+pub struct SelfTestReport {
+    pub addr: SocketAddr,           // Address the tested server was listening on
+    pub checks: Vec<SelfTestCheck>, // Every check, in the order they ran
+}
+
+impl SelfTestReport {
+    /// Returns true when every check passed
+    pub fn passed(&self) -> bool {
+        self.checks.iter().all(|check| check.result.is_ok())
+    }
+}
+
+impl fmt::Display for SelfTestReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "Self-test against {}", self.addr)?;
+        for check in &self.checks {
+            match &check.result {
+                Ok(()) => writeln!(f, "  {}: ok ({:?})", check.name, check.duration)?,
+                Err(e) => writeln!(f, "  {}: FAILED ({:?}): {}", check.name, check.duration, e)?,
+            }
+        }
+        write!(f, "{}", if self.passed() { "PASSED" } else { "FAILED" })
+    }
+}
+
+/// Runs every check against the server listening on `addr`
+pub(crate) fn run_checks(addr: SocketAddr) -> SelfTestReport {
+    let mut checks = Vec::new();
+
+    let started = Instant::now();
+    let stream = TcpStream::connect_timeout(&addr, RESPONSE_TIMEOUT)
+        .and_then(|stream| {
+            stream
+                .set_read_timeout(Some(RESPONSE_TIMEOUT))
+                .map(|_| stream)
+        })
+        .map_err(|e| format!("Failed to connect: {}", e));
+    checks.push(SelfTestCheck {
+        name: "connect",
+        result: stream.as_ref().map(|_| ()).map_err(Clone::clone),
+        duration: started.elapsed(),
+    });
+    let Ok(mut stream) = stream else {
+        return SelfTestReport { addr, checks };
+    };
+
+    let cases: [(&'static str, Check); 3] = [
+        ("echo", check_echo),
+        ("add", check_add),
+        ("malformed_message", check_malformed),
+    ];
+    for (name, check) in cases {
+        let started = Instant::now();
+        let result = check(&mut stream);
+        checks.push(SelfTestCheck {
+            name,
+            result,
+            duration: started.elapsed(),
+        });
+    }
+
+    SelfTestReport { addr, checks }
+}
+
+/// The server echoes a message back unchanged
+fn check_echo(stream: &mut TcpStream) -> Result<(), String> {
+    let content = "self-test".to_string();
+    let message = ClientMessageType::EchoMessage(EchoMessage {
+        content: content.clone(),
+    });
+    match exchange(stream, message)? {
+        Some(ServerMessageType::EchoMessage(echo)) if echo.content == content => Ok(()),
+        other => Err(format!("Unexpected response: {:?}", other)),
+    }
+}
+
+/// The server adds two numbers
+fn check_add(stream: &mut TcpStream) -> Result<(), String> {
+    let message = ClientMessageType::AddRequest(AddRequest { a: 20, b: 22 });
+    match exchange(stream, message)? {
+        Some(ServerMessageType::AddResponse(add)) if add.result == 42 => Ok(()),
+        other => Err(format!("Unexpected response: {:?}", other)),
+    }
+}
+
+/// The server rejects a malformed message and keeps serving the connection
+fn check_malformed(stream: &mut TcpStream) -> Result<(), String> {
+    framing::write_frame(stream, &MALFORMED_PAYLOAD)
+        .map_err(|e| format!("Failed to send: {}", e))?;
+    match receive(stream)? {
+        Some(ServerMessageType::ErrorResponse(error))
+            if error.code() == ErrorCode::DecodeFailed => {}
+        other => return Err(format!("Unexpected response: {:?}", other)),
+    }
+
+    check_add(stream).map_err(|e| format!("Connection unusable afterwards: {}", e))
+}
+
+/// Sends a request and waits for its response
+fn exchange(
+    stream: &mut TcpStream,
+    message: ClientMessageType,
+) -> Result<Option<ServerMessageType>, String> {
+    let client_message = ClientMessage {
+        message: Some(message),
+        ..ClientMessage::default()
+    };
+    framing::write_frame(stream, &client_message.encode_to_vec())
+        .map_err(|e| format!("Failed to send: {}", e))?;
+    receive(stream)
+}
+
+/// Waits for the next message from the server
+fn receive(stream: &mut TcpStream) -> Result<Option<ServerMessageType>, String> {
+    let payload = match framing::read_frame(stream, DEFAULT_MAX_FRAME_SIZE) {
+        Ok(payload) => payload,
+        Err(ref e) if e.kind() == ErrorKind::UnexpectedEof => {
+            return Err("Server closed the connection".to_string());
+        }
+        Err(e) => return Err(format!("Failed to receive: {}", e)),
+    };
+
+    ServerMessage::decode(payload.as_slice())
+        .map(|response| response.message)
+        .map_err(|e| format!("Failed to decode response: {}", e))
+}
@@ -1,169 +1,2148 @@
 // Importing necessary modules and crates
-use crate::message::{EchoMessage, ClientMessage, ServerMessage, AddResponse};
+use crate::admin::{self, ADMIN_TOKEN_HEADER};
+use crate::buffer::AdaptiveBuffer;
+use crate::cache::{EchoDedup, ResponseCache};
+use crate::client::PUSHED_HEADER;
+use crate::clock::{Clock, SystemClock};
+use crate::compute;
+#[cfg(feature = "config")]
+use crate::config::Config;
+#[cfg(feature = "console")]
+use crate::console;
+use crate::events::{
+    CloseReason, ConnectionObserver, EventBus, HandledMessage, Observers, ServerEvent,
+};
+use crate::extract::StateMap;
+use crate::framing::{self, FrameDecoder, DEFAULT_MAX_FRAME_SIZE, HEADER_LEN};
+use crate::handler::{
+    error_response, AddHandler, CalculateHandler, DivideHandler, EchoHandler, Handler, KvHandler,
+    MultiplyHandler, RequestContext, Router, SubtractHandler,
+};
+use crate::histogram::DEFAULT_SCALE;
+use crate::history::EchoHistory;
+use crate::info;
+use crate::kv::{KvPersistence, KvStore};
 use crate::message::client_message::Message as ClientMessageType;
 use crate::message::server_message::Message as ServerMessageType;
+use crate::message::{
+    ClientMessage, ConnectionChurnResponse, DotProductResponse, ErrorCode, MatrixMultiplyResponse,
+    PingRequest, PongResponse, Publication, PublishResponse, RecentErrorsResponse,
+    RunHistoryResponse, ServerBusy, ServerInfoResponse, ServerMessage, SetLogLevelResponse,
+    SubscribeResponse, TopMessagesResponse, UnsubscribeResponse,
+};
+use crate::metrics::Metrics;
+use crate::ndjson;
+use crate::pool::{PoolHandle, ThreadOptions, WorkerPool};
+use crate::privileges::PrivilegeDrop;
+use crate::pubsub::{Topics, OUTBOUND_CAPACITY};
+use crate::recent_errors::RecentErrors;
+use crate::resources::{ResourceLimits, ResourceUsage};
+use crate::rng::{self, Rng};
+use crate::run_history::RunHistory;
+use crate::self_test::{self, SelfTestReport};
+use crate::session::{ClientInfo, Session};
+use crate::stats::{message_type_name, MessageStats, UNKNOWN_MESSAGE_TYPE};
 use log::{error, info, warn};
 use prost::Message;
 use std::{
+    collections::HashMap,
+    error::Error,
+    fmt,
     io::{self, ErrorKind, Read, Write},
-    net::{TcpListener, TcpStream},
+    net::{Shutdown, SocketAddr, TcpListener, TcpStream},
+    panic::{self, AssertUnwindSafe},
+    path::PathBuf,
     sync::{
-        atomic::{AtomicBool, Ordering},
-        Arc,
+        atomic::{AtomicBool, AtomicU64, Ordering},
+        mpsc::{self, Receiver, SyncSender},
+        Arc, Condvar, Mutex,
     },
-    thread,
-    time::Duration,
+    thread::{self, JoinHandle},
+    time::{Duration, Instant},
 };
 
+// How often blocked loops wake up to check whether the server is still running
+const POLL_INTERVAL: Duration = Duration::from_millis(100);
+
+// How long an outbound server waits before dialing the remote endpoint again
+const REDIAL_INTERVAL: Duration = Duration::from_secs(1);
+
+// Size every connection's read buffer starts out with, before adapting to its messages
+const INITIAL_READ_BUFFER: usize = 512;
+
+// Messages a pooled connection handles before giving its worker to a waiting connection
+const SLICE_MESSAGES: usize = 16;
+
+// Errors that stop a running server
+#[derive(Debug)]
+pub enum ServerError {
+    Io(io::Error), // Setting up the server failed
+    AcceptLoopFailed {
+        errors: u32,     // Number of consecutive accept errors
+        last: io::Error, // The last of them
+    },
+}
+
+impl fmt::Display for ServerError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ServerError::Io(e) => write!(f, "{}", e),
+            ServerError::AcceptLoopFailed { errors, last } => write!(
+                f,
+                "Accepting connections failed {} times in a row: {}",
+                errors, last
+            ),
+        }
+    }
+}
+
+impl Error for ServerError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            ServerError::Io(e) => Some(e),
+            ServerError::AcceptLoopFailed { last, .. } => Some(last),
+        }
+    }
+}
+
+impl From<io::Error> for ServerError {
+    fn from(e: io::Error) -> Self {
+        ServerError::Io(e)
+    }
+}
+
+// How the accept loop reacts to errors other than a single connection going away
+#[derive(Debug, Clone)]
+pub struct AcceptErrorPolicy {
+    pub max_consecutive_errors: u32, // Errors in a row after which run() gives up, 0 never does
+    pub initial_backoff: Duration,   // Pause after the first error, doubled on every further one
+    pub max_backoff: Duration,       // Upper bound of the pause
+}
+
+impl Default for AcceptErrorPolicy {
+    fn default() -> Self {
+        AcceptErrorPolicy {
+            max_consecutive_errors: 10,
+            initial_backoff: POLL_INTERVAL,
+            max_backoff: Duration::from_secs(5),
+        }
+    }
+}
+
+// What happens to connections accepted beyond the connection limit
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum OverflowPolicy {
+    #[default]
+    SendBusy, // Tell the client with a ServerBusy message, then close the connection
+    Refuse, // Close the connection right away
+}
+
+// Transport-level probing of connections that went quiet, to detect silently dead peers
+#[derive(Debug, Clone)]
+pub struct KeepalivePolicy {
+    pub idle: Duration,     // Silence after which the peer is probed
+    pub interval: Duration, // Pause between unanswered probes
+    pub retries: u32,       // Unanswered probes after which the connection is closed
+}
+
+impl Default for KeepalivePolicy {
+    fn default() -> Self {
+        KeepalivePolicy {
+            idle: Duration::from_secs(60),
+            interval: Duration::from_secs(10),
+            retries: 3,
+        }
+    }
+}
+
+// Bounds of the headers a request may carry, within the size limit of the whole message
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct HeaderLimits {
+    pub max_count: usize, // Headers a request may carry
+    pub max_bytes: usize, // Bytes of all header names and values together
+}
+
+impl Default for HeaderLimits {
+    fn default() -> Self {
+        HeaderLimits {
+            max_count: 32,
+            max_bytes: 8 * 1024,
+        }
+    }
+}
+
+impl HeaderLimits {
+    /// Describes how `headers` exceed the limits, if they do
+    fn check(&self, headers: &HashMap<String, String>) -> Result<(), String> {
+        if headers.len() > self.max_count {
+            return Err(format!(
+                "{} headers exceed the limit of {}",
+                headers.len(),
+                self.max_count
+            ));
+        }
+        let bytes: usize = headers
+            .iter()
+            .map(|(name, value)| name.len() + value.len())
+            .sum();
+        if bytes > self.max_bytes {
+            return Err(format!(
+                "{} bytes of headers exceed the limit of {}",
+                bytes, self.max_bytes
+            ));
+        }
+        Ok(())
+    }
+}
+
+impl KeepalivePolicy {
+    /// Enables keepalive probes on `stream` as described by the policy
+    fn apply(&self, stream: &TcpStream) -> io::Result<()> {
+        let keepalive = socket2::TcpKeepalive::new().with_time(self.idle);
+        #[cfg(any(target_os = "linux", target_os = "macos", windows))]
+        let keepalive = keepalive.with_interval(self.interval);
+        #[cfg(any(target_os = "linux", target_os = "macos"))]
+        let keepalive = keepalive.with_retries(self.retries);
+        socket2::SockRef::from(stream).set_tcp_keepalive(&keepalive)
+    }
+}
+
+// State shared between the server and all of its client threads
+struct Shared {
+    is_running: AtomicBool,           // Flag to indicate if the server is running
+    started_at_ms: AtomicU64,         // When the server started running, since the Unix epoch
+    history: Arc<Mutex<EchoHistory>>, // Recent echo messages shared by all clients
+    router: Router,                   // Handlers of the requests that are not built into the server
+    state: StateMap,                  // Values handed to handlers, by type
+    topics: Topics,                   // Subscribers of every pub/sub topic
+    kv: Arc<KvStore>,                 // Key-value store shared by all clients
+    recent_errors: Mutex<RecentErrors>, // Recent errors met while serving clients
+    run_history: Mutex<RunHistory>,   // Starts and stops of this and earlier runs
+    events: EventBus,                 // Subscribers to connection events
+    observers: Observers,             // Callbacks run as connections come, go and send messages
+    metrics: Metrics,                 // Server activity counters
+    stats: MessageStats,              // Per-message-type statistics
+    sessions: Mutex<HashMap<u64, Arc<Session>>>, // Sessions of the connected clients, by id
+    streams: Mutex<HashMap<u64, Arc<TcpStream>>>, // Handles to the client connections, by session id
+    outbounds: Mutex<HashMap<u64, SyncSender<ServerMessage>>>, // Queues of messages pushed to the clients, by session id
+    open_connections: Mutex<usize>, // Connections accepted and not torn down yet
+    connection_closed: Condvar,     // Signalled whenever a connection is torn down
+    next_session_id: AtomicU64,     // Identifier handed to the next connection
+    rng_seed: Option<u64>,          // Seed all random streams derive from, if reproducible
+    close_timeout: Duration,        // How long closing may wait for responses to be delivered
+    rng: Mutex<Rng>,                // Random stream of the server itself, for jitter
+    session_summary: bool,          // Whether clients are sent a SessionSummary before being closed
+    cache: ResponseCache,           // Responses of pure handlers, for repeated requests
+    read_buffer: (usize, usize),    // Bounds of the per-connection read buffer size
+    keepalive: Option<KeepalivePolicy>, // Probing of idle connections, if enabled
+    admin_token: Option<String>,    // Token authorizing admin messages, if enabled
+    read_timeout: Option<Duration>, // How long a client may stay silent, if limited
+    write_timeout: Option<Duration>, // How long writing a response may block, if limited
+    echo_dedup_window: Option<Duration>, // How long echoes are remembered for deduplication, if at all
+    resolve_peer_names: bool,            // Whether the names of clients are looked up
+    heartbeat_interval: Option<Duration>, // Silence after which clients are pinged, if they are
+    max_message_size: usize,             // Largest message a client may send
+    header_limits: HeaderLimits,         // Bounds of the headers of a request
+    threads: ThreadOptions,              // How connection, worker and resolver threads are spawned
+    clock: Arc<dyn Clock>,               // Time TTLs and statistics windows are measured against
+}
+
 // Define the Client struct to represent a connected client
 struct Client {
-    stream: TcpStream, // The TCP stream associated with this client
+    stream: Arc<TcpStream>, // The TCP stream associated with this client, also tracked by the server
+    session: Arc<Session>,  // Server-side state of the connection
+    shared: Arc<Shared>,    // State shared with the server
+    buffer: AdaptiveBuffer, // Incoming data, sized to the messages this client sends
+    frames: FrameDecoder,   // Received bytes not handled yet, kept across slices
+    last_read: Instant,     // When the client last sent something
+    pinged_at: Option<Instant>, // When the silent client was sent a heartbeat ping, if it was
+    pings_sent: u64,        // Heartbeat pings sent so far, the nonce of the next one
+    echo_dedup: Option<EchoDedup>, // Echoes answered recently, if deduplicated
+    outbound_sender: SyncSender<ServerMessage>, // Queues messages pushed to the client, e.g. publications
+    outbound: Receiver<ServerMessage>, // Messages pushed to the client, sent in between responses
 }
 
 impl Client {
     /// Creates a new client instance
-    pub fn new(stream: TcpStream) -> Self {
-        Client { stream }
+    pub fn new(stream: Arc<TcpStream>, session: Arc<Session>, shared: Arc<Shared>) -> Self {
+        let (min, max) = shared.read_buffer;
+        let echo_dedup = shared
+            .echo_dedup_window
+            .map(|window| EchoDedup::with_clock(window, Arc::clone(&shared.clock)));
+        let frames = FrameDecoder::new(shared.max_message_size);
+        let (outbound_sender, outbound) = mpsc::sync_channel(OUTBOUND_CAPACITY);
+        let last_read = shared.clock.now();
+        Client {
+            stream,
+            session,
+            shared,
+            buffer: AdaptiveBuffer::new(INITIAL_READ_BUFFER, min, max),
+            frames,
+            last_read,
+            pinged_at: None,
+            pings_sent: 0,
+            echo_dedup,
+            outbound_sender,
+            outbound,
+        }
+    }
+
+    /// Handles communication with the client until the connection ends
+    pub fn handle(&mut self) -> CloseReason {
+        if let Err(reason) = self.prepare() {
+            return reason;
+        }
+        loop {
+            if let Some(reason) = self.handle_slice(&|| false) {
+                return reason;
+            }
+        }
     }
 
-    /// Handles communication with the client
-    pub fn handle(&mut self) -> io::Result<()> {
-        let mut buffer = [0; 512]; // Buffer to store incoming data
-        let stream_clone = self.stream.try_clone()?; // Clone the stream for use in a separate thread
+    /// Configures the connection before anything is read from it
+    fn prepare(&mut self) -> Result<(), CloseReason> {
+        // Wake up periodically so that a server shutdown is noticed
+        if let Err(e) = self.stream.set_read_timeout(Some(POLL_INTERVAL)) {
+            error!("Failed to set read timeout: {}", e);
+            return Err(CloseReason::ReadError);
+        }
+        if let Err(e) = self.stream.set_write_timeout(self.shared.write_timeout) {
+            error!("Failed to set write timeout: {}", e);
+            return Err(CloseReason::WriteError);
+        }
+        if let Some(keepalive) = &self.shared.keepalive {
+            if let Err(e) = keepalive.apply(&self.stream) {
+                warn!("Failed to enable keepalive probes: {}", e);
+            }
+        }
+        Ok(())
+    }
 
-        // Spawn a thread for handling client communication
-        thread::spawn(move || {
-            let mut local_stream = stream_clone;
+    /// Handles communication with the client until the connection ends or, once `yield_to`
+    /// says others are waiting, until SLICE_MESSAGES messages were handled or the client
+    /// stayed silent for a poll interval. Returns `None` when yielding.
+    fn handle_slice(&mut self, yield_to: &dyn Fn() -> bool) -> Option<CloseReason> {
+        let mut handled = 0;
+        loop {
+            if !self.shared.is_running.load(Ordering::SeqCst) || self.session.is_cancelled() {
+                return Some(self.session.close_reason().unwrap_or(CloseReason::Shutdown));
+            }
+
+            // Send what was pushed to the client since it was last served
+            while let Ok(message) = self.outbound.try_recv() {
+                if let Err(reason) = self.send_pushed(message) {
+                    return Some(reason);
+                }
+            }
+
+            // Handle every message received so far, accounting for each of them in the
+            // message statistics
             loop {
-                // Read data from the client
-                let bytes_read = match local_stream.read(&mut buffer) {
-                    Ok(bytes_read) => bytes_read,
+                let frame = match self.frames.next_frame() {
+                    Ok(Some(frame)) => frame,
+                    Ok(None) => break,
                     Err(e) => {
-                        error!("Failed to read from stream: {}", e);
-                        break;
+                        warn!("Rejecting client at {}: {}", self.session.addr(), e);
+                        self.record_error("frame", e.to_string());
+
+                        // Skipping the payload could mean reading gigabytes, tell the client
+                        // why and hang up instead
+                        let response = error_response(ErrorCode::MessageTooLarge, e.to_string());
+                        if let Err(reason) = self.send_response(response, HashMap::new()) {
+                            return Some(reason);
+                        }
+                        return Some(CloseReason::ProtocolViolation);
                     }
                 };
 
-                // If no bytes were read, the client has disconnected
-                if bytes_read == 0 {
-                    info!("Client disconnected");
-                    break;
+                let started = Instant::now();
+                let (message_type, result) = self.process(&frame);
+                let failed = !matches!(result, Ok(true) | Err(CloseReason::ClientGoodbye));
+                let (size, elapsed) = (HEADER_LEN + frame.len(), started.elapsed());
+                self.session.record_message(message_type, size, failed);
+                self.shared.stats.record(message_type, elapsed, failed);
+                let message = HandledMessage {
+                    addr: self.session.addr(),
+                    session_id: self.session.id(),
+                    message_type,
+                    size,
+                    elapsed,
+                    failed,
+                };
+                self.shared
+                    .observers
+                    .notify(|observer| observer.on_message(&message));
+                if self.shared.events.wants_messages() {
+                    self.shared.events.emit(ServerEvent::MessageHandled {
+                        message,
+                        timestamp: self.shared.clock.system_time(),
+                    });
+                }
+                if let Err(reason) = result {
+                    return Some(reason);
                 }
 
-                // Decode the received message as a ClientMessage
-                if let Ok(client_message) = ClientMessage::decode(&buffer[..bytes_read]) {
-                    // Check if the message is an AddRequest
-                    if let Some(ClientMessageType::AddRequest(add_request)) = client_message.message {
-                        let result = add_request.a + add_request.b; // Perform the addition
-                        let add_response = AddResponse { result }; // Create the response message
-
-                        let server_message = ServerMessage {
-                            message: Some(ServerMessageType::AddResponse(add_response)),
-                        };
+                handled += 1;
+                if handled >= SLICE_MESSAGES && yield_to() {
+                    return None;
+                }
+            }
 
-                        // Encode the response and send it back to the client
-                        let payload = server_message.encode_to_vec();
-                        if let Err(e) = local_stream.write_all(&payload) {
-                            error!("Failed to write AddResponse to stream: {}", e);
-                            break;
+            // Read data from the client
+            let bytes_read = match (&*self.stream).read(&mut self.buffer) {
+                Ok(bytes_read) => bytes_read,
+                Err(ref e) if is_keepalive_timeout(e) => {
+                    warn!("Peer stopped answering keepalive probes");
+                    return Some(CloseReason::PeerUnresponsive);
+                }
+                Err(ref e) if matches!(e.kind(), ErrorKind::WouldBlock | ErrorKind::TimedOut) => {
+                    if let Some(timeout) = self.shared.read_timeout {
+                        if self.silent_for() >= timeout {
+                            warn!(
+                                "Disconnecting client at {}: silent for more than {:?}",
+                                self.session.addr(),
+                                timeout
+                            );
+                            return Some(CloseReason::IdleTimeout);
+                        }
+                    }
+                    if let Some(interval) = self.shared.heartbeat_interval {
+                        if let Err(reason) = self.heartbeat(interval) {
+                            return Some(reason);
                         }
                     }
+                    if yield_to() {
+                        return None;
+                    }
+                    continue;
+                }
+                Err(e) => {
+                    error!("Failed to read from stream: {}", e);
+                    self.record_error("read", format!("Failed to read from stream: {}", e));
+                    return Some(CloseReason::ReadError);
                 }
+            };
+
+            // If no bytes were read, the client has disconnected
+            if bytes_read == 0 {
+                return Some(CloseReason::ClientEof);
+            }
+            self.last_read = self.shared.clock.now();
+            self.pinged_at = None;
+
+            if self.session.messages() == 0 && self.frames.buffered() == 0 {
+                let time_to_first_byte = self.session.connected_at().elapsed();
+                self.shared.metrics.record_first_byte(time_to_first_byte);
+            }
+            self.frames.extend(&self.buffer[..bytes_read]);
+            self.buffer.adapt(bytes_read);
+        }
+    }
+
+    /// Returns how long the client has not sent anything, by the server clock
+    fn silent_for(&self) -> Duration {
+        self.shared
+            .clock
+            .now()
+            .saturating_duration_since(self.last_read)
+    }
+
+    /// Pings a client that stayed silent for `interval`, and gives up on it once it stays
+    /// silent for another `interval` after the ping
+    fn heartbeat(&mut self, interval: Duration) -> Result<(), CloseReason> {
+        let now = self.shared.clock.now();
+        match self.pinged_at {
+            Some(pinged_at) if now.saturating_duration_since(pinged_at) >= interval => {
+                warn!(
+                    "Disconnecting client at {}: no answer to ping within {:?}",
+                    self.session.addr(),
+                    interval
+                );
+                Err(CloseReason::PeerUnresponsive)
+            }
+            None if self.silent_for() >= interval => {
+                let nonce = self.pings_sent;
+                self.pings_sent += 1;
+                self.pinged_at = Some(now);
+                let ping = ServerMessageType::PingRequest(PingRequest { nonce });
+                self.send_response(ping, HashMap::new())
+            }
+            _ => Ok(()),
+        }
+    }
+
+    /// Processes a single received payload, returning its message type and whether it was
+    /// handled without an error response, or why the connection has to be closed
+    fn process(&mut self, data: &[u8]) -> (&'static str, Result<bool, CloseReason>) {
+        let session = Arc::clone(&self.session);
+        let _request = session.begin_request();
+
+        // An echo repeated within the dedup window gets the same answer, without being
+        // handled again
+        if let Some(payload) = self.echo_dedup.as_mut().and_then(|dedup| dedup.get(data)) {
+            info!("Answering repeated echo without handling it");
+            return ("echo", self.send_encoded(&payload).map(|_| true));
+        }
+
+        // Every request is wrapped in a ClientMessage, anything else is rejected
+        let client_message = match ClientMessage::decode(data) {
+            Ok(client_message) => client_message,
+            Err(e) => {
+                error!("Failed to decode message: {}", e);
+                let description = format!("Failed to decode {} bytes", data.len());
+                self.record_error("decode", description.clone());
+                let response = error_response(ErrorCode::DecodeFailed, description);
+                let result = self.send_response(response, HashMap::new());
+                return (UNKNOWN_MESSAGE_TYPE, result.map(|_| false));
+            }
+        };
+        let message_type = message_type_name(&client_message.message);
+        match client_message.message {
+            // Nothing to answer, the client is about to hang up
+            Some(ClientMessageType::ClientGoodbye(_)) => {
+                return (message_type, Err(CloseReason::ClientGoodbye));
+            }
+            None => {
+                // Most likely a message type added after this server was built
+                let description = "Unsupported message type".to_string();
+                let response = error_response(ErrorCode::UnknownMessageType, description);
+                let result = self.send_response(response, HashMap::new());
+                return (message_type, result.map(|_| false));
+            }
+            _ => {}
+        }
+
+        // Headers could otherwise take up most of a message the size limit allows
+        if let Err(description) = self.shared.header_limits.check(&client_message.headers) {
+            warn!("Rejecting {} request: {}", message_type, description);
+            self.record_error("headers", description.clone());
+            let response = error_response(ErrorCode::HeadersTooLarge, description);
+            let result = self.send_response(response, HashMap::new());
+            return (message_type, result.map(|_| false));
+        }
+
+        // A panicking handler fails its request, not the connection
+        let headers = &client_message.headers;
+        let request = client_message.message;
+        let response = match panic::catch_unwind(AssertUnwindSafe(|| {
+            self.handle_request(message_type, request, headers)
+        })) {
+            Ok(response) => response,
+            Err(_) => {
+                error!("Handler for {} request panicked", message_type);
+                self.record_error("handler", format!("Handler for {} panicked", message_type));
+                Some(error_response(
+                    ErrorCode::HandlerFailed,
+                    format!("Failed to handle {} request", message_type),
+                ))
+            }
+        };
+
+        let mut succeeded = true;
+        if let Some(message) = response {
+            // A handler refusing the request fails it just like a panicking one
+            succeeded = !matches!(message, ServerMessageType::ErrorResponse(_));
+
+            // Propagate the request headers so metadata such as trace context survives,
+            // credentials are not reflected back and neither is the mark of pushed messages,
+            // which would turn the response into one
+            let mut headers = client_message.headers;
+            headers.remove(ADMIN_TOKEN_HEADER);
+            headers.remove(PUSHED_HEADER);
+            let payload = ServerMessage {
+                message: Some(message),
+                headers,
+            }
+            .encode_to_vec();
+            if let Err(reason) = self.send_encoded(&payload) {
+                return (message_type, Err(reason));
+            }
+            if message_type == "echo" {
+                if let Some(dedup) = &mut self.echo_dedup {
+                    dedup.insert(data, payload);
+                }
+            }
+        }
+
+        (message_type, Ok(succeeded))
+    }
 
-                // Decode the received message as an EchoMessage
-                if let Ok(message) = EchoMessage::decode(&buffer[..bytes_read]) {
-                    info!("Received: {}", message.content);
-                    println!("Received: {}", message.content);
+    /// Handles a decoded request, returning the response to send if there is one
+    fn handle_request(
+        &self,
+        message_type: &'static str,
+        request: Option<ClientMessageType>,
+        headers: &HashMap<String, String>,
+    ) -> Option<ServerMessageType> {
+        // Registered handlers take precedence over the ones built into the server
+        if let Some(handler) = self.shared.router.get(message_type) {
+            let request = request?;
+            let context = RequestContext {
+                message_type,
+                headers,
+                session: &self.session,
+                state: &self.shared.state,
+            };
+            let mut encoded = Vec::new();
+            request.encode(&mut encoded);
+            return self
+                .shared
+                .cache
+                .get_or_compute(message_type, &encoded, || handler.handle(request, &context));
+        }
 
-                    // Echo the message back to the client
-                    let payload = message.encode_to_vec();
-                    if let Err(e) = local_stream.write_all(&payload) {
-                        error!("Failed to write to stream: {}", e);
-                        break;
+        match request {
+            // Routed to the built-in handlers registered by the server
+            Some(ClientMessageType::AddRequest(_)) => None,
+            Some(ClientMessageType::SubtractRequest(_)) => None,
+            Some(ClientMessageType::MultiplyRequest(_)) => None,
+            Some(ClientMessageType::DivideRequest(_)) => None,
+            Some(ClientMessageType::CalculateRequest(_)) => None,
+            Some(ClientMessageType::SetRequest(_)) => None,
+            Some(ClientMessageType::GetRequest(_)) => None,
+            Some(ClientMessageType::DeleteRequest(_)) => None,
+            Some(ClientMessageType::EchoMessage(_)) => None,
+            Some(ClientMessageType::HistoryRequest(history_request)) => {
+                let history = self.shared.history.lock().unwrap();
+                let history_response = history.page(
+                    history_request.limit as usize,
+                    &history_request.continuation_token,
+                );
+                Some(ServerMessageType::HistoryResponse(history_response))
+            }
+            Some(ClientMessageType::TopMessagesRequest(top_messages_request)) => {
+                let top_messages = if self.is_admin(headers) {
+                    self.shared.stats.top(top_messages_request.limit as usize)
+                } else {
+                    TopMessagesResponse {
+                        error: "Unauthorized".to_string(),
+                        ..TopMessagesResponse::default()
                     }
-                    if let Err(e) = local_stream.flush() {
-                        error!("Failed to flush stream: {}", e);
-                        break;
+                };
+                Some(ServerMessageType::TopMessagesResponse(top_messages))
+            }
+            Some(ClientMessageType::DotProductRequest(request)) => {
+                let response = match compute::dot_product(&request.a, &request.b) {
+                    Ok(result) => DotProductResponse {
+                        result,
+                        error: String::new(),
+                    },
+                    Err(e) => DotProductResponse {
+                        result: 0.0,
+                        error: e.to_string(),
+                    },
+                };
+                Some(ServerMessageType::DotProductResponse(response))
+            }
+            Some(ClientMessageType::MatrixMultiplyRequest(request)) => {
+                let a = request.a.unwrap_or_default();
+                let b = request.b.unwrap_or_default();
+                let response = match compute::matrix_multiply(&a, &b) {
+                    Ok(result) => MatrixMultiplyResponse {
+                        result: Some(result),
+                        error: String::new(),
+                    },
+                    Err(e) => MatrixMultiplyResponse {
+                        result: None,
+                        error: e.to_string(),
+                    },
+                };
+                Some(ServerMessageType::MatrixMultiplyResponse(response))
+            }
+            Some(ClientMessageType::SetLogLevelRequest(request)) => {
+                let response = if self.is_admin(headers) {
+                    admin::set_log_level(&request)
+                } else {
+                    SetLogLevelResponse {
+                        previous_level: String::new(),
+                        error: "Unauthorized".to_string(),
                     }
+                };
+                Some(ServerMessageType::SetLogLevelResponse(response))
+            }
+            Some(ClientMessageType::ConnectionChurnRequest(_)) => {
+                let response = if self.is_admin(headers) {
+                    let mut churn = self.shared.metrics.churn_window();
+                    churn.to_response(self.shared.clock.system_time())
                 } else {
-                    error!("Failed to decode message");
-                }
+                    ConnectionChurnResponse {
+                        error: "Unauthorized".to_string(),
+                        ..ConnectionChurnResponse::default()
+                    }
+                };
+                Some(ServerMessageType::ConnectionChurnResponse(response))
+            }
+            Some(ClientMessageType::RecentErrorsRequest(request)) => {
+                let response = if self.is_admin(headers) {
+                    let recent_errors = self.shared.recent_errors.lock().unwrap();
+                    RecentErrorsResponse {
+                        errors: recent_errors.recent(request.limit as usize),
+                        error: String::new(),
+                    }
+                } else {
+                    RecentErrorsResponse {
+                        errors: Vec::new(),
+                        error: "Unauthorized".to_string(),
+                    }
+                };
+                Some(ServerMessageType::RecentErrorsResponse(response))
+            }
+            Some(ClientMessageType::RunHistoryRequest(request)) => {
+                let response = if self.is_admin(headers) {
+                    let run_history = self.shared.run_history.lock().unwrap();
+                    RunHistoryResponse {
+                        runs: run_history.recent(request.limit as usize),
+                        error: String::new(),
+                    }
+                } else {
+                    RunHistoryResponse {
+                        runs: Vec::new(),
+                        error: "Unauthorized".to_string(),
+                    }
+                };
+                Some(ServerMessageType::RunHistoryResponse(response))
+            }
+            Some(ClientMessageType::SubscribeRequest(request)) => {
+                let response = if request.topic.is_empty() {
+                    SubscribeResponse {
+                        error: "Topic must not be empty".to_string(),
+                        ..SubscribeResponse::default()
+                    }
+                } else {
+                    // Replayed publications are queued, and sent after this response
+                    let outbound = self.outbound_sender.clone();
+                    let subscribed = self.shared.topics.subscribe_from(
+                        &request.topic,
+                        self.session.id(),
+                        outbound,
+                        request.from_offset,
+                    );
+                    SubscribeResponse {
+                        topic: request.topic,
+                        error: String::new(),
+                        next_offset: subscribed.next_offset,
+                        replayed: subscribed.replayed as u32,
+                    }
+                };
+                Some(ServerMessageType::SubscribeResponse(response))
+            }
+            Some(ClientMessageType::UnsubscribeRequest(request)) => {
+                let topics = &self.shared.topics;
+                let was_subscribed = topics.unsubscribe(&request.topic, self.session.id());
+                Some(ServerMessageType::UnsubscribeResponse(
+                    UnsubscribeResponse {
+                        topic: request.topic,
+                        was_subscribed,
+                    },
+                ))
+            }
+            Some(ClientMessageType::PublishRequest(request)) => {
+                let response = if request.topic.is_empty() {
+                    PublishResponse {
+                        error: "Topic must not be empty".to_string(),
+                        ..PublishResponse::default()
+                    }
+                } else {
+                    // The acknowledgement is queued, and sent after this response
+                    let receipt = request.ack.then(|| self.outbound_sender.clone());
+                    let publication = Publication {
+                        topic: request.topic,
+                        payload: request.payload,
+                        publisher_id: self.session.id(),
+                        offset: 0, // Numbered by the topic
+                    };
+                    let published = self
+                        .shared
+                        .topics
+                        .publish_with_receipt(publication, receipt);
+                    PublishResponse {
+                        delivered: published.queued as u32,
+                        error: String::new(),
+                        offset: published.offset,
+                    }
+                };
+                Some(ServerMessageType::PublishResponse(response))
+            }
+            Some(ClientMessageType::ServerInfoRequest(_)) => {
+                let started_at_ms = self.shared.started_at_ms.load(Ordering::SeqCst);
+                let run_history = self.shared.run_history.lock().unwrap();
+                Some(ServerMessageType::ServerInfoResponse(ServerInfoResponse {
+                    session_id: self.session.id(),
+                    previous_run_crashed: run_history.previous_run_crashed(),
+                    ..info::server_info(started_at_ms, self.shared.max_message_size)
+                }))
+            }
+            Some(ClientMessageType::PingRequest(ping)) => {
+                Some(ServerMessageType::PongResponse(PongResponse {
+                    nonce: ping.nonce,
+                }))
+            }
+            // Receiving it already proved the client is alive
+            Some(ClientMessageType::PongResponse(_)) => None,
+            // Nothing to answer, the client is about to hang up
+            Some(ClientMessageType::ClientGoodbye(_)) => None,
+            None => None,
+        }
+    }
+
+    /// Sends a response carrying `headers` back to the client
+    fn send_response(
+        &mut self,
+        message: ServerMessageType,
+        headers: HashMap<String, String>,
+    ) -> Result<(), CloseReason> {
+        self.send_message(ServerMessage {
+            message: Some(message),
+            headers,
+        })
+    }
+
+    /// Sends a message to the client
+    fn send_message(&mut self, server_message: ServerMessage) -> Result<(), CloseReason> {
+        self.send_encoded(&server_message.encode_to_vec())
+    }
+
+    /// Sends a message pushed to the client, settling the receipt of a publication
+    fn send_pushed(&mut self, mut server_message: ServerMessage) -> Result<(), CloseReason> {
+        // Marked so that clients can tell it apart from the response they are waiting for
+        server_message
+            .headers
+            .insert(PUSHED_HEADER.to_string(), "true".to_string());
+        let result = self.send_encoded(&server_message.encode_to_vec());
+        if let Some(ServerMessageType::Publication(publication)) = &server_message.message {
+            let topics = &self.shared.topics;
+            topics.settle(publication, self.session.id(), result.is_ok());
+        }
+        result
+    }
+
+    /// Sends an encoded ServerMessage to the client
+    fn send_encoded(&mut self, payload: &[u8]) -> Result<(), CloseReason> {
+        if let Err(e) = framing::write_frame(&mut &*self.stream, payload) {
+            error!("Failed to write response to stream: {}", e);
+            self.record_error("write", format!("Failed to write response: {}", e));
+            return Err(CloseReason::WriteError);
+        }
+        self.session.record_sent(HEADER_LEN + payload.len());
+        if let Err(e) = (&*self.stream).flush() {
+            error!("Failed to flush stream: {}", e);
+            self.record_error("write", format!("Failed to flush response: {}", e));
+            return Err(CloseReason::WriteError);
+        }
+        Ok(())
+    }
+
+    /// Returns true when a request carrying `headers` may use admin messages
+    fn is_admin(&self, headers: &HashMap<String, String>) -> bool {
+        let authorized = admin::is_authorized(self.shared.admin_token.as_deref(), headers);
+        if !authorized {
+            warn!("Rejected admin request from {}", self.session.addr());
+        }
+        authorized
+    }
+
+    /// Keeps an error met on this connection for RecentErrorsRequest
+    fn record_error(&self, kind: &str, message: String) {
+        self.shared
+            .recent_errors
+            .lock()
+            .unwrap()
+            .record(self.session.id(), kind, message);
+    }
+
+    /// Tells the client what happened over the connection before the server closes it
+    fn send_session_summary(&mut self, reason: CloseReason) {
+        let server_message = ServerMessage {
+            message: Some(ServerMessageType::SessionSummary(
+                self.session.summary(reason),
+            )),
+            ..ServerMessage::default()
+        };
+
+        // Best effort, the connection is going away anyway
+        let payload = server_message.encode_to_vec();
+        if let Err(e) = framing::write_frame(&mut &*self.stream, &payload) {
+            error!("Failed to write session summary to stream: {}", e);
+            self.record_error("write", format!("Failed to write session summary: {}", e));
+        }
+    }
 
-                // Clear the buffer to ensure old messages don't interfere
-                local_stream.set_nonblocking(true).unwrap();
-                while local_stream.read(&mut buffer).is_ok() {}
-                local_stream.set_nonblocking(false).unwrap();
+    /// Closes the connection without losing responses still on their way to the client:
+    /// our side is shut down first, then the client's remaining data is consumed until it
+    /// hangs up or `timeout` expires. Closing with unread data would reset the connection
+    /// and could discard what was already written.
+    fn close(&mut self, timeout: Duration) {
+        let deadline = Instant::now() + timeout;
+        let _ = self.stream.set_write_timeout(Some(timeout));
+        if let Err(e) = (&*self.stream)
+            .flush()
+            .and_then(|_| self.stream.shutdown(Shutdown::Write))
+        {
+            // The connection is already gone, there is nothing left to deliver
+            info!("Failed to shut down connection: {}", e);
+            return;
+        }
+
+        let mut buffer = [0; 512];
+        while let Some(remaining) = deadline.checked_duration_since(Instant::now()) {
+            if remaining.is_zero() || self.stream.set_read_timeout(Some(remaining)).is_err() {
+                break;
             }
+            match (&*self.stream).read(&mut buffer) {
+                Ok(bytes_read) if bytes_read > 0 => continue,
+                _ => break,
+            }
+        }
+    }
+}
+
+// Configuration options for the server
+#[derive(Debug, Clone)]
+pub struct ServerConfig {
+    pub history_capacity: usize, // Number of echo messages kept for HistoryRequest, 0 disables it
+    pub recent_errors_capacity: usize, // Number of errors kept for RecentErrorsRequest
+    pub stats_window: Duration,  // Time window covered by TopMessagesRequest statistics
+    pub histogram_scale: i32,    // Bucket scale of the latency histograms, higher is finer
+    pub privileges: PrivilegeDrop, // Privileges given up after binding, before serving clients
+    pub session_summary: bool,   // Send clients a SessionSummary when the server closes them
+    pub response_cache: HashMap<String, Duration>, // Time to live of cached responses, per message type
+    pub accept_errors: AcceptErrorPolicy,          // How persistent accept errors are handled
+    pub rng_seed: Option<u64>, // Makes every random decision reproducible, for tests
+    pub close_timeout: Duration, // How long closing a connection may wait for responses to be delivered
+    pub resource_limits: ResourceLimits, // Resource usage beyond which new connections wait
+    pub read_buffer_min: usize,  // Smallest size a connection's read buffer shrinks to
+    pub read_buffer_max: usize,  // Largest size a connection's read buffer grows to
+    pub keepalive: Option<KeepalivePolicy>, // Probing of idle connections, disabled by default
+    pub admin_token: Option<String>, // Token authorizing admin messages, disabled without one
+    pub worker_threads: Option<usize>, // Threads serving connections, one per connection if unset
+    pub read_timeout: Option<Duration>, // How long a client may stay silent before it is disconnected
+    pub write_timeout: Option<Duration>, // How long writing a response may block before the client is disconnected
+    pub max_connections: Option<usize>,  // Connections served at once, unlimited if unset
+    pub overflow_policy: OverflowPolicy, // What happens to connections beyond max_connections
+    pub echo_dedup_window: Option<Duration>, // Identical echoes within it are answered without being handled again
+    pub resolve_peer_names: bool, // Look the names of clients up by reverse DNS, in the background
+    pub handlers: Router, // Handlers replacing or adding to the built-in ones, by message type
+    pub observers: Observers, // Callbacks run as connections come, go and send messages
+    pub state: StateMap,  // Values handlers can extract with State, by type
+    pub threads: ThreadOptions, // Names and stack sizes of the threads the server spawns
+    pub heartbeat_interval: Option<Duration>, // Silence after which a client is pinged, and then disconnected
+    pub max_message_size: usize, // Largest message a client may send, larger ones are answered with an error
+    pub header_limits: HeaderLimits, // Headers a request may carry, requests with more are answered with an error
+    pub topic_retention: usize,      // Publications retained per topic for replay, 0 retains none
+    pub kv_capacity: usize,          // Keys the shared key-value store holds at most
+    pub kv_persistence: Option<KvPersistence>, // Snapshots of the key-value store, kept in memory only if unset
+    pub run_history: Option<PathBuf>, // File the start and stop of every run are recorded in, kept in memory only if unset
+    pub clock: Arc<dyn Clock>, // Time cache TTLs, dedup and statistics windows are measured against
+}
+
+impl Default for ServerConfig {
+    fn default() -> Self {
+        ServerConfig {
+            history_capacity: 0,
+            recent_errors_capacity: 100,
+            stats_window: Duration::from_secs(60),
+            histogram_scale: DEFAULT_SCALE,
+            privileges: PrivilegeDrop::default(),
+            session_summary: false,
+            response_cache: HashMap::new(),
+            accept_errors: AcceptErrorPolicy::default(),
+            rng_seed: None,
+            close_timeout: Duration::from_secs(1),
+            resource_limits: ResourceLimits::default(),
+            read_buffer_min: 128,
+            read_buffer_max: 64 * 1024,
+            keepalive: None,
+            admin_token: None,
+            worker_threads: None,
+            read_timeout: None,
+            write_timeout: None,
+            max_connections: None,
+            overflow_policy: OverflowPolicy::default(),
+            echo_dedup_window: None,
+            resolve_peer_names: false,
+            handlers: Router::new(),
+            observers: Observers::default(),
+            state: StateMap::new(),
+            threads: ThreadOptions::default(),
+            heartbeat_interval: None,
+            max_message_size: DEFAULT_MAX_FRAME_SIZE,
+            header_limits: HeaderLimits::default(),
+            topic_retention: 0,
+            kv_capacity: 10_000,
+            kv_persistence: None,
+            run_history: None,
+            clock: Arc::new(SystemClock),
+        }
+    }
+}
+
+// Builder for servers that need more than the default configuration
+#[derive(Debug, Clone, Default)]
+pub struct ServerBuilder {
+    config: ServerConfig, // Configuration being assembled
+}
+
+impl ServerBuilder {
+    /// Keeps the last `capacity` echo messages for HistoryRequest
+    pub fn history_capacity(mut self, capacity: usize) -> Self {
+        self.config.history_capacity = capacity;
+        self
+    }
+
+    /// Keeps the last `capacity` errors for RecentErrorsRequest, 0 keeps none
+    pub fn recent_errors_capacity(mut self, capacity: usize) -> Self {
+        self.config.recent_errors_capacity = capacity;
+        self
+    }
+
+    /// Sets the time window covered by TopMessagesRequest statistics
+    pub fn stats_window(mut self, window: Duration) -> Self {
+        self.config.stats_window = window;
+        self
+    }
+
+    /// Sets the bucket scale of the latency histograms, each power of two is split into
+    /// 2^scale buckets
+    pub fn histogram_scale(mut self, scale: i32) -> Self {
+        self.config.histogram_scale = scale;
+        self
+    }
+
+    /// Switches to `user` after binding
+    pub fn user(mut self, user: &str) -> Self {
+        self.config.privileges.user = Some(user.to_string());
+        self
+    }
+
+    /// Switches to `group` after binding, instead of the user's primary group
+    pub fn group(mut self, group: &str) -> Self {
+        self.config.privileges.group = Some(group.to_string());
+        self
+    }
+
+    /// Confines the process to `path` after binding
+    pub fn chroot(mut self, path: impl Into<PathBuf>) -> Self {
+        self.config.privileges.chroot = Some(path.into());
+        self
+    }
+
+    /// Sends clients a SessionSummary before the server closes their connection
+    pub fn session_summary(mut self, enabled: bool) -> Self {
+        self.config.session_summary = enabled;
+        self
+    }
+
+    /// Caches responses to `message_type` requests for `ttl`. Only handlers registered as
+    /// cacheable are, such as the built-in "add", "divide" or "get", or those given to
+    /// `cacheable_handler`.
+    pub fn cache_responses(mut self, message_type: &str, ttl: Duration) -> Self {
+        self.config
+            .response_cache
+            .insert(message_type.to_string(), ttl);
+        self
+    }
+
+    /// Sets how the accept loop backs off on errors and when it gives up
+    pub fn accept_error_policy(mut self, policy: AcceptErrorPolicy) -> Self {
+        self.config.accept_errors = policy;
+        self
+    }
+
+    /// Derives all randomness from `seed`, so that runs can be reproduced
+    pub fn rng_seed(mut self, seed: u64) -> Self {
+        self.config.rng_seed = Some(seed);
+        self
+    }
+
+    /// Measures cache TTLs, the echo dedup window, statistics windows and connection churn
+    /// against `clock` instead of the system clock, for tests. Socket timeouts keep real time.
+    pub fn clock(mut self, clock: impl Clock + 'static) -> Self {
+        self.config.clock = Arc::new(clock);
+        self
+    }
+
+    /// Disconnects clients that send nothing for `timeout`
+    pub fn read_timeout(mut self, timeout: Duration) -> Self {
+        self.config.read_timeout = Some(timeout);
+        self
+    }
+
+    /// Disconnects clients whose responses cannot be written within `timeout`, typically
+    /// because they stopped reading them
+    pub fn write_timeout(mut self, timeout: Duration) -> Self {
+        self.config.write_timeout = Some(timeout);
+        self
+    }
+
+    /// Serves at most `max` connections at once, turning away the others as `policy` says
+    pub fn max_connections(mut self, max: usize, policy: OverflowPolicy) -> Self {
+        self.config.max_connections = Some(max);
+        self.config.overflow_policy = policy;
+        self
+    }
+
+    /// Answers echoes identical to one the same client sent within `window` without
+    /// handling them again, so that they are not recorded in the history twice
+    pub fn echo_dedup(mut self, window: Duration) -> Self {
+        self.config.echo_dedup_window = Some(window);
+        self
+    }
+
+    /// Looks up the name of every client by reverse DNS, without delaying its requests
+    pub fn resolve_peer_names(mut self, enabled: bool) -> Self {
+        self.config.resolve_peer_names = enabled;
+        self
+    }
+
+    /// Sets how long closing a connection may wait for responses to be delivered
+    pub fn close_timeout(mut self, timeout: Duration) -> Self {
+        self.config.close_timeout = timeout;
+        self
+    }
+
+    /// Sets the resource usage beyond which new connections are left waiting
+    pub fn resource_limits(mut self, limits: ResourceLimits) -> Self {
+        self.config.resource_limits = limits;
+        self
+    }
+
+    /// Sets the bounds within which per-connection read buffers adapt to message sizes
+    pub fn read_buffer_size(mut self, min: usize, max: usize) -> Self {
+        self.config.read_buffer_min = min;
+        self.config.read_buffer_max = max;
+        self
+    }
+
+    /// Probes connections that stay silent for too long and closes them once the peer
+    /// stops answering
+    pub fn keepalive(mut self, policy: KeepalivePolicy) -> Self {
+        self.config.keepalive = Some(policy);
+        self
+    }
+
+    /// Enables admin messages for requests carrying `token` in their admin-token header
+    pub fn admin_token(mut self, token: &str) -> Self {
+        self.config.admin_token = Some(token.to_string());
+        self
+    }
+
+    /// Serves connections on a pool of `threads` worker threads. While connections wait for
+    /// a worker, busy connections take turns so that none of them is starved.
+    pub fn worker_threads(mut self, threads: usize) -> Self {
+        self.config.worker_threads = Some(threads);
+        self
+    }
+
+    /// Pings clients that stay silent for `interval` and disconnects those that stay silent
+    /// for another `interval`, pruning dead connections that TCP does not notice, e.g. behind
+    /// NATs. Checked every poll interval.
+    pub fn heartbeat(mut self, interval: Duration) -> Self {
+        self.config.heartbeat_interval = Some(interval);
+        self
+    }
+
+    /// Accepts messages of up to `size` bytes. A client announcing a larger one is sent an
+    /// ErrorResponse and disconnected.
+    pub fn max_message_size(mut self, size: usize) -> Self {
+        self.config.max_message_size = size;
+        self
+    }
+
+    /// Accepts requests carrying up to `max_count` headers of `max_bytes` in total, names
+    /// included. Requests with more are answered with a HEADERS_TOO_LARGE ErrorResponse.
+    pub fn header_limits(mut self, max_count: usize, max_bytes: usize) -> Self {
+        self.config.header_limits = HeaderLimits {
+            max_count,
+            max_bytes,
+        };
+        self
+    }
+
+    /// Retains the last `messages` publications of every topic, which subscribers can replay
+    /// from an offset, e.g. to catch up after reconnecting
+    pub fn topic_retention(mut self, messages: usize) -> Self {
+        self.config.topic_retention = messages;
+        self
+    }
+
+    /// Lets the shared key-value store hold at most `capacity` keys, setting further ones
+    /// is rejected until keys are deleted
+    pub fn kv_capacity(mut self, capacity: usize) -> Self {
+        self.config.kv_capacity = capacity;
+        self
+    }
+
+    /// Loads the key-value store from the snapshot at `path` when the server is created,
+    /// and writes it back every `interval` it changed as well as when the server stops
+    pub fn kv_persistence(mut self, path: impl Into<PathBuf>, interval: Duration) -> Self {
+        self.config.kv_persistence = Some(KvPersistence {
+            path: path.into(),
+            interval,
         });
+        self
+    }
 
-        Ok(())
+    /// Records when every run of the server started and stopped in the file at `path`, so
+    /// that a run which never stopped cleanly is reported as crashed by the next one
+    pub fn run_history(mut self, path: impl Into<PathBuf>) -> Self {
+        self.config.run_history = Some(path.into());
+        self
+    }
+
+    /// Names the threads the server spawns "<prefix>-<role>", e.g. "<prefix>-worker-0"
+    pub fn thread_name_prefix(mut self, prefix: &str) -> Self {
+        self.config.threads.name_prefix = prefix.to_string();
+        self
+    }
+
+    /// Gives every thread the server spawns a stack of `size` bytes instead of the platform
+    /// default, for deployments running many of them on little memory
+    pub fn thread_stack_size(mut self, size: usize) -> Self {
+        self.config.threads.stack_size = Some(size);
+        self
+    }
+
+    /// Handles requests of `message_type` with `handler`, instead of the built-in handler
+    pub fn handler(mut self, message_type: &str, handler: impl Handler + 'static) -> Self {
+        self.config.handlers.register(message_type, handler);
+        self
+    }
+
+    /// Handles requests of `message_type` with `handler` like `handler`, declaring that its
+    /// responses only depend on the request, so that `cache_responses` may cache them
+    pub fn cacheable_handler(
+        mut self,
+        message_type: &str,
+        handler: impl Handler + 'static,
+    ) -> Self {
+        self.config
+            .handlers
+            .register_cacheable(message_type, handler);
+        self
+    }
+
+    /// Runs the callbacks of `observer` as clients connect, disconnect and send messages,
+    /// e.g. for auditing or custom metrics
+    pub fn observer(mut self, observer: impl ConnectionObserver + 'static) -> Self {
+        self.config.observers.add(Arc::new(observer));
+        self
+    }
+
+    /// Makes `value` available to handler functions taking a `State<T>` parameter, replacing
+    /// any previous value of its type
+    pub fn state<T: Send + Sync + 'static>(mut self, value: T) -> Self {
+        self.config.state.insert(value);
+        self
+    }
+
+    /// Binds the server to the specified address
+    pub fn bind(self, addr: &str) -> io::Result<Server> {
+        Server::with_config(addr, self.config)
+    }
+
+    /// Serves connections accepted by `listener`, bound elsewhere
+    pub fn listener(self, listener: TcpListener) -> io::Result<Server> {
+        Server::from_listener(listener, self.config)
+    }
+
+    /// Serves connections on the socket the service manager passed to the process, or binds
+    /// to `addr` if it passed none, e.g. when started by hand
+    #[cfg(feature = "systemd")]
+    pub fn bind_activated(self, addr: &str) -> io::Result<Server> {
+        match crate::activation::listener()? {
+            Some(listener) => self.listener(listener),
+            None => self.bind(addr),
+        }
     }
 }
 
 // Define the Server struct to represent the server
 pub struct Server {
-    listener: TcpListener, // TCP listener for incoming connections
-    is_running: Arc<AtomicBool>, // Flag to indicate if the server is running
+    listener: TcpListener,                 // TCP listener for incoming connections
+    shared: Arc<Shared>,                   // State shared with the client threads
+    privileges: PrivilegeDrop,             // Privileges to drop before serving the first client
+    accept_errors: AcceptErrorPolicy,      // How persistent accept errors are handled
+    resource_limits: ResourceLimits,       // Resource usage beyond which connections wait
+    kv_persistence: Option<KvPersistence>, // Where the key-value store is snapshotted, if it is
+    worker_threads: Option<usize>,         // Size of the pool serving connections, if bounded
+    max_connections: Option<usize>,        // Connections served at once, if limited
+    overflow_policy: OverflowPolicy,       // What happens to connections beyond the limit
 }
 
 impl Server {
     /// Creates a new server instance
     pub fn new(addr: &str) -> io::Result<Self> {
+        Self::with_config(addr, ServerConfig::default())
+    }
+
+    /// Creates a new server instance serving connections on `pool_size` worker threads
+    pub fn new_with_pool(addr: &str, pool_size: usize) -> io::Result<Self> {
+        Self::with_config(
+            addr,
+            ServerConfig {
+                worker_threads: Some(pool_size),
+                ..ServerConfig::default()
+            },
+        )
+    }
+
+    /// Creates a new server instance with the given configuration
+    pub fn with_config(addr: &str, config: ServerConfig) -> io::Result<Self> {
         let listener = TcpListener::bind(addr)?; // Bind to the specified address
-        let is_running = Arc::new(AtomicBool::new(false));
-        Ok(Server { listener, is_running })
+        Self::from_listener(listener, config)
+    }
+
+    /// Creates a new server instance from the settings of a deployment, bound to its address
+    /// and logging at its level. TLS has to be terminated in front of the server, settings
+    /// for it are rejected.
+    #[cfg(feature = "config")]
+    pub fn from_config(config: Config) -> io::Result<Self> {
+        let Some(address) = &config.address else {
+            return Err(io::Error::new(
+                ErrorKind::InvalidInput,
+                "No address configured",
+            ));
+        };
+        if config.tls_cert.is_some() || config.tls_key.is_some() {
+            return Err(io::Error::new(
+                ErrorKind::Unsupported,
+                "TLS is not supported by the server",
+            ));
+        }
+        if let Some(level) = config.log_level {
+            log::set_max_level(level);
+        }
+        Self::with_config(address, config.server_config())
+    }
+
+    /// Creates a new server instance accepting connections on `listener`
+    pub fn from_listener(listener: TcpListener, mut config: ServerConfig) -> io::Result<Self> {
+        // Resolved now, the working directory may change while the server runs, e.g. when
+        // the process detaches from its terminal
+        if let Some(persistence) = &mut config.kv_persistence {
+            persistence.path = std::path::absolute(&persistence.path)?;
+        }
+        if let Some(path) = &mut config.run_history {
+            *path = std::path::absolute(&*path)?;
+        }
+        let history = Arc::new(Mutex::new(EchoHistory::new(config.history_capacity)));
+        let mut router = Router::new();
+        router.register_cacheable("add", AddHandler);
+        router.register_cacheable("subtract", SubtractHandler);
+        router.register_cacheable("multiply", MultiplyHandler);
+        router.register_cacheable("divide", DivideHandler);
+        router.register_cacheable("calculate", CalculateHandler);
+        let kv = Arc::new(match &config.kv_persistence {
+            Some(persistence) => KvStore::load(&persistence.path, config.kv_capacity)?,
+            None => KvStore::new(config.kv_capacity),
+        });
+        for message_type in ["set", "delete"] {
+            router.register(message_type, KvHandler::new(Arc::clone(&kv)));
+        }
+        // Reading has no side effects, a cached value is at most its TTL out of date
+        router.register_cacheable("get", KvHandler::new(Arc::clone(&kv)));
+        router.register("echo", EchoHandler::new(Arc::clone(&history)));
+        router.merge(config.handlers);
+        let run_history = match &config.run_history {
+            Some(path) => RunHistory::load(path)?,
+            None => RunHistory::new(),
+        };
+        let cache = ResponseCache::with_clock(
+            &config.response_cache,
+            |message_type| router.is_cacheable(message_type),
+            Arc::clone(&config.clock),
+        );
+        let shared = Arc::new(Shared {
+            is_running: AtomicBool::new(false),
+            started_at_ms: AtomicU64::new(0),
+            history,
+            router,
+            state: config.state,
+            topics: Topics::with_retention(config.topic_retention),
+            kv,
+            recent_errors: Mutex::new(RecentErrors::new(config.recent_errors_capacity)),
+            run_history: Mutex::new(run_history),
+            events: EventBus::default(),
+            observers: config.observers,
+            metrics: Metrics::with_clock(config.histogram_scale, Arc::clone(&config.clock)),
+            stats: MessageStats::with_clock(
+                config.stats_window,
+                config.histogram_scale,
+                Arc::clone(&config.clock),
+            ),
+            sessions: Mutex::new(HashMap::new()),
+            streams: Mutex::new(HashMap::new()),
+            outbounds: Mutex::new(HashMap::new()),
+            open_connections: Mutex::new(0),
+            connection_closed: Condvar::new(),
+            next_session_id: AtomicU64::new(1),
+            rng_seed: config.rng_seed,
+            close_timeout: config.close_timeout,
+            // Stream 0 is free for the server, session ids start at 1
+            rng: Mutex::new(Rng::new(rng::stream_seed(config.rng_seed, 0))),
+            session_summary: config.session_summary,
+            cache,
+            read_buffer: (config.read_buffer_min, config.read_buffer_max),
+            keepalive: config.keepalive,
+            admin_token: config.admin_token,
+            read_timeout: config.read_timeout,
+            write_timeout: config.write_timeout,
+            echo_dedup_window: config.echo_dedup_window,
+            resolve_peer_names: config.resolve_peer_names,
+            threads: config.threads,
+            heartbeat_interval: config.heartbeat_interval,
+            max_message_size: config.max_message_size,
+            header_limits: config.header_limits,
+            clock: config.clock,
+        });
+        Ok(Server {
+            listener,
+            shared,
+            privileges: config.privileges,
+            accept_errors: config.accept_errors,
+            resource_limits: config.resource_limits,
+            kv_persistence: config.kv_persistence,
+            worker_threads: config.worker_threads,
+            max_connections: config.max_connections,
+            overflow_policy: config.overflow_policy,
+        })
+    }
+
+    /// Describes this build of the server, as answered to ServerInfoRequest
+    pub fn server_info(&self) -> ServerInfoResponse {
+        let started_at_ms = self.shared.started_at_ms.load(Ordering::SeqCst);
+        ServerInfoResponse {
+            previous_run_crashed: self
+                .shared
+                .run_history
+                .lock()
+                .unwrap()
+                .previous_run_crashed(),
+            ..info::server_info(started_at_ms, self.shared.max_message_size)
+        }
+    }
+
+    /// Returns the address the server is listening on, useful after binding to port 0
+    pub fn local_addr(&self) -> io::Result<SocketAddr> {
+        self.listener.local_addr()
+    }
+
+    /// Returns a builder to configure a new server
+    pub fn builder() -> ServerBuilder {
+        ServerBuilder::default()
     }
 
-    /// Runs the server, accepting and handling client connections
-    pub fn run(&self) -> io::Result<()> {
-        self.is_running.store(true, Ordering::SeqCst); // Set the server as running
+    /// Runs the server on a thread of its own, returning once it accepts connections, or with
+    /// the error it failed with before. The server runs until the handle stops it or is
+    /// dropped.
+    pub fn start(self) -> Result<ServerHandle, ServerError> {
+        let local_addr = self.local_addr()?;
+        let server = Arc::new(self);
+        let runner = Arc::clone(&server);
+        let thread = server
+            .shared
+            .threads
+            .spawn("accept", move || runner.run())?;
+
+        // Stopping a server that is not running yet does nothing, wait until it is
+        while !server.is_running() {
+            if thread.is_finished() {
+                return match thread.join() {
+                    Ok(Err(e)) => Err(e),
+                    Ok(Ok(())) => Err(ServerError::Io(io::Error::other(
+                        "Server stopped before it ran",
+                    ))),
+                    Err(panic) => panic::resume_unwind(panic),
+                };
+            }
+            thread::sleep(Duration::from_millis(1));
+        }
+        Ok(ServerHandle {
+            server,
+            local_addr,
+            thread: Some(thread),
+        })
+    }
+
+    /// Runs the server, accepting and handling client connections. Fails once accepting
+    /// keeps failing for longer than the accept error policy allows.
+    pub fn run(&self) -> Result<(), ServerError> {
+        self.begin_run()?;
         info!("Server is running on {}", self.listener.local_addr()?);
 
         self.listener.set_nonblocking(true)?; // Set listener to non-blocking mode
 
-        while self.is_running.load(Ordering::SeqCst) {
+        // Dropped when the loop ends, which waits for the connections it still serves
+        let threads = &self.shared.threads;
+        let pool = self
+            .worker_threads
+            .map(|size| WorkerPool::with_threads(size, threads))
+            .transpose()?;
+        let mut connection_threads = 0;
+
+        let mut consecutive_errors = 0;
+        let mut last_check: Option<Instant> = None;
+        let mut under_pressure = false;
+        let mut last_snapshot = Instant::now();
+        let mut saved_version = self.shared.kv.version();
+        while self.shared.is_running.load(Ordering::SeqCst) {
+            if let Some(persistence) = &self.kv_persistence {
+                if last_snapshot.elapsed() >= persistence.interval {
+                    last_snapshot = Instant::now();
+                    saved_version = self.snapshot_kv(saved_version);
+                }
+            }
+
+            let limits = &self.resource_limits;
+            if !limits.is_empty()
+                && last_check.is_none_or(|checked| checked.elapsed() >= limits.check_interval)
+            {
+                last_check = Some(Instant::now());
+                under_pressure = self.check_resources(under_pressure);
+            }
+            if under_pressure {
+                // Leave new connections waiting in the backlog until resources are freed
+                thread::sleep(POLL_INTERVAL);
+                continue;
+            }
+
             match self.listener.accept() {
                 Ok((stream, addr)) => {
+                    consecutive_errors = 0;
+                    if let Some(max) = self.max_connections {
+                        if self.connection_count() >= max {
+                            self.turn_away(stream, addr, max);
+                            continue;
+                        }
+                    }
                     info!("New client connected: {}", addr);
 
-                    // Clone the `is_running` flag for the client thread
-                    let is_running_clone = Arc::clone(&self.is_running);
-
-                    // Spawn a thread to handle the client
-                    thread::spawn(move || {
-                        let mut client = Client::new(stream);
-
-                        while is_running_clone.load(Ordering::SeqCst) {
-                            if let Err(e) = client.handle() {
-                                error!("Error handling client: {}", e);
-                                break;
+                    // Hand the client to a worker, or spawn a thread to handle it
+                    *self.shared.open_connections.lock().unwrap() += 1;
+                    let shared = Arc::clone(&self.shared);
+                    match &pool {
+                        Some(pool) => {
+                            let handle = pool.handle().clone();
+                            pool.execute(Box::new(move || {
+                                Self::serve_pooled(stream, addr, shared, handle)
+                            }));
+                        }
+                        None => {
+                            connection_threads += 1;
+                            let role = format!("conn-{}", connection_threads);
+                            if let Err(e) =
+                                threads.spawn(&role, move || Self::serve(stream, addr, shared))
+                            {
+                                // The stream went with the closure, the client is dropped
+                                error!("Failed to spawn thread for {}: {}", addr, e);
+                                *self.shared.open_connections.lock().unwrap() -= 1;
+                                self.shared.connection_closed.notify_all();
                             }
                         }
-
-                        info!("Client at {} disconnected", addr);
-                    });
+                    }
                 }
                 Err(ref e) if e.kind() == ErrorKind::WouldBlock => {
                     // No incoming connections, sleep briefly to reduce CPU usage
-                    thread::sleep(Duration::from_millis(100));
+                    thread::sleep(POLL_INTERVAL);
+                }
+                Err(ref e) if is_connection_error(e) => {
+                    // Only the connection being accepted is affected, carry on with the next
+                    warn!("Error accepting connection: {}", e);
                 }
                 Err(e) => {
+                    // Something like running out of file descriptors, give it time to recover
+                    consecutive_errors += 1;
                     error!("Error accepting connection: {}", e);
+
+                    let policy = &self.accept_errors;
+                    if consecutive_errors == policy.max_consecutive_errors {
+                        self.shared.is_running.store(false, Ordering::SeqCst);
+                        self.shared.events.emit(ServerEvent::AcceptLoopFailed {
+                            errors: consecutive_errors,
+                            kind: e.kind(),
+                        });
+                        self.end_run(saved_version);
+                        return Err(ServerError::AcceptLoopFailed {
+                            errors: consecutive_errors,
+                            last: e,
+                        });
+                    }
+
+                    let backoff = policy
+                        .initial_backoff
+                        .saturating_mul(2u32.saturating_pow(consecutive_errors - 1))
+                        .min(policy.max_backoff);
+                    // Jitter keeps servers sharing the same trouble from retrying in lockstep
+                    let jitter = 0.5 + 0.5 * self.shared.rng.lock().unwrap().next_f64();
+                    thread::sleep(backoff.mul_f64(jitter));
                 }
             }
         }
 
         info!("Server stopped.");
+        // Pooled connections are served to the end, their changes belong in the snapshot
+        drop(pool);
+        self.end_run(saved_version);
+        Ok(())
+    }
+
+    /// Gives up privileges and marks the server as running, recording when it started
+    fn begin_run(&self) -> Result<(), ServerError> {
+        self.privileges.apply()?; // Nothing privileged is needed once bound, or to dial out
+        self.shared.is_running.store(true, Ordering::SeqCst); // Set the server as running
+        let started_at_ms = info::unix_time_ms();
+        self.shared
+            .started_at_ms
+            .store(started_at_ms, Ordering::SeqCst);
+        let recorded = self
+            .shared
+            .run_history
+            .lock()
+            .unwrap()
+            .record_start(started_at_ms);
+        if let Err(e) = recorded {
+            warn!("Failed to record the start of this run: {}", e);
+        }
+        info!("{}", info::banner());
         Ok(())
     }
 
+    /// Writes the key-value store to its snapshot if it changed since `saved_version`, and
+    /// records that this run stopped rather than crashed
+    fn end_run(&self, saved_version: u64) {
+        self.snapshot_kv(saved_version);
+        let recorded = self
+            .shared
+            .run_history
+            .lock()
+            .unwrap()
+            .record_stop(info::unix_time_ms());
+        if let Err(e) = recorded {
+            warn!("Failed to record the stop of this run: {}", e);
+        }
+    }
+
+    /// Writes the key-value store to its snapshot if it changed since `saved_version`,
+    /// returning the version the snapshot holds
+    fn snapshot_kv(&self, saved_version: u64) -> u64 {
+        let Some(persistence) = &self.kv_persistence else {
+            return saved_version;
+        };
+        let version = self.shared.kv.version();
+        if version == saved_version {
+            return saved_version;
+        }
+        match self.shared.kv.save(&persistence.path) {
+            Ok(()) => version,
+            Err(e) => {
+                error!(
+                    "Failed to write key-value snapshot to {}: {}",
+                    persistence.path.display(),
+                    e
+                );
+                saved_version
+            }
+        }
+    }
+
+    /// Turns away a connection beyond the connection limit, as the overflow policy says
+    fn turn_away(&self, mut stream: TcpStream, addr: SocketAddr, max: usize) {
+        warn!(
+            "Turning away client at {}: {} connections already open",
+            addr, max
+        );
+        if self.overflow_policy == OverflowPolicy::Refuse {
+            return;
+        }
+
+        let busy = ServerMessage {
+            message: Some(ServerMessageType::ServerBusy(ServerBusy {
+                max_connections: max as u32,
+            })),
+            ..ServerMessage::default()
+        };
+        // Never hold up the accept loop for long, the client gets what fits right away
+        let _ = stream.set_write_timeout(Some(POLL_INTERVAL));
+        if framing::write_frame(&mut stream, &busy.encode_to_vec()).is_ok() {
+            let _ = stream.shutdown(Shutdown::Write);
+        }
+        // Closing with unread data resets the connection, which may discard the message
+        let _ = stream.set_nonblocking(true);
+        let mut buffer = [0; 512];
+        while matches!(stream.read(&mut buffer), Ok(bytes_read) if bytes_read > 0) {}
+    }
+
+    /// Measures resource usage against the limits, returning whether the server is under
+    /// pressure. Transitions are reported, and while under pressure the oldest idle
+    /// connection is shed if so configured.
+    fn check_resources(&self, was_under_pressure: bool) -> bool {
+        let usage = ResourceUsage::sample();
+        let under_pressure = self.resource_limits.exceeded_by(&usage);
+
+        if under_pressure && !was_under_pressure {
+            warn!(
+                "Resource limits exceeded, not accepting connections: {:?}",
+                usage
+            );
+            self.shared
+                .events
+                .emit(ServerEvent::ResourcePressure { usage });
+        } else if !under_pressure && was_under_pressure {
+            info!("Resource usage back within limits: {:?}", usage);
+            self.shared
+                .events
+                .emit(ServerEvent::ResourcePressureRelieved { usage });
+        }
+
+        if under_pressure && self.resource_limits.shed_idle {
+            let sessions = self.shared.sessions.lock().unwrap();
+            let oldest_idle = sessions
+                .values()
+                .filter(|session| session.in_flight() == 0 && !session.is_cancelled())
+                .min_by_key(|session| session.connected_at());
+            if let Some(session) = oldest_idle {
+                warn!("Shedding idle client at {}", session.addr());
+                session.close(CloseReason::ResourcePressure);
+            }
+        }
+
+        under_pressure
+    }
+
+    /// Runs the server in reverse-connection mode: instead of accepting clients it dials
+    /// `remote` and serves requests over that connection, redialing until stopped.
+    /// Useful for devices behind NAT, the listener the server was created with is not used.
+    pub fn run_outbound(&self, remote: &str) -> Result<(), ServerError> {
+        self.begin_run()?;
+        info!("Server is dialing {}", remote);
+
+        let mut last_snapshot = Instant::now();
+        let mut saved_version = self.shared.kv.version();
+        while self.shared.is_running.load(Ordering::SeqCst) {
+            match TcpStream::connect(remote).and_then(|stream| Ok((stream.peer_addr()?, stream))) {
+                Ok((addr, stream)) => {
+                    info!("Connected to remote client: {}", addr);
+
+                    // Serve the connection on this thread, there is only ever one
+                    *self.shared.open_connections.lock().unwrap() += 1;
+                    Self::serve(stream, addr, Arc::clone(&self.shared));
+                }
+                Err(e) => {
+                    // Also when the connection broke right after being established
+                    error!("Failed to connect to {}: {}", remote, e);
+                }
+            }
+
+            // Connections are served on this thread, snapshots are taken in between
+            if let Some(persistence) = &self.kv_persistence {
+                if last_snapshot.elapsed() >= persistence.interval {
+                    last_snapshot = Instant::now();
+                    saved_version = self.snapshot_kv(saved_version);
+                }
+            }
+
+            // Wait before redialing, waking up regularly to notice a shutdown
+            let mut waited = Duration::ZERO;
+            while waited < REDIAL_INTERVAL && self.shared.is_running.load(Ordering::SeqCst) {
+                thread::sleep(POLL_INTERVAL);
+                waited += POLL_INTERVAL;
+            }
+        }
+
+        info!("Server stopped.");
+        self.end_run(saved_version);
+        Ok(())
+    }
+
+    /// Serves a single client connection and reports how it ended
+    fn serve(stream: TcpStream, addr: SocketAddr, shared: Arc<Shared>) {
+        let mut client = Self::open(stream, addr, shared);
+        let reason = client.handle();
+        Self::finish(client, reason);
+    }
+
+    /// Serves a client connection on a worker of `pool`, taking turns with the other
+    /// connections whenever some are waiting for a worker
+    fn serve_pooled(stream: TcpStream, addr: SocketAddr, shared: Arc<Shared>, pool: PoolHandle) {
+        let mut client = Self::open(stream, addr, shared);
+        match client.prepare() {
+            Ok(()) => Self::serve_slice(client, pool),
+            Err(reason) => Self::finish(client, reason),
+        }
+    }
+
+    /// Serves a slice of a pooled connection, queuing it behind the waiting connections
+    /// if it yielded
+    fn serve_slice(mut client: Client, pool: PoolHandle) {
+        match client.handle_slice(&|| pool.queued() > 0) {
+            Some(reason) => Self::finish(client, reason),
+            None => {
+                let next = pool.clone();
+                pool.execute(Box::new(move || Self::serve_slice(client, next)));
+            }
+        }
+    }
+
+    /// Registers a new client connection
+    fn open(stream: TcpStream, addr: SocketAddr, shared: Arc<Shared>) -> Client {
+        let id = shared.next_session_id.fetch_add(1, Ordering::SeqCst);
+        let seed = rng::stream_seed(shared.rng_seed, id);
+        let session =
+            Session::with_rng_seed(id, addr, seed).with_connected_since(shared.clock.system_time());
+        let session = Arc::new(session);
+        shared
+            .sessions
+            .lock()
+            .unwrap()
+            .insert(id, Arc::clone(&session));
+        let stream = Arc::new(stream);
+        shared
+            .streams
+            .lock()
+            .unwrap()
+            .insert(id, Arc::clone(&stream));
+        if shared.resolve_peer_names {
+            let identity = Arc::clone(session.identity());
+            let resolver = shared.threads.spawn("resolve", move || {
+                if let Some(hostname) = identity.resolve() {
+                    info!("Client at {} is {}", identity.addr(), hostname);
+                }
+            });
+            if let Err(e) = resolver {
+                warn!("Failed to spawn thread resolving {}: {}", addr, e);
+            }
+        }
+        let client = Client::new(stream, Arc::clone(&session), Arc::clone(&shared));
+        shared
+            .outbounds
+            .lock()
+            .unwrap()
+            .insert(id, client.outbound_sender.clone());
+        shared.metrics.record_connect();
+        shared.events.emit(ServerEvent::Connected {
+            addr: session.addr(),
+        });
+        shared
+            .observers
+            .notify(|observer| observer.on_connect(addr));
+
+        client
+    }
+
+    /// Closes a client connection and reports how it ended
+    fn finish(mut client: Client, reason: CloseReason) {
+        let (session, shared) = (Arc::clone(&client.session), Arc::clone(&client.shared));
+        let (id, addr) = (session.id(), session.addr());
+        if shared.session_summary && reason.is_server_initiated() {
+            client.send_session_summary(reason);
+        }
+        if !matches!(
+            reason,
+            CloseReason::ReadError | CloseReason::WriteError | CloseReason::PeerUnresponsive
+        ) {
+            client.close(shared.close_timeout);
+        }
+
+        // Flag whatever might still be running on behalf of the connection
+        session.cancellation_token().cancel();
+        shared.sessions.lock().unwrap().remove(&id);
+        shared.streams.lock().unwrap().remove(&id);
+        shared.outbounds.lock().unwrap().remove(&id);
+        shared.topics.unsubscribe_all(id);
+        // Publications still queued never reach the client, their publishers learn of it
+        for message in client.outbound.try_iter() {
+            if let Some(ServerMessageType::Publication(publication)) = &message.message {
+                shared.topics.settle(publication, id, false);
+            }
+        }
+
+        info!("Client at {} disconnected: {}", addr, reason);
+        shared.metrics.record_close(reason);
+        if session.messages() == 0 {
+            shared.metrics.record_abandoned();
+        }
+        shared
+            .events
+            .emit(ServerEvent::Disconnected { addr, reason });
+        shared
+            .observers
+            .notify(|observer| observer.on_disconnect(addr, reason));
+
+        *shared.open_connections.lock().unwrap() -= 1;
+        shared.connection_closed.notify_all();
+    }
+
+    /// Returns the number of connections accepted and not torn down yet, including those
+    /// waiting for a worker
+    pub fn connection_count(&self) -> usize {
+        *self.shared.open_connections.lock().unwrap()
+    }
+
+    /// Stops the server and waits up to `timeout` until every connection is torn down.
+    /// Connections get half of the time to close gracefully, those still open after that
+    /// are shut down. Returns whether all of them were gone in time.
+    pub fn stop_and_wait(&self, timeout: Duration) -> bool {
+        let started = Instant::now();
+        self.stop();
+
+        let mut open = self.shared.open_connections.lock().unwrap();
+        while *open > 0 {
+            let elapsed = started.elapsed();
+            if elapsed >= timeout {
+                warn!("{} connections still open after shutting down", *open);
+                return false;
+            }
+            if elapsed >= timeout / 2 {
+                // Cut off whatever is still waiting for a slow peer
+                for stream in self.shared.streams.lock().unwrap().values() {
+                    let _ = stream.shutdown(Shutdown::Both);
+                }
+            }
+            let wait = (timeout - elapsed).min(POLL_INTERVAL);
+            open = self
+                .shared
+                .connection_closed
+                .wait_timeout(open, wait)
+                .unwrap()
+                .0;
+        }
+        true
+    }
+
+    /// Queues `message` for every connected client, which is sent in between the responses
+    /// of the client. Returns how many clients it was queued for, clients whose queue is
+    /// full miss it.
+    pub fn broadcast(&self, message: ServerMessage) -> usize {
+        let outbounds = self.shared.outbounds.lock().unwrap();
+        outbounds
+            .values()
+            .filter(|outbound| outbound.try_send(message.clone()).is_ok())
+            .count()
+    }
+
     /// Stops the server
     pub fn stop(&self) {
-        if self.is_running.load(Ordering::SeqCst) {
-            self.is_running.store(false, Ordering::SeqCst); // Set the running flag to false
+        if self.shared.is_running.load(Ordering::SeqCst) {
+            self.shared.is_running.store(false, Ordering::SeqCst); // Set the running flag to false
+
+            // Cancel the work in flight on behalf of the connected clients
+            for session in self.shared.sessions.lock().unwrap().values() {
+                session.cancellation_token().cancel();
+            }
             info!("Shutdown signal sent.");
         } else {
             warn!("Server was already stopped or not running.");
         }
     }
+
+    /// Starts a server on an ephemeral loopback port, runs a client through the echo, add
+    /// and malformed message paths against it and reports how each of them went. A smoke
+    /// test for operators to run after deploying.
+    pub fn self_test() -> Result<SelfTestReport, ServerError> {
+        let server = Server::new("127.0.0.1:0")?;
+        let addr = server.local_addr()?;
+
+        thread::scope(|scope| {
+            let handle = scope.spawn(|| server.run());
+            while !server.is_running() && !handle.is_finished() {
+                thread::sleep(Duration::from_millis(1));
+            }
+
+            let report = self_test::run_checks(addr);
+            server.stop();
+            handle
+                .join()
+                .map_err(|_| io::Error::other("Server thread panicked"))??;
+            Ok(report)
+        })
+    }
+
+    /// Returns true while the server is running
+    pub fn is_running(&self) -> bool {
+        self.shared.is_running.load(Ordering::SeqCst)
+    }
+
+    /// Subscribes to connection events published by the server
+    pub fn subscribe_events(&self) -> Receiver<ServerEvent> {
+        self.shared.events.subscribe()
+    }
+
+    /// Subscribes to connection events and to a MessageHandled event for every message the
+    /// server handled
+    pub fn subscribe_all_events(&self) -> Receiver<ServerEvent> {
+        self.shared.events.subscribe_with_messages()
+    }
+
+    /// Streams the server events, those for every message included, to `writer` as
+    /// newline-delimited JSON from a background thread, which ends once the server is
+    /// dropped or writing fails
+    pub fn export_events<W>(&self, writer: W) -> io::Result<thread::JoinHandle<io::Result<()>>>
+    where
+        W: Write + Send + 'static,
+    {
+        ndjson::spawn_sink(self.subscribe_all_events(), writer, &self.shared.threads)
+    }
+
+    /// Draws a live table of the connected clients to `writer` every `interval` from a
+    /// background thread, which ends once the server is dropped or writing fails
+    #[cfg(feature = "console")]
+    pub fn console<W>(
+        &self,
+        writer: W,
+        interval: Duration,
+    ) -> io::Result<thread::JoinHandle<io::Result<()>>>
+    where
+        W: Write + Send + 'static,
+    {
+        let shared = Arc::downgrade(&self.shared);
+        console::spawn(
+            move || {
+                let shared = shared.upgrade()?;
+                let sessions = shared.sessions.lock().unwrap().values().cloned().collect();
+                Some(console::render(sessions, &shared.metrics))
+            },
+            writer,
+            interval,
+            &self.shared.threads,
+        )
+    }
+
+    /// Returns how the server spawns its threads, for threads spawned on its behalf
+    pub fn thread_options(&self) -> &ThreadOptions {
+        &self.shared.threads
+    }
+
+    /// Returns the sessions of the currently connected clients
+    pub fn active_sessions(&self) -> Vec<Arc<Session>> {
+        self.shared
+            .sessions
+            .lock()
+            .unwrap()
+            .values()
+            .cloned()
+            .collect()
+    }
+
+    /// Returns the connected clients, oldest connection first
+    pub fn clients(&self) -> Vec<ClientInfo> {
+        let sessions = self.shared.sessions.lock().unwrap();
+        let mut clients: Vec<_> = sessions
+            .values()
+            .map(|session| session.client_info())
+            .collect();
+        clients.sort_by_key(|client| client.id);
+        clients
+    }
+
+    /// Drops the connection of the client `id`, e.g. one that misbehaves. The connection
+    /// is closed as kicked once the request it may be handling completes, and shut down if
+    /// it is still open after the close timeout, e.g. because the client stopped reading.
+    /// Returns false if no such client is connected.
+    pub fn disconnect(&self, id: u64) -> bool {
+        let sessions = self.shared.sessions.lock().unwrap();
+        let Some(session) = sessions.get(&id) else {
+            return false;
+        };
+        warn!("Disconnecting client at {}", session.addr());
+        session.close(CloseReason::Kicked);
+
+        let shared = Arc::downgrade(&self.shared);
+        let close_timeout = self.shared.close_timeout;
+        let cutoff = self.shared.threads.spawn("kick", move || {
+            thread::sleep(close_timeout);
+            let Some(shared) = shared.upgrade() else {
+                return;
+            };
+            // Still registered only while its connection thread has not finished closing
+            let streams = shared.streams.lock().unwrap();
+            if let Some(stream) = streams.get(&id) {
+                warn!("Shutting down the connection of kicked client {}", id);
+                let _ = stream.shutdown(Shutdown::Both);
+            }
+        });
+        if let Err(e) = cutoff {
+            warn!("Failed to spawn thread cutting off client {}: {}", id, e);
+        }
+        true
+    }
+
+    /// Returns the cache of handler responses
+    pub fn response_cache(&self) -> &ResponseCache {
+        &self.shared.cache
+    }
+
+    /// Returns the activity counters of the server
+    pub fn metrics(&self) -> &Metrics {
+        &self.shared.metrics
+    }
+
+    /// Returns the key-value store shared by all clients
+    pub fn kv_store(&self) -> &KvStore {
+        &self.shared.kv
+    }
+}
+
+// Server running on a thread of its own, as started by Server::start. Dropping the handle
+// stops the server and waits for it.
+pub struct ServerHandle {
+    server: Arc<Server>,                                 // The running server
+    local_addr: SocketAddr,                              // Address the server is listening on
+    thread: Option<JoinHandle<Result<(), ServerError>>>, // Thread running the server, until joined
+}
+
+impl ServerHandle {
+    /// Returns the address the server is listening on, useful after binding to port 0
+    pub fn local_addr(&self) -> SocketAddr {
+        self.local_addr
+    }
+
+    /// Returns the running server, e.g. for its metrics
+    pub fn server(&self) -> &Server {
+        &self.server
+    }
+
+    /// Stops the server, without waiting for its connections to close
+    pub fn stop(&self) {
+        self.server.stop();
+    }
+
+    /// Waits until the server has stopped, after `stop` or because it failed, returning the
+    /// error it failed with
+    pub fn join(mut self) -> Result<(), ServerError> {
+        match self.thread.take().map(JoinHandle::join) {
+            Some(Ok(result)) => result,
+            Some(Err(panic)) => panic::resume_unwind(panic),
+            None => Ok(()),
+        }
+    }
+}
+
+impl Drop for ServerHandle {
+    /// Stops the server and waits for it
+    fn drop(&mut self) {
+        self.stop();
+        if let Some(Ok(Err(e))) = self.thread.take().map(JoinHandle::join) {
+            error!("Server failed: {}", e);
+        }
+    }
+}
+
+/// Returns true for read errors caused by keepalive probes going unanswered. Read timeouts
+/// are reported as `WouldBlock` on unix, so a timed out read means the peer is gone.
+#[cfg(unix)]
+fn is_keepalive_timeout(e: &io::Error) -> bool {
+    e.raw_os_error() == Some(libc::ETIMEDOUT)
+}
+
+#[cfg(not(unix))]
+fn is_keepalive_timeout(_e: &io::Error) -> bool {
+    false
+}
+
+/// Returns true for accept errors that only concern the connection being accepted
+fn is_connection_error(e: &io::Error) -> bool {
+    matches!(
+        e.kind(),
+        ErrorKind::ConnectionAborted | ErrorKind::ConnectionReset | ErrorKind::Interrupted
+    )
 }
@@ -0,0 +1,227 @@
+// Importing necessary modules and crates
+use crate::events::CloseReason;
+use crate::message::SessionSummary;
+use crate::peer::PeerIdentity;
+use crate::rng::{self, Rng};
+use std::{
+    net::SocketAddr,
+    sync::{
+        atomic::{AtomicBool, AtomicU64, AtomicUsize, Ordering},
+        Arc, Mutex, MutexGuard,
+    },
+    time::{Duration, Instant, SystemTime},
+};
+
+// Cooperative cancellation flag, cheap to clone and share with running handlers
+#[derive(Debug, Clone, Default)]
+pub struct CancellationToken {
+    cancelled: Arc<AtomicBool>, // Set once the work should be abandoned
+}
+
+impl CancellationToken {
+    /// Requests cancellation of everything observing this token
+    pub fn cancel(&self) {
+        self.cancelled.store(true, Ordering::SeqCst);
+    }
+
+    /// Returns true once cancellation was requested
+    pub fn is_cancelled(&self) -> bool {
+        self.cancelled.load(Ordering::SeqCst)
+    }
+}
+
+// A connected client, as listed by Server::clients
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ClientInfo {
+    pub id: u64,          // Identifier of the connection, unique while the server lives
+    pub addr: SocketAddr, // Address the client connected from
+    pub connected_since: SystemTime, // Wall-clock time the connection was accepted at
+    pub age: Duration,    // How long the connection has been open
+}
+
+// Server-side state of a single client connection
+#[derive(Debug)]
+pub struct Session {
+    id: u64,                                   // Unique identifier of the connection
+    identity: Arc<PeerIdentity>,               // Who the peer is
+    connected_at: Instant,                     // When the connection was established
+    connected_since: SystemTime,               // Wall-clock time of the same moment
+    cancel: CancellationToken,                 // Cancelled when the connection goes away
+    in_flight: AtomicUsize,                    // Number of requests currently being handled
+    messages: AtomicU64,                       // Number of messages handled
+    errors: AtomicU64,                         // Number of messages that could not be handled
+    bytes_received: AtomicU64,                 // Bytes read from the peer
+    bytes_sent: AtomicU64,                     // Bytes written to the peer
+    last_message: Mutex<Option<&'static str>>, // Type of the most recently handled message
+    rng: Mutex<Rng>,                           // Random stream of the connection
+    close_reason: Mutex<Option<CloseReason>>,  // Why the server closes the connection, if it does
+}
+
+impl Session {
+    /// Creates the session of a freshly established connection
+    pub fn new(id: u64, addr: SocketAddr) -> Self {
+        Self::with_rng_seed(id, addr, rng::stream_seed(None, id))
+    }
+
+    /// Creates the session of a freshly established connection, with a reproducible random
+    /// stream
+    pub fn with_rng_seed(id: u64, addr: SocketAddr, seed: u64) -> Self {
+        Session {
+            id,
+            identity: Arc::new(PeerIdentity::new(addr)),
+            connected_at: Instant::now(),
+            connected_since: SystemTime::now(),
+            cancel: CancellationToken::default(),
+            in_flight: AtomicUsize::new(0),
+            messages: AtomicU64::new(0),
+            errors: AtomicU64::new(0),
+            bytes_received: AtomicU64::new(0),
+            bytes_sent: AtomicU64::new(0),
+            last_message: Mutex::new(None),
+            rng: Mutex::new(Rng::new(seed)),
+            close_reason: Mutex::new(None),
+        }
+    }
+
+    /// Sets the wall-clock time the connection was accepted at, as told by the server's clock
+    pub fn with_connected_since(mut self, connected_since: SystemTime) -> Self {
+        self.connected_since = connected_since;
+        self
+    }
+
+    /// Returns the unique identifier of the connection
+    pub fn id(&self) -> u64 {
+        self.id
+    }
+
+    /// Returns the address of the peer, IPv4-mapped addresses as IPv4
+    pub fn addr(&self) -> SocketAddr {
+        self.identity.addr()
+    }
+
+    /// Returns who the peer is
+    pub fn identity(&self) -> &Arc<PeerIdentity> {
+        &self.identity
+    }
+
+    /// Returns when the connection was established
+    pub fn connected_at(&self) -> Instant {
+        self.connected_at
+    }
+
+    /// Returns the wall-clock time the connection was accepted at
+    pub fn connected_since(&self) -> SystemTime {
+        self.connected_since
+    }
+
+    /// Describes the client of the connection
+    pub fn client_info(&self) -> ClientInfo {
+        ClientInfo {
+            id: self.id,
+            addr: self.addr(),
+            connected_since: self.connected_since,
+            age: self.connected_at.elapsed(),
+        }
+    }
+
+    /// Returns the token cancelled when the connection goes away
+    pub fn cancellation_token(&self) -> &CancellationToken {
+        &self.cancel
+    }
+
+    /// Returns true once the connection went away, results are then never delivered
+    pub fn is_cancelled(&self) -> bool {
+        self.cancel.is_cancelled()
+    }
+
+    /// Returns the random stream of the connection, for jitter, sampling and the like
+    pub fn rng(&self) -> MutexGuard<'_, Rng> {
+        self.rng.lock().unwrap()
+    }
+
+    /// Asks the server to close the connection for `reason`, the first reason given wins
+    pub fn close(&self, reason: CloseReason) {
+        self.close_reason.lock().unwrap().get_or_insert(reason);
+        self.cancel.cancel();
+    }
+
+    /// Returns why the connection was asked to close, if it was
+    pub fn close_reason(&self) -> Option<CloseReason> {
+        *self.close_reason.lock().unwrap()
+    }
+
+    /// Returns the number of requests currently being handled
+    pub fn in_flight(&self) -> usize {
+        self.in_flight.load(Ordering::SeqCst)
+    }
+
+    /// Marks the start of a request, which ends when the returned guard is dropped
+    pub fn begin_request(&self) -> InFlightGuard<'_> {
+        self.in_flight.fetch_add(1, Ordering::SeqCst);
+        InFlightGuard { session: self }
+    }
+
+    /// Returns the number of messages handled so far
+    pub fn messages(&self) -> u64 {
+        self.messages.load(Ordering::Relaxed)
+    }
+
+    /// Returns the number of messages that could not be handled
+    pub fn errors(&self) -> u64 {
+        self.errors.load(Ordering::Relaxed)
+    }
+
+    /// Returns the number of bytes read from the peer
+    pub fn bytes_received(&self) -> u64 {
+        self.bytes_received.load(Ordering::Relaxed)
+    }
+
+    /// Returns the number of bytes written to the peer
+    pub fn bytes_sent(&self) -> u64 {
+        self.bytes_sent.load(Ordering::Relaxed)
+    }
+
+    /// Returns the type of the most recently handled message
+    pub fn last_message(&self) -> Option<&'static str> {
+        *self.last_message.lock().unwrap()
+    }
+
+    /// Accounts for a handled message of type `message_type` and `bytes` bytes
+    pub fn record_message(&self, message_type: &'static str, bytes: usize, failed: bool) {
+        *self.last_message.lock().unwrap() = Some(message_type);
+        self.messages.fetch_add(1, Ordering::Relaxed);
+        self.bytes_received
+            .fetch_add(bytes as u64, Ordering::Relaxed);
+        if failed {
+            self.errors.fetch_add(1, Ordering::Relaxed);
+        }
+    }
+
+    /// Accounts for `bytes` bytes written to the peer
+    pub fn record_sent(&self, bytes: usize) {
+        self.bytes_sent.fetch_add(bytes as u64, Ordering::Relaxed);
+    }
+
+    /// Summarizes the traffic of the connection so far
+    pub fn summary(&self, reason: CloseReason) -> SessionSummary {
+        SessionSummary {
+            messages: self.messages.load(Ordering::Relaxed),
+            errors: self.errors.load(Ordering::Relaxed),
+            bytes_received: self.bytes_received.load(Ordering::Relaxed),
+            bytes_sent: self.bytes_sent.load(Ordering::Relaxed),
+            duration_ms: self.connected_at.elapsed().as_millis() as u64,
+            close_reason: reason.to_string(),
+        }
+    }
+}
+
+// Keeps a request accounted as in flight for as long as it is alive
+pub struct InFlightGuard<'a> {
+    session: &'a Session, // Session the request belongs to
+}
+
+impl Drop for InFlightGuard<'_> {
+    fn drop(&mut self) {
+        self.session.in_flight.fetch_sub(1, Ordering::SeqCst);
+    }
+}
@@ -0,0 +1,168 @@
+// Importing necessary modules and crates
+use crate::clock::{Clock, SystemClock};
+use crate::histogram::ExponentialHistogram;
+use crate::message::client_message::Message as ClientMessageType;
+use crate::message::{MessageTypeStats, TopMessagesResponse};
+use std::{
+    collections::{HashMap, VecDeque},
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
+};
+
+// Name used for payloads that could not be attributed to a message type
+pub const UNKNOWN_MESSAGE_TYPE: &str = "unknown";
+
+// Upper bound on the samples kept per message type, whatever the window length
+const MAX_SAMPLES_PER_TYPE: usize = 10_000;
+
+/// Returns the name under which a client message is accounted
+pub fn message_type_name(message: &Option<ClientMessageType>) -> &'static str {
+    match message {
+        Some(ClientMessageType::EchoMessage(_)) => "echo",
+        Some(ClientMessageType::AddRequest(_)) => "add",
+        Some(ClientMessageType::SubtractRequest(_)) => "subtract",
+        Some(ClientMessageType::MultiplyRequest(_)) => "multiply",
+        Some(ClientMessageType::DivideRequest(_)) => "divide",
+        Some(ClientMessageType::CalculateRequest(_)) => "calculate",
+        Some(ClientMessageType::SetRequest(_)) => "set",
+        Some(ClientMessageType::GetRequest(_)) => "get",
+        Some(ClientMessageType::DeleteRequest(_)) => "delete",
+        Some(ClientMessageType::HistoryRequest(_)) => "history",
+        Some(ClientMessageType::TopMessagesRequest(_)) => "top_messages",
+        Some(ClientMessageType::ClientGoodbye(_)) => "goodbye",
+        Some(ClientMessageType::DotProductRequest(_)) => "dot_product",
+        Some(ClientMessageType::MatrixMultiplyRequest(_)) => "matrix_multiply",
+        Some(ClientMessageType::SetLogLevelRequest(_)) => "set_log_level",
+        Some(ClientMessageType::RecentErrorsRequest(_)) => "recent_errors",
+        Some(ClientMessageType::RunHistoryRequest(_)) => "run_history",
+        Some(ClientMessageType::ConnectionChurnRequest(_)) => "connection_churn",
+        Some(ClientMessageType::SubscribeRequest(_)) => "subscribe",
+        Some(ClientMessageType::UnsubscribeRequest(_)) => "unsubscribe",
+        Some(ClientMessageType::PublishRequest(_)) => "publish",
+        Some(ClientMessageType::ServerInfoRequest(_)) => "server_info",
+        Some(ClientMessageType::PingRequest(_)) => "ping",
+        Some(ClientMessageType::PongResponse(_)) => "pong",
+        None => UNKNOWN_MESSAGE_TYPE,
+    }
+}
+
+// A single handled message
+struct Sample {
+    at: Instant,       // When handling finished
+    latency: Duration, // Time spent handling the message
+    failed: bool,      // Whether handling the message failed
+}
+
+// Per-message-type statistics over a rolling time window
+pub struct MessageStats {
+    window: Duration,     // Age after which samples are forgotten
+    histogram_scale: i32, // Resolution of the latency histograms
+    samples: Mutex<HashMap<&'static str, VecDeque<Sample>>>, // Recent samples, oldest first
+    clock: Arc<dyn Clock>, // Time the window is measured against
+}
+
+impl MessageStats {
+    /// Creates statistics covering the last `window` of traffic, with latency histograms of
+    /// the given bucket scale
+    pub fn new(window: Duration, histogram_scale: i32) -> Self {
+        MessageStats::with_clock(window, histogram_scale, Arc::new(SystemClock))
+    }
+
+    /// Creates statistics like `new`, measuring the window against `clock`
+    pub fn with_clock(window: Duration, histogram_scale: i32, clock: Arc<dyn Clock>) -> Self {
+        MessageStats {
+            window,
+            histogram_scale,
+            samples: Mutex::new(HashMap::new()),
+            clock,
+        }
+    }
+
+    /// Records a handled message of the given type
+    pub fn record(&self, message_type: &'static str, latency: Duration, failed: bool) {
+        let now = self.clock.now();
+        let mut samples = self.samples.lock().unwrap();
+        let samples = samples.entry(message_type).or_default();
+
+        if samples.len() == MAX_SAMPLES_PER_TYPE {
+            samples.pop_front();
+        }
+        samples.push_back(Sample {
+            at: now,
+            latency,
+            failed,
+        });
+    }
+
+    /// Summarizes the window, busiest message types first, keeping at most `limit` of them
+    /// (all of them if `limit` is 0)
+    pub fn top(&self, limit: usize) -> TopMessagesResponse {
+        let now = self.clock.now();
+        let mut samples = self.samples.lock().unwrap();
+
+        let mut stats: Vec<MessageTypeStats> = samples
+            .iter_mut()
+            .filter_map(|(message_type, samples)| {
+                // Forget everything that fell out of the window
+                while samples
+                    .front()
+                    .is_some_and(|sample| now.duration_since(sample.at) > self.window)
+                {
+                    samples.pop_front();
+                }
+                summarize(message_type, samples, self.histogram_scale)
+            })
+            .collect();
+
+        stats.sort_by(|a, b| {
+            b.count
+                .cmp(&a.count)
+                .then_with(|| a.message_type.cmp(&b.message_type))
+        });
+        if limit > 0 {
+            stats.truncate(limit);
+        }
+
+        TopMessagesResponse {
+            stats,
+            window_secs: self.window.as_secs() as u32,
+            error: String::new(),
+        }
+    }
+}
+
+/// Computes the statistics of one message type, `None` if it has no samples
+fn summarize(
+    message_type: &str,
+    samples: &VecDeque<Sample>,
+    histogram_scale: i32,
+) -> Option<MessageTypeStats> {
+    if samples.is_empty() {
+        return None;
+    }
+
+    let count = samples.len() as u64;
+    let errors = samples.iter().filter(|sample| sample.failed).count() as u64;
+
+    let mut latencies: Vec<u64> = samples
+        .iter()
+        .map(|sample| sample.latency.as_micros() as u64)
+        .collect();
+    latencies.sort_unstable();
+    let p99_index = (latencies.len() * 99).div_ceil(100) - 1;
+
+    let mut histogram = ExponentialHistogram::new(histogram_scale);
+    for &latency in &latencies {
+        histogram.record(latency);
+    }
+
+    Some(MessageTypeStats {
+        message_type: message_type.to_string(),
+        count,
+        errors,
+        error_rate: errors as f64 / count as f64,
+        avg_latency_us: latencies.iter().sum::<u64>() / count,
+        p99_latency_us: latencies[p99_index],
+        latency_histogram_us: Some(histogram.to_message()),
+    })
+}
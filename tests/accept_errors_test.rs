@@ -0,0 +1,62 @@
+#![cfg(all(feature = "server", unix))]
+
+use embedded_recruitment_task::{
+    events::ServerEvent,
+    server::{AcceptErrorPolicy, Server, ServerError},
+};
+use std::{net::TcpStream, time::Duration};
+
+// Changes the soft limit on open file descriptors, returning the previous limits
+fn set_fd_limit(soft: libc::rlim_t) -> libc::rlimit {
+    let mut limits = libc::rlimit {
+        rlim_cur: 0,
+        rlim_max: 0,
+    };
+    // SAFETY: both calls only access the rlimit structs on the stack
+    unsafe {
+        assert_eq!(libc::getrlimit(libc::RLIMIT_NOFILE, &mut limits), 0);
+        let lowered = libc::rlimit {
+            rlim_cur: soft,
+            rlim_max: limits.rlim_max,
+        };
+        assert_eq!(libc::setrlimit(libc::RLIMIT_NOFILE, &lowered), 0);
+    }
+    limits
+}
+
+#[test]
+fn test_accept_loop_gives_up_without_file_descriptors() {
+    let policy = AcceptErrorPolicy {
+        max_consecutive_errors: 3,
+        initial_backoff: Duration::from_millis(1),
+        max_backoff: Duration::from_millis(10),
+    };
+    let server = Server::builder()
+        .accept_error_policy(policy)
+        .bind("127.0.0.1:0")
+        .expect("Failed to start server");
+    let events = server.subscribe_events();
+
+    // Queue a connection, then leave no file descriptor to accept it with
+    let addr = server.local_addr().expect("Failed to get server address");
+    let _client = TcpStream::connect(addr).expect("Failed to connect to the server");
+    let limits = set_fd_limit(0);
+    let result = server.run();
+    // SAFETY: restores the limits read above
+    unsafe {
+        assert_eq!(libc::setrlimit(libc::RLIMIT_NOFILE, &limits), 0);
+    }
+
+    match result {
+        Err(ServerError::AcceptLoopFailed { errors, last }) => {
+            assert_eq!(errors, 3);
+            assert_eq!(last.raw_os_error(), Some(libc::EMFILE));
+        }
+        result => panic!("Expected AcceptLoopFailed, got {:?}", result),
+    }
+    assert!(matches!(
+        events.try_recv(),
+        Ok(ServerEvent::AcceptLoopFailed { errors: 3, .. })
+    ));
+    assert!(!server.is_running(), "Server still claims to be running");
+}
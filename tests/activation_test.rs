@@ -0,0 +1,79 @@
+#![cfg(all(feature = "systemd", unix))]
+
+use embedded_recruitment_task::{activation, client::Client, server::Server};
+use std::{
+    env,
+    fs::File,
+    io::ErrorKind,
+    net::TcpListener,
+    os::unix::io::{AsRawFd, RawFd},
+    process,
+};
+
+mod test_server;
+
+use test_server::TestServer;
+
+// Descriptor the service manager passes the first socket as
+const FIRST_FD: RawFd = 3;
+
+// Puts a duplicate of `fd` where the service manager would pass it
+fn pass(fd: RawFd) {
+    // SAFETY: both descriptors stay open while the call runs
+    assert!(unsafe { libc::dup2(fd, FIRST_FD) } >= 0);
+    env::set_var("LISTEN_PID", process::id().to_string());
+    env::set_var("LISTEN_FDS", "1");
+}
+
+// The variables are process wide, so every case runs in this one test
+#[test]
+fn test_inherits_the_passed_listener() {
+    // Whatever the harness keeps at the first descriptor is restored afterwards. A free one
+    // is taken, so no descriptor opened below ends up there and is replaced.
+    // SAFETY: duplicating never invalidates the original
+    let saved = unsafe { libc::dup(FIRST_FD) };
+    if saved < 0 {
+        // SAFETY: opening with a valid path, the lowest free descriptor is the first one
+        let placeholder = unsafe { libc::open(c"/dev/null".as_ptr(), libc::O_RDONLY) };
+        assert_eq!(placeholder, FIRST_FD);
+    }
+
+    assert!(activation::listeners().unwrap().is_empty());
+
+    // Meant for another process
+    env::set_var("LISTEN_PID", "1");
+    env::set_var("LISTEN_FDS", "1");
+    assert!(activation::listeners().unwrap().is_empty());
+    assert!(env::var_os("LISTEN_FDS").is_none(), "Variables were kept");
+
+    let file = File::open(env::current_exe().unwrap()).unwrap();
+    pass(file.as_raw_fd());
+    let error = activation::listeners().expect_err("A file was taken for a socket");
+    assert_eq!(error.kind(), ErrorKind::InvalidInput);
+
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let addr = listener.local_addr().unwrap();
+    pass(listener.as_raw_fd());
+    let inherited = activation::listener()
+        .unwrap()
+        .expect("Passed listener was not found");
+    assert_eq!(inherited.local_addr().unwrap(), addr);
+
+    let server = Server::builder()
+        .listener(inherited)
+        .expect("Failed to start server");
+    let server = TestServer::run(server);
+    let mut client = Client::connect(addr).expect("Failed to connect to the server");
+    client.ping().expect("Inherited listener is not served");
+    drop(client);
+    server.stop();
+
+    // The server closed the inherited listener, a placeholder is not put back
+    if saved >= 0 {
+        // SAFETY: `saved` is a descriptor of this test's own
+        unsafe {
+            libc::dup2(saved, FIRST_FD);
+            libc::close(saved);
+        }
+    }
+}
@@ -0,0 +1,301 @@
+#![cfg(feature = "server")]
+
+use embedded_recruitment_task::{
+    admin::ADMIN_TOKEN_HEADER,
+    clock::MockClock,
+    events::{CloseReason, ServerEvent},
+    framing::{self, DEFAULT_MAX_FRAME_SIZE},
+    message::{
+        client_message, server_message, ConnectionChurnRequest, ConnectionChurnResponse,
+        RecentErrorsRequest, RecentErrorsResponse, SetLogLevelRequest, SetLogLevelResponse,
+    },
+    server::Server,
+};
+use log::LevelFilter;
+use std::{
+    collections::HashMap,
+    io::Write,
+    thread,
+    time::{Duration, Instant},
+};
+
+mod client;
+mod test_server;
+
+use test_server::TestServer;
+
+// Builds the headers of an admin request, presenting `token` if there is one
+fn admin_headers(token: Option<&str>) -> HashMap<String, String> {
+    token
+        .map(|token| HashMap::from([(ADMIN_TOKEN_HEADER.to_string(), token.to_string())]))
+        .unwrap_or_default()
+}
+
+// Asks the server for the errors it met recently
+fn recent_errors(
+    client: &mut client::Client,
+    limit: u32,
+    token: Option<&str>,
+) -> RecentErrorsResponse {
+    let message = client_message::Message::RecentErrorsRequest(RecentErrorsRequest { limit });
+    assert!(
+        client
+            .send_with_headers(message, admin_headers(token))
+            .is_ok(),
+        "Failed to send message"
+    );
+    match client.receive().map(|response| response.message) {
+        Ok(Some(server_message::Message::RecentErrorsResponse(response))) => response,
+        _ => panic!("Expected RecentErrorsResponse, but received a different message"),
+    }
+}
+
+// Asks the server for its connection churn, presenting `token` if there is one
+fn connection_churn(client: &mut client::Client, token: Option<&str>) -> ConnectionChurnResponse {
+    let message = client_message::Message::ConnectionChurnRequest(ConnectionChurnRequest {});
+    assert!(
+        client
+            .send_with_headers(message, admin_headers(token))
+            .is_ok(),
+        "Failed to send message"
+    );
+    match client.receive().map(|response| response.message) {
+        Ok(Some(server_message::Message::ConnectionChurnResponse(response))) => response,
+        _ => panic!("Expected ConnectionChurnResponse, but received a different message"),
+    }
+}
+
+// Asks the server to change the log level, presenting `token` if there is one
+fn set_log_level(
+    client: &mut client::Client,
+    target: &str,
+    level: &str,
+    token: Option<&str>,
+) -> SetLogLevelResponse {
+    let message = client_message::Message::SetLogLevelRequest(SetLogLevelRequest {
+        target: target.to_string(),
+        level: level.to_string(),
+    });
+    assert!(
+        client
+            .send_with_headers(message, admin_headers(token))
+            .is_ok(),
+        "Failed to send message"
+    );
+
+    let response = client.receive().expect("Failed to receive response");
+    assert!(
+        !response.headers.contains_key(ADMIN_TOKEN_HEADER),
+        "The admin token was sent back"
+    );
+    match response.message {
+        Some(server_message::Message::SetLogLevelResponse(response)) => response,
+        _ => panic!("Expected SetLogLevelResponse, but received a different message"),
+    }
+}
+
+#[test]
+fn test_log_level_changes_at_runtime() {
+    log::set_max_level(LevelFilter::Info);
+    let server = Server::builder()
+        .admin_token("secret")
+        .bind("127.0.0.1:0")
+        .expect("Failed to start server");
+    let server = TestServer::run(server);
+
+    let mut client = client::Client::new(&server.ip(), server.port(), 1000);
+    assert!(client.connect().is_ok(), "Failed to connect to the server");
+
+    // Without the right token nothing changes
+    for token in [None, Some("guess")] {
+        let response = set_log_level(&mut client, "", "trace", token);
+        assert_eq!(response.error, "Unauthorized");
+        assert_eq!(log::max_level(), LevelFilter::Info);
+    }
+
+    // Invalid requests are rejected with the reason
+    let response = set_log_level(&mut client, "", "loud", Some("secret"));
+    assert_eq!(response.error, "Unknown log level: loud");
+    let response = set_log_level(&mut client, "my_crate::module", "debug", Some("secret"));
+    assert!(!response.error.is_empty(), "Per-target level was accepted");
+    assert_eq!(log::max_level(), LevelFilter::Info);
+
+    let response = set_log_level(&mut client, "", "TRACE", Some("secret"));
+    assert!(response.error.is_empty(), "{}", response.error);
+    assert_eq!(response.previous_level, "info");
+    assert_eq!(log::max_level(), LevelFilter::Trace);
+
+    assert!(
+        client.disconnect().is_ok(),
+        "Failed to disconnect from the server"
+    );
+    server.stop();
+}
+
+#[test]
+fn test_admin_messages_disabled_without_token() {
+    let server = TestServer::start();
+    let mut client = client::Client::new(&server.ip(), server.port(), 1000);
+    assert!(client.connect().is_ok(), "Failed to connect to the server");
+
+    let response = set_log_level(&mut client, "", "trace", Some(""));
+    assert_eq!(response.error, "Unauthorized");
+
+    assert!(
+        client.disconnect().is_ok(),
+        "Failed to disconnect from the server"
+    );
+    server.stop();
+}
+
+#[test]
+fn test_recent_errors_are_kept() {
+    let server = Server::builder()
+        .admin_token("secret")
+        .bind("127.0.0.1:0")
+        .expect("Failed to start server");
+    let events = server.subscribe_events();
+    let server = TestServer::run(server);
+    let next_event = || {
+        events
+            .recv_timeout(Duration::from_secs(5))
+            .expect("Timed out waiting for a server event")
+    };
+
+    let mut admin = client::Client::new(&server.ip(), server.port(), 1000);
+    assert!(admin.connect().is_ok(), "Failed to connect to the server");
+    assert!(matches!(next_event(), ServerEvent::Connected { .. }));
+    assert!(recent_errors(&mut admin, 0, Some("secret"))
+        .errors
+        .is_empty());
+    assert_eq!(recent_errors(&mut admin, 0, None).error, "Unauthorized");
+
+    // One client sends garbage, another one announces a frame far beyond the limit
+    let mut garbage = client::Client::new(&server.ip(), server.port(), 1000);
+    assert!(garbage.connect().is_ok(), "Failed to connect to the server");
+    assert!(matches!(next_event(), ServerEvent::Connected { .. }));
+    let garbage_session = server
+        .server()
+        .active_sessions()
+        .into_iter()
+        .map(|session| session.id())
+        .max()
+        .unwrap();
+    let mut stream = garbage.stream().unwrap();
+    framing::write_frame(&mut stream, &[0xff; 4]).unwrap();
+    let deadline = Instant::now() + Duration::from_secs(5);
+    while recent_errors(&mut admin, 0, Some("secret"))
+        .errors
+        .is_empty()
+    {
+        assert!(Instant::now() < deadline, "Decode failure was not recorded");
+        thread::sleep(Duration::from_millis(10));
+    }
+
+    let mut oversized = client::Client::new(&server.ip(), server.port(), 1000);
+    assert!(
+        oversized.connect().is_ok(),
+        "Failed to connect to the server"
+    );
+    assert!(matches!(next_event(), ServerEvent::Connected { .. }));
+    let len = (DEFAULT_MAX_FRAME_SIZE as u32 + 1).to_be_bytes();
+    oversized.stream().unwrap().write_all(&len).unwrap();
+    assert!(matches!(next_event(), ServerEvent::Disconnected { .. }));
+
+    // Newest first, and limited on request
+    let errors = recent_errors(&mut admin, 0, Some("secret")).errors;
+    assert_eq!(errors.len(), 2, "{:?}", errors);
+    assert_eq!(errors[0].kind, "frame");
+    assert_eq!(errors[1].kind, "decode");
+    assert_eq!(errors[1].session_id, garbage_session);
+    assert!(errors[0].session_id > garbage_session);
+    assert!(errors[0].timestamp_ms >= errors[1].timestamp_ms);
+    assert_eq!(
+        recent_errors(&mut admin, 1, Some("secret")).errors,
+        errors[..1]
+    );
+
+    server.stop();
+}
+
+#[test]
+fn test_connection_churn_is_reported() {
+    let server = Server::builder()
+        .admin_token("secret")
+        .bind("127.0.0.1:0")
+        .expect("Failed to start server");
+    let events = server.subscribe_events();
+    let server = TestServer::run(server);
+
+    let mut admin = client::Client::new(&server.ip(), server.port(), 1000);
+    assert!(admin.connect().is_ok(), "Failed to connect to the server");
+    assert_eq!(connection_churn(&mut admin, None).error, "Unauthorized");
+
+    // A client flapping twice
+    for _ in 0..2 {
+        let mut flapping = client::Client::new(&server.ip(), server.port(), 1000);
+        assert!(
+            flapping.connect().is_ok(),
+            "Failed to connect to the server"
+        );
+        assert!(
+            flapping.disconnect().is_ok(),
+            "Failed to disconnect from the server"
+        );
+        while !matches!(
+            events
+                .recv_timeout(Duration::from_secs(5))
+                .expect("Timed out waiting for a server event"),
+            ServerEvent::Disconnected { .. }
+        ) {}
+    }
+
+    // The test may straddle two buckets, so sum them up
+    let response = connection_churn(&mut admin, Some("secret"));
+    assert!(response.error.is_empty());
+    assert_eq!(response.bucket_secs, 60);
+    let connects: u64 = response.buckets.iter().map(|bucket| bucket.connects).sum();
+    let eofs: u64 = response
+        .buckets
+        .iter()
+        .map(|bucket| bucket.disconnects.get(CloseReason::ClientEof.as_str()))
+        .map(|count| count.copied().unwrap_or(0))
+        .sum();
+    assert_eq!(connects, 3);
+    assert_eq!(eofs, 2);
+
+    assert!(
+        admin.disconnect().is_ok(),
+        "Failed to disconnect from the server"
+    );
+    server.stop();
+}
+
+#[test]
+fn test_connection_churn_follows_the_server_clock() {
+    let clock = MockClock::new();
+    let server = Server::builder()
+        .admin_token("secret")
+        .clock(clock.clone())
+        .bind("127.0.0.1:0")
+        .expect("Failed to start server");
+    let server = TestServer::run(server);
+
+    let mut admin = client::Client::new(&server.ip(), server.port(), 1000);
+    assert!(admin.connect().is_ok(), "Failed to connect to the server");
+    let response = connection_churn(&mut admin, Some("secret"));
+    let connects: u64 = response.buckets.iter().map(|bucket| bucket.connects).sum();
+    assert_eq!(connects, 1);
+
+    // Two hours later by the server's clock, the connect left the window
+    clock.advance(Duration::from_secs(2 * 60 * 60));
+    assert!(connection_churn(&mut admin, Some("secret"))
+        .buckets
+        .is_empty());
+
+    assert!(
+        admin.disconnect().is_ok(),
+        "Failed to disconnect from the server"
+    );
+    server.stop();
+}
@@ -0,0 +1,54 @@
+#![cfg(feature = "server")]
+
+use embedded_recruitment_task::{
+    client::{Client, ClientError},
+    message::ErrorCode,
+};
+
+mod test_server;
+
+use test_server::TestServer;
+
+// Asserts that the request was rejected with `code`
+fn assert_rejected<T: std::fmt::Debug>(result: Result<T, ClientError>, code: ErrorCode) {
+    match result {
+        Err(ClientError::Rejected(error)) => {
+            assert_eq!(error.code(), code);
+            assert!(!error.description.is_empty());
+        }
+        other => panic!("Expected {:?}, got {:?}", code, other),
+    }
+}
+
+#[test]
+fn test_arithmetic_operations() {
+    let server = TestServer::start();
+    let mut client = Client::connect(("127.0.0.1", server.port() as u16))
+        .expect("Failed to connect to the server");
+
+    assert_eq!(client.subtract(10, 25).unwrap(), -15);
+    assert_eq!(client.multiply(-6, 7).unwrap(), -42);
+    assert_eq!(client.divide(7, 2).unwrap(), (3, 1));
+    assert_eq!(client.divide(-7, 2).unwrap(), (-3, -1));
+
+    drop(client);
+    server.stop();
+}
+
+#[test]
+fn test_arithmetic_errors_are_rejected() {
+    let server = TestServer::start();
+    let mut client = Client::connect(("127.0.0.1", server.port() as u16))
+        .expect("Failed to connect to the server");
+
+    assert_rejected(client.divide(1, 0), ErrorCode::DivisionByZero);
+    assert_rejected(client.divide(i32::MIN, -1), ErrorCode::ArithmeticOverflow);
+    assert_rejected(client.subtract(i32::MIN, 1), ErrorCode::ArithmeticOverflow);
+    assert_rejected(client.multiply(i32::MAX, 2), ErrorCode::ArithmeticOverflow);
+
+    // Errors fail the request only
+    assert_eq!(client.divide(i32::MIN, 1).unwrap(), (i32::MIN, 0));
+
+    drop(client);
+    server.stop();
+}
@@ -0,0 +1,68 @@
+#![cfg(feature = "server")]
+
+use embedded_recruitment_task::{
+    client::{Client, PUSHED_HEADER},
+    events::ServerEvent,
+    message::{server_message, EchoMessage, ServerMessage},
+};
+use std::time::Duration;
+
+mod test_server;
+
+use test_server::TestServer;
+
+// A notice pushed to every client
+fn notice() -> ServerMessage {
+    ServerMessage {
+        message: Some(server_message::Message::EchoMessage(EchoMessage {
+            content: "Maintenance at noon".to_string(),
+        })),
+        ..ServerMessage::default()
+    }
+}
+
+#[test]
+fn test_broadcast_reaches_every_client() {
+    let server = TestServer::start();
+    let events = server.server().subscribe_events();
+    let mut clients: Vec<Client> = (0..3)
+        .map(|_| {
+            Client::connect(("127.0.0.1", server.port() as u16))
+                .expect("Failed to connect to the server")
+        })
+        .collect();
+    for _ in 0..clients.len() {
+        let event = events
+            .recv_timeout(Duration::from_secs(5))
+            .expect("Timed out waiting for a connection");
+        assert!(matches!(event, ServerEvent::Connected { .. }));
+    }
+
+    assert_eq!(server.server().broadcast(notice()), 3);
+    for client in &mut clients {
+        let pushed = client.receive().unwrap();
+        assert_eq!(pushed.message, notice().message);
+        assert!(pushed.headers.contains_key(PUSHED_HEADER));
+
+        // Clients keep being served as usual
+        assert_eq!(client.add(1, 2).unwrap(), 3);
+    }
+
+    // Clients that left are no longer reached
+    clients.pop();
+    let event = events
+        .recv_timeout(Duration::from_secs(5))
+        .expect("Timed out waiting for a disconnection");
+    assert!(matches!(event, ServerEvent::Disconnected { .. }));
+    assert_eq!(server.server().broadcast(notice()), 2);
+
+    drop(clients);
+    server.stop();
+}
+
+#[test]
+fn test_broadcast_without_clients() {
+    let server = TestServer::start();
+    assert_eq!(server.server().broadcast(notice()), 0);
+    server.stop();
+}
@@ -0,0 +1,96 @@
+#![cfg(feature = "server")]
+
+use embedded_recruitment_task::{
+    buffer::AdaptiveBuffer,
+    message::{client_message, server_message, EchoMessage},
+    server::Server,
+};
+
+mod client;
+mod test_server;
+
+use test_server::TestServer;
+
+#[test]
+fn test_buffer_grows_when_reads_fill_it() {
+    let mut buffer = AdaptiveBuffer::new(512, 128, 2048);
+    assert_eq!(buffer.len(), 512);
+
+    // Every full read doubles the buffer until the upper bound is reached
+    buffer.adapt(512);
+    assert_eq!(buffer.len(), 1024);
+    buffer.adapt(1024);
+    assert_eq!(buffer.len(), 2048);
+    buffer.adapt(2048);
+    assert_eq!(buffer.len(), 2048, "Buffer grew beyond its upper bound");
+}
+
+#[test]
+fn test_buffer_shrinks_after_many_small_reads() {
+    let mut buffer = AdaptiveBuffer::new(512, 128, 2048);
+
+    // A few small reads are not enough to give up capacity
+    for _ in 0..7 {
+        buffer.adapt(10);
+    }
+    assert_eq!(buffer.len(), 512);
+    buffer.adapt(10);
+    assert_eq!(buffer.len(), 256);
+
+    // A read of moderate size interrupts the run of small reads
+    for _ in 0..7 {
+        buffer.adapt(10);
+    }
+    buffer.adapt(100);
+    buffer.adapt(10);
+    assert_eq!(buffer.len(), 256);
+
+    // Shrinking stops at the lower bound
+    for _ in 0..64 {
+        buffer.adapt(1);
+    }
+    assert_eq!(buffer.len(), 128, "Buffer shrank below its lower bound");
+}
+
+#[test]
+fn test_buffer_initial_size_is_clamped() {
+    assert_eq!(AdaptiveBuffer::new(16, 128, 2048).len(), 128);
+    assert_eq!(AdaptiveBuffer::new(4096, 128, 2048).len(), 2048);
+    assert_eq!(AdaptiveBuffer::new(512, 0, 0).len(), 1);
+}
+
+#[test]
+fn test_server_serves_with_small_read_buffers() {
+    // Set up a server whose read buffers start at, and stay within, a few dozen bytes
+    let server = Server::builder()
+        .read_buffer_size(32, 64)
+        .bind("127.0.0.1:0")
+        .expect("Failed to start server");
+    let server = TestServer::run(server);
+
+    let mut client = client::Client::new(&server.ip(), server.port(), 1000);
+    assert!(client.connect().is_ok(), "Failed to connect to the server");
+
+    for content in ["a", "short message", "a message of a few more bytes"] {
+        let message = client_message::Message::EchoMessage(EchoMessage {
+            content: content.to_string(),
+        });
+        assert!(client.send(message).is_ok(), "Failed to send message");
+
+        match client.receive().map(|response| response.message) {
+            Ok(Some(server_message::Message::EchoMessage(echo))) => {
+                assert_eq!(
+                    echo.content, content,
+                    "Echoed message content does not match"
+                );
+            }
+            _ => panic!("Expected EchoMessage, but received a different message"),
+        }
+    }
+
+    assert!(
+        client.disconnect().is_ok(),
+        "Failed to disconnect from the server"
+    );
+    server.stop();
+}
@@ -0,0 +1,168 @@
+#![cfg(feature = "server")]
+
+use embedded_recruitment_task::{
+    cache::EchoDedup,
+    client::Client,
+    clock::MockClock,
+    extract::from_fn,
+    message::{
+        client_message, server_message, AddRequest, GetRequest, MultiplyRequest, MultiplyResponse,
+        SubtractRequest, SubtractResponse,
+    },
+    server::Server,
+};
+use std::{
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc,
+    },
+    time::Duration,
+};
+
+mod client;
+mod test_server;
+
+use test_server::TestServer;
+
+// Sends an addition and returns its result
+fn add(client: &mut client::Client, a: i32, b: i32) -> i32 {
+    let message = client_message::Message::AddRequest(AddRequest { a, b });
+    assert!(client.send(message).is_ok(), "Failed to send message");
+
+    match client.receive().map(|response| response.message) {
+        Ok(Some(server_message::Message::AddResponse(add_response))) => add_response.result,
+        _ => panic!("Expected AddResponse, but received a different message"),
+    }
+}
+
+#[test]
+fn test_add_responses_are_cached() {
+    // Set up a server caching additions, and ignoring a type that cannot be cached
+    let server = Server::builder()
+        .cache_responses("add", Duration::from_secs(60))
+        .cache_responses("history", Duration::from_secs(60))
+        .bind("127.0.0.1:0")
+        .expect("Failed to start server");
+    let server = TestServer::run(server);
+
+    // Create and connect the client
+    let mut client = client::Client::new(&server.ip(), server.port(), 1000);
+    assert!(client.connect().is_ok(), "Failed to connect to the server");
+
+    // Repeating a request is answered from the cache, a different one is not
+    assert_eq!(add(&mut client, 1, 2), 3);
+    assert_eq!(add(&mut client, 1, 2), 3);
+    assert_eq!(add(&mut client, 2, 2), 4);
+
+    let cache = server.server().response_cache();
+    assert_eq!(cache.hits(), 1, "Unexpected number of cache hits");
+    assert_eq!(cache.misses(), 2, "Unexpected number of cache misses");
+
+    // Disconnect the client
+    assert!(
+        client.disconnect().is_ok(),
+        "Failed to disconnect from the server"
+    );
+
+    // Stop the server and wait for thread to finish
+    server.stop();
+}
+
+#[test]
+fn test_cached_responses_expire() {
+    // Set up a server caching additions very briefly
+    let clock = MockClock::new();
+    let server = Server::builder()
+        .cache_responses("add", Duration::from_millis(50))
+        .clock(clock.clone())
+        .bind("127.0.0.1:0")
+        .expect("Failed to start server");
+    let server = TestServer::run(server);
+
+    // Create and connect the client
+    let mut client = client::Client::new(&server.ip(), server.port(), 1000);
+    assert!(client.connect().is_ok(), "Failed to connect to the server");
+
+    assert_eq!(add(&mut client, 1, 2), 3);
+    clock.advance(Duration::from_millis(100));
+    assert_eq!(add(&mut client, 1, 2), 3);
+
+    let cache = server.server().response_cache();
+    assert_eq!(cache.hits(), 0, "Expired response was reused");
+    assert_eq!(cache.misses(), 2, "Unexpected number of cache misses");
+
+    // Stop the server and wait for thread to finish
+    server.stop();
+}
+
+#[test]
+fn test_echo_dedup_remembers_within_window() {
+    let clock = MockClock::new();
+    let mut dedup = EchoDedup::with_clock(Duration::from_millis(100), Arc::new(clock.clone()));
+    assert!(dedup.get(b"request").is_none());
+
+    dedup.insert(b"request", b"response".to_vec());
+    assert_eq!(dedup.get(b"request").as_deref(), Some(&b"response"[..]));
+    assert!(dedup.get(b"other request").is_none());
+    assert_eq!(dedup.hits(), 1);
+
+    clock.advance(Duration::from_millis(100));
+    assert!(
+        dedup.get(b"request").is_some(),
+        "Forgotten within the window"
+    );
+    clock.advance(Duration::from_millis(1));
+    assert!(dedup.get(b"request").is_none());
+    assert_eq!(dedup.hits(), 2);
+}
+
+#[test]
+fn test_handlers_declare_whether_they_are_cacheable() {
+    let calls = Arc::new(AtomicU64::new(0));
+    let counted = Arc::clone(&calls);
+    let server = Server::builder()
+        .cacheable_handler(
+            "multiply",
+            from_fn(move |request: MultiplyRequest| {
+                counted.fetch_add(1, Ordering::SeqCst);
+                MultiplyResponse {
+                    result: request.a * request.b,
+                }
+            }),
+        )
+        // Replacing a cacheable built-in handler with a plain one makes it uncacheable
+        .handler(
+            "subtract",
+            from_fn(|request: SubtractRequest| SubtractResponse {
+                result: request.a - request.b,
+            }),
+        )
+        .cache_responses("multiply", Duration::from_secs(60))
+        .cache_responses("subtract", Duration::from_secs(60))
+        .cache_responses("get", Duration::from_secs(60))
+        .bind("127.0.0.1:0")
+        .expect("Failed to start server");
+    let server = TestServer::run(server);
+    let mut client = Client::connect(("127.0.0.1", server.port() as u16))
+        .expect("Failed to connect to the server");
+
+    for _ in 0..2 {
+        assert_eq!(client.multiply(3, 4).unwrap(), 12);
+        assert_eq!(client.subtract(3, 4).unwrap(), -1);
+        let get = client_message::Message::GetRequest(GetRequest {
+            key: "missing".to_string(),
+        });
+        client.request(get).expect("Failed to get a value");
+    }
+    assert_eq!(
+        calls.load(Ordering::SeqCst),
+        1,
+        "Cached multiply was handled again"
+    );
+    let cache = server.server().response_cache();
+    assert_eq!(cache.hits(), 2, "Unexpected number of cache hits");
+    assert_eq!(cache.misses(), 2, "Unexpected number of cache misses");
+
+    drop(client);
+    server.stop();
+}
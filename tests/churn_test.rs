@@ -0,0 +1,75 @@
+#![cfg(feature = "server")]
+
+use embedded_recruitment_task::{churn::ConnectionChurn, events::CloseReason, metrics::Metrics};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+// A point in time the given number of seconds after the Unix epoch
+fn at(secs: u64) -> SystemTime {
+    UNIX_EPOCH + Duration::from_secs(secs)
+}
+
+#[test]
+fn test_churn_is_bucketed_by_time() {
+    let mut churn = ConnectionChurn::new(Duration::from_secs(60), 4);
+    churn.record_connect(at(600));
+    churn.record_connect(at(659));
+    churn.record_disconnect(at(659), CloseReason::ClientEof);
+    churn.record_connect(at(780));
+    churn.record_disconnect(at(790), CloseReason::Kicked);
+    churn.record_disconnect(at(795), CloseReason::Kicked);
+
+    // Minutes without churn are left out
+    let buckets = churn.buckets(at(800));
+    assert_eq!(buckets.len(), 2);
+    assert_eq!(buckets[0].start, at(600));
+    assert_eq!(buckets[0].connects, 2);
+    assert_eq!(buckets[0].disconnects(CloseReason::ClientEof), 1);
+    assert_eq!(buckets[0].total_disconnects(), 1);
+    assert_eq!(buckets[1].start, at(780));
+    assert_eq!(buckets[1].connects, 1);
+    assert_eq!(buckets[1].disconnects(CloseReason::Kicked), 2);
+    assert_eq!(buckets[1].total_disconnects(), 2);
+
+    // The oldest minute falls out of the rolling window
+    let buckets = churn.buckets(at(840));
+    assert_eq!(buckets.len(), 1);
+    assert_eq!(buckets[0].start, at(780));
+    assert!(churn.buckets(at(1100)).is_empty());
+}
+
+#[test]
+fn test_churn_response_names_close_reasons() {
+    let mut churn = ConnectionChurn::new(Duration::from_secs(60), 60);
+    churn.record_connect(at(60));
+    churn.record_disconnect(at(61), CloseReason::IdleTimeout);
+
+    let response = churn.to_response(at(62));
+    assert_eq!(response.bucket_secs, 60);
+    assert!(response.error.is_empty());
+    assert_eq!(response.buckets.len(), 1);
+    assert_eq!(response.buckets[0].start_ms, 60_000);
+    assert_eq!(response.buckets[0].connects, 1);
+    assert_eq!(response.buckets[0].disconnects.len(), 1);
+    assert_eq!(response.buckets[0].disconnects["idle_timeout"], 1);
+}
+
+#[test]
+fn test_metrics_track_churn() {
+    let metrics = Metrics::default();
+    metrics.record_connect();
+    metrics.record_connect();
+    metrics.record_close(CloseReason::ClientGoodbye);
+
+    let churn = metrics.churn();
+    assert_eq!(churn.iter().map(|bucket| bucket.connects).sum::<u64>(), 2);
+    assert_eq!(
+        churn
+            .iter()
+            .map(|bucket| bucket.disconnects(CloseReason::ClientGoodbye))
+            .sum::<u64>(),
+        1
+    );
+
+    metrics.reset();
+    assert!(metrics.churn().is_empty());
+}
@@ -0,0 +1,141 @@
+#![cfg(all(feature = "server", unix))]
+
+use embedded_recruitment_task::client::Client;
+#[cfg(feature = "daemon")]
+use std::{
+    fs, thread,
+    time::{Duration, Instant},
+};
+use std::{
+    io::{BufRead, BufReader},
+    net::SocketAddr,
+    process::{Child, Command, Stdio},
+};
+
+// Starts the server binary with `args`, its log on a pipe
+fn spawn(args: &[&str]) -> Child {
+    Command::new(env!("CARGO_BIN_EXE_embedded-recruitment-task"))
+        .args(args)
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .expect("Failed to start the server binary")
+}
+
+#[test]
+fn test_usage_and_invalid_arguments() {
+    let output = spawn(&["--help"]).wait_with_output().unwrap();
+    assert!(output.status.success());
+    assert!(String::from_utf8_lossy(&output.stdout).contains("--max-conns"));
+
+    let output = spawn(&["--port", "many"]).wait_with_output().unwrap();
+    assert_eq!(output.status.code(), Some(2));
+    assert!(String::from_utf8_lossy(&output.stderr).contains("--port"));
+    assert_eq!(spawn(&["--bogus"]).wait().unwrap().code(), Some(2));
+
+    // Arguments are fine, but the server cannot start
+    let output = spawn(&["--port", "0", "--config", "/nonexistent/server.toml"])
+        .wait_with_output()
+        .unwrap();
+    assert_eq!(output.status.code(), Some(1));
+}
+
+#[test]
+fn test_self_test_flag() {
+    let output = spawn(&["--self-test"]).wait_with_output().unwrap();
+    assert!(output.status.success());
+    let report = String::from_utf8_lossy(&output.stdout);
+    assert!(report.contains("Self-test against"), "{}", report);
+    assert!(report.trim_end().ends_with("PASSED"), "{}", report);
+}
+
+// Reads the log of a starting server up to the address it reports
+fn running_addr(log: &mut impl Iterator<Item = std::io::Result<String>>) -> SocketAddr {
+    log.map_while(Result::ok)
+        .find_map(|line| {
+            let (_, addr) = line.split_once("Server is running on ")?;
+            addr.parse().ok()
+        })
+        .expect("Server did not report its address")
+}
+
+#[test]
+fn test_serves_until_interrupted() {
+    let mut child = spawn(&["--addr", "127.0.0.1", "--port=0", "--max-conns", "4"]);
+    let mut log = BufReader::new(child.stderr.take().unwrap()).lines();
+    let addr = running_addr(&mut log);
+
+    let mut client = Client::connect(addr).expect("Failed to connect to the server");
+    client.ping().expect("Server does not answer");
+    drop(client);
+
+    // Ctrl-C stops the server gracefully
+    // SAFETY: signals the child started above
+    assert_eq!(
+        unsafe { libc::kill(child.id() as libc::pid_t, libc::SIGINT) },
+        0
+    );
+    let status = child.wait().unwrap();
+    assert!(status.success(), "Server exited with {}", status);
+    assert!(log
+        .map_while(Result::ok)
+        .any(|line| line.contains("Server stopped")));
+}
+
+#[test]
+fn test_ipv6_hosts_are_bracketed() {
+    let mut child = spawn(&["--addr", "::1", "--port", "0"]);
+    let mut log = BufReader::new(child.stderr.take().unwrap()).lines();
+    let addr = running_addr(&mut log);
+    assert!(addr.is_ipv6(), "Server is running on {}", addr);
+    assert!(
+        Client::connect(addr).is_ok(),
+        "Failed to connect to the server"
+    );
+
+    // SAFETY: signals the child started above
+    assert_eq!(
+        unsafe { libc::kill(child.id() as libc::pid_t, libc::SIGTERM) },
+        0
+    );
+    assert!(child.wait().unwrap().success());
+}
+
+#[cfg(feature = "daemon")]
+#[test]
+fn test_daemonized_server_keeps_a_pid_file() {
+    let name = format!("cli-test-{}.pid", std::process::id());
+    let path = std::env::temp_dir().join(&name);
+    let _ = fs::remove_file(&path);
+    // The process started here exits once the server runs in the background, the relative
+    // pid file still ends up next to where it was started
+    let status = Command::new(env!("CARGO_BIN_EXE_embedded-recruitment-task"))
+        .args(["--port", "0", "--daemonize", "--pid-file", &name])
+        .current_dir(std::env::temp_dir())
+        .status()
+        .expect("Failed to start the server binary");
+    assert!(status.success(), "Detaching failed with {}", status);
+
+    let wait_for = |done: &dyn Fn() -> bool| {
+        let deadline = Instant::now() + Duration::from_secs(5);
+        while !done() {
+            assert!(
+                Instant::now() < deadline,
+                "Timed out waiting for the daemon"
+            );
+            thread::sleep(Duration::from_millis(10));
+        }
+    };
+    let read_pid = || {
+        fs::read_to_string(&path)
+            .ok()
+            .and_then(|contents| contents.trim().parse::<libc::pid_t>().ok())
+    };
+    wait_for(&|| read_pid().is_some());
+    let pid = read_pid().unwrap();
+
+    // SIGTERM stops the daemon gracefully, which removes its pid file
+    // SAFETY: signals the daemon named by the pid file written above
+    assert_eq!(unsafe { libc::kill(pid, libc::SIGTERM) }, 0);
+    wait_for(&|| !path.exists());
+}
@@ -1,21 +1,26 @@
-use embedded_recruitment_task::message::{client_message, ServerMessage};
-use log::error;
-use log::info;
-use prost::Message;
-use std::io::Read;
-use std::io::Write;
+// Not every test uses every helper
+#![allow(dead_code)]
+
+pub use embedded_recruitment_task::client::ClientError;
+use embedded_recruitment_task::{
+    client,
+    message::{client_message, ClientMessage, ServerMessage},
+};
 use std::{
+    collections::HashMap,
     io,
-    net::{SocketAddr, TcpStream, ToSocketAddrs},
+    net::{Shutdown, TcpStream},
     time::Duration,
 };
 
-// TCP/IP Client
+// Library client behind the connect and disconnect steps the tests are written against
 pub struct Client {
-    ip: String,
-    port: u32,
-    timeout: Duration,
-    stream: Option<TcpStream>,
+    ip: String,                     // Host of the server
+    port: u32,                      // Port of the server
+    timeout: Duration,              // How long connecting and every response may take
+    nodelay: bool,                  // Whether Nagle's algorithm is disabled
+    keepalive: Option<Duration>,    // Idle time before keepalive probes, if enabled
+    client: Option<client::Client>, // Connection, while there is one
 }
 
 impl Client {
@@ -24,117 +29,81 @@ impl Client {
             ip: ip.to_string(),
             port,
             timeout: Duration::from_millis(timeout_ms),
-            stream: None,
+            nodelay: false,
+            keepalive: None,
+            client: None,
         }
     }
 
-    // connect the client to the server
-    pub fn connect(&mut self) -> io::Result<()> {
-        println!("Connecting to {}:{}", self.ip, self.port);
-
-        // Resolve the address
-        let address = format!("{}:{}", self.ip, self.port);
-        let socket_addrs: Vec<SocketAddr> = address.to_socket_addrs()?.collect();
+    // disable Nagle's algorithm on the connection
+    pub fn with_nodelay(mut self, nodelay: bool) -> Self {
+        self.nodelay = nodelay;
+        self
+    }
 
-        if socket_addrs.is_empty() {
-            return Err(io::Error::new(
-                io::ErrorKind::InvalidInput,
-                "Invalid IP or port",
-            ));
-        }
+    // enable TCP keepalive probes after the connection has been idle for `idle`
+    pub fn with_keepalive(mut self, idle: Duration) -> Self {
+        self.keepalive = Some(idle);
+        self
+    }
 
-        // Connect to the server with a timeout
-        let stream = TcpStream::connect_timeout(&socket_addrs[0], self.timeout)?;
-        self.stream = Some(stream);
+    // the underlying stream, if connected
+    pub fn stream(&self) -> Option<&TcpStream> {
+        self.client.as_ref().map(client::Client::stream)
+    }
 
-        println!("Connected to the server!");
+    // connect the client to the server
+    pub fn connect(&mut self) -> Result<(), ClientError> {
+        let options = client::ClientOptions {
+            timeout: self.timeout,
+            nodelay: self.nodelay,
+            keepalive: self.keepalive,
+        };
+        let client = client::Client::connect_with((self.ip.as_str(), self.port as u16), &options)?;
+        self.client = Some(client);
         Ok(())
     }
 
-    // disconnect the client
-    pub fn disconnect(&mut self) -> io::Result<()> {
-        if let Some(stream) = self.stream.take() {
-            stream.shutdown(std::net::Shutdown::Both)?;
+    // hang up without saying goodbye, the server sees the end of the stream
+    pub fn disconnect(&mut self) -> Result<(), ClientError> {
+        if let Some(client) = self.client.take() {
+            client
+                .stream()
+                .shutdown(Shutdown::Both)
+                .map_err(ClientError::ConnectionLost)?;
         }
-
-        println!("Disconnected from the server!");
         Ok(())
     }
 
-    // generic message to send message to the server
-    pub fn send(&mut self, message: client_message::Message) -> io::Result<()> {
-        if let Some(ref mut stream) = self.stream {
-            // Encode the message to a buffer
-            let mut buffer = Vec::new();
-            message.encode(&mut buffer);
-
-            // Send the buffer to the server
-            stream.write_all(&buffer)?;
-            stream.flush()?;
-
-            println!("Sent message: {:?}", message);
-            Ok(())
-        } else {
-            Err(io::Error::new(
-                io::ErrorKind::NotConnected,
-                "No active connection",
-            ))
-        }
-    } 
-    
+    // send a message to the server
+    pub fn send(&mut self, message: client_message::Message) -> Result<(), ClientError> {
+        self.send_with_headers(message, HashMap::new())
+    }
 
-   /*pub fn send(&mut self, message: client_message::Message) -> io::Result<()> {
-        if let Some(ref mut stream) = self.stream {
-            // Clear the buffer before sending the new message
-            stream.set_nonblocking(true)?;
-            let mut buffer: Vec<u8> = vec![0u8; 1024];
-            stream.read(&mut buffer).ok(); // Read and discard any existing data
+    // send a message carrying headers to the server
+    pub fn send_with_headers(
+        &mut self,
+        message: client_message::Message,
+        headers: HashMap<String, String>,
+    ) -> Result<(), ClientError> {
+        self.connection()?.send(ClientMessage {
+            message: Some(message),
+            headers,
+        })
+    }
 
-            // Send the new message to the server
-            stream.write_all(&buffer)?;
-            stream.flush()?;
+    // wait for the next message from the server
+    pub fn receive(&mut self) -> Result<ServerMessage, ClientError> {
+        self.connection()?.receive()
+    }
 
-            println!("Sent message: {:?}", message);
-            Ok(())
-        } else {
-            Err(io::Error::new(
-                io::ErrorKind::NotConnected,
-                "No active connection",
-            ))
-        }
-    } */ pub fn receive(&mut self) -> io::Result<ServerMessage> {
-        if let Some(ref mut stream) = self.stream {
-            info!("Receiving message from the server");
-            print!("Receiving message from the server");
-            let mut buffer: Vec<u8> = vec![0u8; 1024];
-            let bytes_read: usize = stream.read(&mut buffer)?;
-            if bytes_read == 0 {
-                info!("Server disconnected.");
-                println!("Server disconnected.");
-                return Err(io::Error::new(
-                    io::ErrorKind::ConnectionAborted,
-                    "Server disconnected",
-                ));
-            }
-            
-            info!("Received {} bytes from the server", bytes_read);
-            println!("Received {} bytes from the server", bytes_read);
-            //buffer.clear();
-            // Decode the received message
-            ServerMessage::decode(&buffer[..bytes_read]).map_err(|e| {
-                io::Error::new(
-                    io::ErrorKind::InvalidData,
-                    format!("Failed to decode ServerMessage: {}", e),
-                )
-            })
-            
-        } else {
-            error!("No active connection");
-            print!("No active connection");
-            Err(io::Error::new(
+    // the connection, an error without one
+    fn connection(&mut self) -> Result<&mut client::Client, ClientError> {
+        self.client.as_mut().ok_or_else(|| {
+            ClientError::ConnectionLost(io::Error::new(
                 io::ErrorKind::NotConnected,
                 "No active connection",
             ))
-        }
+        })
     }
 }
@@ -0,0 +1,51 @@
+#![cfg(feature = "server")]
+
+use embedded_recruitment_task::message::{client_message, AddRequest};
+use std::{io::ErrorKind, net::TcpListener};
+
+mod client;
+mod test_server;
+
+use test_server::TestServer;
+
+use client::ClientError;
+
+#[test]
+fn test_client_error_kinds() {
+    // Nothing listens on a port that was just released
+    let port = {
+        let listener = TcpListener::bind("localhost:0").unwrap();
+        listener.local_addr().unwrap().port() as u32
+    };
+    let mut client = client::Client::new("localhost", port, 1000);
+    assert!(matches!(
+        client.connect(),
+        Err(ClientError::ConnectFailed(_))
+    ));
+
+    // Sending without a connection is reported as such
+    let not_connected = |result: Result<_, ClientError>| matches!(result, Err(ClientError::ConnectionLost(e)) if e.kind() == ErrorKind::NotConnected);
+    let message = client_message::Message::AddRequest(AddRequest { a: 1, b: 2 });
+    assert!(not_connected(client.send(message)));
+    assert!(not_connected(client.receive().map(|_| ())));
+
+    // Set up the server in a separate thread
+    let server = TestServer::start();
+
+    // Waiting for an answer that never comes times out
+    let mut client = client::Client::new(&server.ip(), server.port(), 200);
+    assert!(client.connect().is_ok(), "Failed to connect to the server");
+    assert!(matches!(client.receive(), Err(ClientError::Timeout)));
+
+    // A server that goes away shows up as a lost connection
+    let mut client = client::Client::new(&server.ip(), server.port(), 5000);
+    assert!(client.connect().is_ok(), "Failed to connect to the server");
+    let message = client_message::Message::AddRequest(AddRequest { a: 1, b: 2 });
+    assert!(client.send(message).is_ok(), "Failed to send message");
+    assert!(client.receive().is_ok(), "Failed to receive response");
+    server.stop();
+    assert!(matches!(
+        client.receive(),
+        Err(ClientError::ConnectionLost(_))
+    ));
+}
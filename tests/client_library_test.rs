@@ -0,0 +1,135 @@
+#![cfg(feature = "server")]
+
+use embedded_recruitment_task::{
+    client::{Client, ClientError},
+    events::{CloseReason, ServerEvent},
+    message::{client_message, server_message, AddRequest, ClientMessage, HistoryRequest},
+    server::Server,
+};
+use std::{net::TcpListener, time::Duration};
+
+mod test_server;
+
+use test_server::TestServer;
+
+#[test]
+fn test_library_client_round_trips() {
+    let server = Server::builder()
+        .history_capacity(10)
+        .bind("127.0.0.1:0")
+        .expect("Failed to start server");
+    let events = server.subscribe_events();
+    let server = TestServer::run(server);
+
+    let mut client = Client::connect(("127.0.0.1", server.port() as u16))
+        .expect("Failed to connect to the server");
+    assert_eq!(client.send_echo("Hello, World!").unwrap(), "Hello, World!");
+    assert_eq!(client.add(40, 2).unwrap(), 42);
+
+    // Anything else goes through the generic request
+    let message = client_message::Message::HistoryRequest(HistoryRequest::default());
+    match client.request(message).unwrap().message {
+        Some(server_message::Message::HistoryResponse(history)) => {
+            assert_eq!(history.entries.len(), 1);
+            assert_eq!(history.entries[0].content, "Hello, World!");
+        }
+        _ => panic!("Expected HistoryResponse, but received a different message"),
+    }
+
+    // Dropping the client says goodbye
+    drop(client);
+    let next_event = || {
+        events
+            .recv_timeout(Duration::from_secs(5))
+            .expect("Timed out waiting for a server event")
+    };
+    assert!(matches!(next_event(), ServerEvent::Connected { .. }));
+    assert!(matches!(
+        next_event(),
+        ServerEvent::Disconnected {
+            reason: CloseReason::ClientGoodbye,
+            ..
+        }
+    ));
+
+    server.stop();
+}
+
+#[test]
+fn test_library_client_errors() {
+    // Nothing listens on a port that was just released
+    let addr = TcpListener::bind("127.0.0.1:0")
+        .unwrap()
+        .local_addr()
+        .unwrap();
+    assert!(matches!(
+        Client::connect(addr),
+        Err(ClientError::ConnectFailed(_))
+    ));
+
+    // Waiting for an answer that never comes times out
+    let server = TestServer::start();
+    let addr = ("127.0.0.1", server.port() as u16);
+    let mut client = Client::connect_timeout(addr, Duration::from_millis(200))
+        .expect("Failed to connect to the server");
+    assert!(matches!(client.receive(), Err(ClientError::Timeout)));
+
+    // A server that goes away shows up as a lost connection
+    let mut client = Client::connect(addr).expect("Failed to connect to the server");
+    assert_eq!(client.add(1, 2).unwrap(), 3);
+    server.stop();
+    assert!(matches!(
+        client.receive(),
+        Err(ClientError::ConnectionLost(_))
+    ));
+}
+
+#[test]
+fn test_split_client_sends_from_many_threads() {
+    let server = TestServer::start();
+    let client = Client::connect(("127.0.0.1", server.port() as u16))
+        .expect("Failed to connect to the server");
+    let (mut reader, writer) = client.split().expect("Failed to split client");
+
+    // Several threads share the writer while this one consumes every response
+    let senders: Vec<_> = (0..4)
+        .map(|thread| {
+            let writer = writer.clone();
+            std::thread::spawn(move || {
+                for i in 0..25 {
+                    let message = ClientMessage {
+                        message: Some(client_message::Message::AddRequest(AddRequest {
+                            a: thread * 100,
+                            b: i,
+                        })),
+                        ..ClientMessage::default()
+                    };
+                    writer.send(message).expect("Failed to send message");
+                }
+            })
+        })
+        .collect();
+
+    let mut results = Vec::new();
+    for _ in 0..100 {
+        match reader.receive().map(|response| response.message) {
+            Ok(Some(server_message::Message::AddResponse(add))) => results.push(add.result),
+            other => panic!("Expected AddResponse, but received {:?}", other),
+        }
+    }
+    for sender in senders {
+        sender.join().unwrap();
+    }
+
+    // Every request was answered exactly once, none of them was garbled
+    results.sort_unstable();
+    let mut expected: Vec<i32> = (0..4)
+        .flat_map(|thread| (0..25).map(move |i| thread * 100 + i))
+        .collect();
+    expected.sort_unstable();
+    assert_eq!(results, expected);
+
+    drop(writer);
+    drop(reader);
+    server.stop();
+}
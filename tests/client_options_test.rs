@@ -0,0 +1,73 @@
+#![cfg(feature = "server")]
+
+use embedded_recruitment_task::{
+    client::{Client, ClientOptions},
+    message::{client_message, EchoMessage},
+};
+use std::time::Duration;
+
+mod client;
+mod test_server;
+
+use test_server::TestServer;
+
+#[test]
+fn test_client_socket_options() {
+    // Set up the server in a separate thread
+    let server = TestServer::start();
+
+    // Create and connect a tuned client
+    let mut client = client::Client::new(&server.ip(), server.port(), 1000)
+        .with_nodelay(true)
+        .with_keepalive(Duration::from_secs(30));
+    assert!(client.connect().is_ok(), "Failed to connect to the server");
+
+    let stream = client.stream().expect("Client is not connected");
+    assert!(
+        stream.nodelay().unwrap(),
+        "Nagle's algorithm is still enabled"
+    );
+    assert!(
+        socket2::SockRef::from(stream).keepalive().unwrap(),
+        "TCP keepalive is not enabled"
+    );
+
+    // The connection still works as usual
+    let message = client_message::Message::EchoMessage(EchoMessage {
+        content: "Hello, World!".to_string(),
+    });
+    assert!(client.send(message).is_ok(), "Failed to send message");
+    assert!(client.receive().is_ok(), "Failed to receive echo");
+
+    // Disconnect the client
+    assert!(
+        client.disconnect().is_ok(),
+        "Failed to disconnect from the server"
+    );
+
+    // Stop the server and wait for thread to finish
+    server.stop();
+}
+
+#[test]
+fn test_library_client_socket_options() {
+    let server = TestServer::start();
+    let options = ClientOptions {
+        nodelay: true,
+        keepalive: Some(Duration::from_secs(30)),
+        ..ClientOptions::default()
+    };
+    let mut client = Client::connect_with(("127.0.0.1", server.port() as u16), &options)
+        .expect("Failed to connect to the server");
+    assert!(client.stream().nodelay().unwrap());
+    assert!(socket2::SockRef::from(client.stream()).keepalive().unwrap());
+    assert_eq!(client.send_echo("Hello").unwrap(), "Hello");
+
+    // Without options, Nagle's algorithm and keepalive stay as the platform has them
+    let client = Client::connect(("127.0.0.1", server.port() as u16))
+        .expect("Failed to connect to the server");
+    assert!(!client.stream().nodelay().unwrap());
+
+    drop(client);
+    server.stop();
+}
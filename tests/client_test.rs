@@ -1,33 +1,24 @@
+#![cfg(feature = "server")]
+
 use embedded_recruitment_task::{
-    message::{client_message, server_message, AddRequest, EchoMessage},
-    server::Server,
-};
-use std::{
-    sync::Arc,
-    thread::{self, JoinHandle},
+    framing,
+    message::{client_message, server_message, AddRequest, ClientMessage, EchoMessage},
 };
+use prost::Message;
+use std::{io::Write, thread, time::Duration};
 
 mod client;
+mod test_server;
 
-fn setup_server_thread(server: Arc<Server>) -> JoinHandle<()> {
-    thread::spawn(move || {
-        server.run().expect("Server encountered an error");
-    })
-}
-
-fn create_server() -> Arc<Server> {
-    Arc::new(Server::new("localhost:8080").expect("Failed to start server"))
-}
+use test_server::TestServer;
 
 #[test]
-#[ignore = "please remove ignore and fix this test"]
 fn test_client_connection() {
     // Set up the server in a separate thread
-    let server = create_server();
-    let handle = setup_server_thread(server.clone());
+    let server = TestServer::start();
 
     // Create and connect the client
-    let mut client = client::Client::new("localhost", 8080, 1000);
+    let mut client = client::Client::new(&server.ip(), server.port(), 1000);
     assert!(client.connect().is_ok(), "Failed to connect to the server");
 
     // Disconnect the client
@@ -38,26 +29,21 @@ fn test_client_connection() {
 
     // Stop the server and wait for thread to finish
     server.stop();
-    assert!(
-        handle.join().is_ok(),
-        "Server thread panicked or failed to join"
-    );
 }
 
 #[test]
-#[ignore = "please remove ignore and fix this test"]
 fn test_client_echo_message() {
     // Set up the server in a separate thread
-    let server = create_server();
-    let handle = setup_server_thread(server.clone());
+    let server = TestServer::start();
 
     // Create and connect the client
-    let mut client = client::Client::new("localhost", 8080, 1000);
+    let mut client = client::Client::new(&server.ip(), server.port(), 1000);
     assert!(client.connect().is_ok(), "Failed to connect to the server");
 
     // Prepare the message
-    let mut echo_message = EchoMessage::default();
-    echo_message.content = "Hello, World!".to_string();
+    let echo_message = EchoMessage {
+        content: "Hello, World!".to_string(),
+    };
     let message = client_message::Message::EchoMessage(echo_message.clone());
 
     // Send the message to the server
@@ -88,25 +74,19 @@ fn test_client_echo_message() {
 
     // Stop the server and wait for thread to finish
     server.stop();
-    assert!(
-        handle.join().is_ok(),
-        "Server thread panicked or failed to join"
-    );
 }
 
 #[test]
-#[ignore = "please remove ignore and fix this test"]
 fn test_multiple_echo_messages() {
     // Set up the server in a separate thread
-    let server = create_server();
-    let handle = setup_server_thread(server.clone());
+    let server = TestServer::start();
 
     // Create and connect the client
-    let mut client = client::Client::new("localhost", 8080, 1000);
+    let mut client = client::Client::new(&server.ip(), server.port(), 1000);
     assert!(client.connect().is_ok(), "Failed to connect to the server");
 
     // Prepare multiple messages
-    let messages = vec![
+    let messages = [
         "Hello, World!".to_string(),
         "How are you?".to_string(),
         "Goodbye!".to_string(),
@@ -114,8 +94,9 @@ fn test_multiple_echo_messages() {
 
     // Send and receive multiple messages
     for message_content in messages {
-        let mut echo_message = EchoMessage::default();
-        echo_message.content = message_content.clone();
+        let echo_message = EchoMessage {
+            content: message_content.clone(),
+        };
         let message = client_message::Message::EchoMessage(echo_message);
 
         // Send the message to the server
@@ -147,24 +128,18 @@ fn test_multiple_echo_messages() {
 
     // Stop the server and wait for thread to finish
     server.stop();
-    assert!(
-        handle.join().is_ok(),
-        "Server thread panicked or failed to join"
-    );
 }
 
 #[test]
-#[ignore = "please remove ignore and fix this test"]
 fn test_multiple_clients() {
     // Set up the server in a separate thread
-    let server = create_server();
-    let handle = setup_server_thread(server.clone());
+    let server = TestServer::start();
 
     // Create and connect multiple clients
-    let mut clients = vec![
-        client::Client::new("localhost", 8080, 1000),
-        client::Client::new("localhost", 8080, 1000),
-        client::Client::new("localhost", 8080, 1000),
+    let mut clients = [
+        client::Client::new(&server.ip(), server.port(), 1000),
+        client::Client::new(&server.ip(), server.port(), 1000),
+        client::Client::new(&server.ip(), server.port(), 1000),
     ];
 
     for client in clients.iter_mut() {
@@ -172,7 +147,7 @@ fn test_multiple_clients() {
     }
 
     // Prepare multiple messages
-    let messages = vec![
+    let messages = [
         "Hello, World!".to_string(),
         "How are you?".to_string(),
         "Goodbye!".to_string(),
@@ -180,8 +155,9 @@ fn test_multiple_clients() {
 
     // Send and receive multiple messages for each client
     for message_content in messages {
-        let mut echo_message = EchoMessage::default();
-        echo_message.content = message_content.clone();
+        let echo_message = EchoMessage {
+            content: message_content.clone(),
+        };
         let message = client_message::Message::EchoMessage(echo_message.clone());
 
         for client in clients.iter_mut() {
@@ -220,28 +196,20 @@ fn test_multiple_clients() {
 
     // Stop the server and wait for thread to finish
     server.stop();
-    assert!(
-        handle.join().is_ok(),
-        "Server thread panicked or failed to join"
-    );
 }
 
 #[test]
-//#[ignore = "please remove ignore and fix this test"]
 fn test_client_add_request() {
     // Set up the server in a separate thread
-    let server = create_server();
-    let handle = setup_server_thread(server.clone());
+    let server = TestServer::start();
 
     // Create and connect the client
-    let mut client = client::Client::new("localhost", 8080, 1000);
+    let mut client = client::Client::new(&server.ip(), server.port(), 1000);
     assert!(client.connect().is_ok(), "Failed to connect to the server");
 
     // Prepare the message
-    let mut add_request = AddRequest::default();
-    add_request.a = 10;
-    add_request.b = 20;
-    let message = client_message::Message::AddRequest(add_request.clone());
+    let add_request = AddRequest { a: 10, b: 20 };
+    let message = client_message::Message::AddRequest(add_request);
 
     // Send the message to the server
     assert!(client.send(message).is_ok(), "Failed to send message");
@@ -272,8 +240,57 @@ fn test_client_add_request() {
 
     // Stop the server and wait for thread to finish
     server.stop();
+}
+
+#[test]
+fn test_coalesced_and_split_messages() {
+    // Set up the server in a separate thread
+    let server = TestServer::start();
+    let mut client = client::Client::new(&server.ip(), server.port(), 1000);
+    assert!(client.connect().is_ok(), "Failed to connect to the server");
+
+    let add = |a, b| {
+        let message = ClientMessage {
+            message: Some(client_message::Message::AddRequest(AddRequest { a, b })),
+            ..ClientMessage::default()
+        };
+        framing::encode_frame(&message.encode_to_vec())
+    };
+    let expect_sum = |client: &mut client::Client, sum| match client.receive() {
+        Ok(response) => match response.message {
+            Some(server_message::Message::AddResponse(add_response)) => {
+                assert_eq!(
+                    add_response.result, sum,
+                    "AddResponse result does not match"
+                );
+            }
+            _ => panic!("Expected AddResponse, but received a different message"),
+        },
+        Err(e) => panic!("Failed to receive response: {}", e),
+    };
+
+    // Two requests arriving in a single segment are both answered
+    let mut stream = client.stream().expect("Client is not connected");
+    let mut coalesced = add(1, 2);
+    coalesced.extend(add(3, 4));
+    stream.write_all(&coalesced).unwrap();
+    expect_sum(&mut client, 3);
+    expect_sum(&mut client, 7);
+
+    // A request arriving in pieces is answered once it is complete
+    let split = add(5, 6);
+    let mut stream = client.stream().expect("Client is not connected");
+    stream.write_all(&split[..3]).unwrap();
+    thread::sleep(Duration::from_millis(50));
+    stream.write_all(&split[3..]).unwrap();
+    expect_sum(&mut client, 11);
+
+    // Disconnect the client
     assert!(
-        handle.join().is_ok(),
-        "Server thread panicked or failed to join"
+        client.disconnect().is_ok(),
+        "Failed to disconnect from the server"
     );
+
+    // Stop the server and wait for thread to finish
+    server.stop();
 }
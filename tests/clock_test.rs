@@ -0,0 +1,62 @@
+#![cfg(feature = "server")]
+
+use embedded_recruitment_task::{
+    clock::{Clock, MockClock, SystemClock},
+    histogram::DEFAULT_SCALE,
+    metrics::Metrics,
+    stats::MessageStats,
+};
+use std::{sync::Arc, time::Duration};
+
+#[test]
+fn test_mock_clock_moves_when_told() {
+    let clock = MockClock::new();
+    let other = clock.clone();
+    let (now, system_time) = (clock.now(), clock.system_time());
+    assert_eq!(clock.now(), now, "Mock clock moved by itself");
+
+    // Clones share the time
+    other.advance(Duration::from_secs(90));
+    assert_eq!(clock.now() - now, Duration::from_secs(90));
+    assert_eq!(
+        clock.system_time().duration_since(system_time).unwrap(),
+        Duration::from_secs(90)
+    );
+    assert_eq!(clock.elapsed(), Duration::from_secs(90));
+
+    let before = SystemClock.now();
+    assert!(SystemClock.now() >= before);
+}
+
+#[test]
+fn test_statistics_window_follows_the_clock() {
+    let clock = MockClock::new();
+    let stats = MessageStats::with_clock(
+        Duration::from_secs(60),
+        DEFAULT_SCALE,
+        Arc::new(clock.clone()),
+    );
+    stats.record("add", Duration::from_millis(1), false);
+
+    clock.advance(Duration::from_secs(60));
+    stats.record("echo", Duration::from_millis(1), false);
+    assert_eq!(stats.top(0).stats.len(), 2, "Sample left the window early");
+
+    // The addition falls out of the window, the echo is still in it
+    clock.advance(Duration::from_secs(1));
+    let top = stats.top(0);
+    assert_eq!(top.stats.len(), 1);
+    assert_eq!(top.stats[0].message_type, "echo");
+}
+
+#[test]
+fn test_churn_buckets_follow_the_clock() {
+    let clock = MockClock::new();
+    let metrics = Metrics::with_clock(DEFAULT_SCALE, Arc::new(clock.clone()));
+    metrics.record_connect();
+    assert_eq!(metrics.churn().len(), 1);
+
+    // An hour later the connect is forgotten
+    clock.advance(Duration::from_secs(2 * 60 * 60));
+    assert!(metrics.churn().is_empty());
+}
@@ -0,0 +1,86 @@
+use embedded_recruitment_task::message::{
+    client_message, server_message, AddRequest, AddResponse, ClientMessage, EchoMessage,
+    HistoryRequest, HistoryResponse, ServerMessage,
+};
+use proptest::prelude::*;
+use prost::{bytes::Buf, Message};
+use std::collections::HashMap;
+
+fn echo_message() -> impl Strategy<Value = EchoMessage> {
+    ".{0,64}".prop_map(|content| EchoMessage { content })
+}
+
+fn headers() -> impl Strategy<Value = HashMap<String, String>> {
+    proptest::collection::hash_map("[a-z-]{1,16}", ".{0,32}", 0..4)
+}
+
+fn client_message() -> impl Strategy<Value = ClientMessage> {
+    let message = prop_oneof![
+        echo_message().prop_map(client_message::Message::EchoMessage),
+        (any::<i32>(), any::<i32>())
+            .prop_map(|(a, b)| client_message::Message::AddRequest(AddRequest { a, b })),
+        (any::<u32>(), ".*").prop_map(|(limit, continuation_token)| {
+            client_message::Message::HistoryRequest(HistoryRequest {
+                limit,
+                continuation_token,
+            })
+        }),
+    ];
+    (proptest::option::of(message), headers())
+        .prop_map(|(message, headers)| ClientMessage { message, headers })
+}
+
+fn server_message() -> impl Strategy<Value = ServerMessage> {
+    let message = prop_oneof![
+        echo_message().prop_map(server_message::Message::EchoMessage),
+        any::<i32>()
+            .prop_map(|result| server_message::Message::AddResponse(AddResponse { result })),
+        (
+            proptest::collection::vec(echo_message(), 0..8),
+            any::<u32>(),
+            ".*"
+        )
+            .prop_map(|(entries, total, continuation_token)| {
+                server_message::Message::HistoryResponse(HistoryResponse {
+                    entries,
+                    total,
+                    continuation_token,
+                })
+            }),
+    ];
+    (proptest::option::of(message), headers())
+        .prop_map(|(message, headers)| ServerMessage { message, headers })
+}
+
+// Decodes a message from a buffer split in two at `split`, as if it arrived in two reads
+fn decode_split<M: Message + Default>(bytes: &[u8], split: usize) -> M {
+    let split = split % (bytes.len() + 1);
+    let (head, tail) = bytes.split_at(split);
+    M::decode(head.chain(tail)).expect("Failed to decode fragmented message")
+}
+
+proptest! {
+    #[test]
+    fn client_message_round_trip(message in client_message()) {
+        let bytes = message.encode_to_vec();
+        prop_assert_eq!(ClientMessage::decode(bytes.as_slice()).unwrap(), message);
+    }
+
+    #[test]
+    fn server_message_round_trip(message in server_message()) {
+        let bytes = message.encode_to_vec();
+        prop_assert_eq!(ServerMessage::decode(bytes.as_slice()).unwrap(), message);
+    }
+
+    #[test]
+    fn client_message_fragmented(message in client_message(), split in any::<usize>()) {
+        let bytes = message.encode_to_vec();
+        prop_assert_eq!(decode_split::<ClientMessage>(&bytes, split), message);
+    }
+
+    #[test]
+    fn server_message_fragmented(message in server_message(), split in any::<usize>()) {
+        let bytes = message.encode_to_vec();
+        prop_assert_eq!(decode_split::<ServerMessage>(&bytes, split), message);
+    }
+}
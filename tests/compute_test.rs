@@ -0,0 +1,143 @@
+#![cfg(feature = "server")]
+
+use embedded_recruitment_task::{
+    compute::{self, ComputeError, MAX_ELEMENTS},
+    message::{client_message, server_message, DotProductRequest, Matrix, MatrixMultiplyRequest},
+};
+
+mod client;
+mod test_server;
+
+use test_server::TestServer;
+
+// Builds a row-major matrix
+fn matrix(rows: u32, cols: u32, values: Vec<f32>) -> Matrix {
+    Matrix { rows, cols, values }
+}
+
+#[test]
+fn test_dot_product() {
+    assert_eq!(
+        compute::dot_product(&[1.0, 2.0, 3.0], &[4.0, 5.0, 6.0]),
+        Ok(32.0)
+    );
+    assert_eq!(compute::dot_product(&[], &[]), Ok(0.0));
+    assert_eq!(
+        compute::dot_product(&[1.0], &[1.0, 2.0]),
+        Err(ComputeError::LengthMismatch { a: 1, b: 2 })
+    );
+
+    let long = vec![1.0; MAX_ELEMENTS + 1];
+    assert_eq!(
+        compute::dot_product(&long, &long),
+        Err(ComputeError::TooLarge {
+            elements: MAX_ELEMENTS + 1
+        })
+    );
+}
+
+#[test]
+fn test_matrix_multiply() {
+    let a = matrix(2, 3, vec![1.0, 2.0, 3.0, 4.0, 5.0, 6.0]);
+    let b = matrix(3, 2, vec![7.0, 8.0, 9.0, 10.0, 11.0, 12.0]);
+    assert_eq!(
+        compute::matrix_multiply(&a, &b),
+        Ok(matrix(2, 2, vec![58.0, 64.0, 139.0, 154.0]))
+    );
+
+    // Shapes are validated before anything is computed
+    assert_eq!(
+        compute::matrix_multiply(&a, &a),
+        Err(ComputeError::DimensionMismatch {
+            a_cols: 3,
+            b_rows: 2
+        })
+    );
+    let ragged = matrix(2, 2, vec![1.0, 2.0, 3.0]);
+    assert_eq!(
+        compute::matrix_multiply(&ragged, &ragged),
+        Err(ComputeError::BadShape {
+            rows: 2,
+            cols: 2,
+            values: 3
+        })
+    );
+
+    // A tall times a wide matrix whose result exceeds the limit
+    let tall = matrix(1024, 1, vec![1.0; 1024]);
+    let wide = matrix(1, 1024, vec![1.0; 1024]);
+    assert_eq!(
+        compute::matrix_multiply(&tall, &wide),
+        Err(ComputeError::TooLarge {
+            elements: 1024 * 1024
+        })
+    );
+}
+
+#[test]
+fn test_parallel_matrix_multiply_matches_naive() {
+    // Large enough to be split across threads
+    let n = 96;
+    let a = matrix(n, n, (0..n * n).map(|i| (i % 7) as f32).collect());
+    let b = matrix(n, n, (0..n * n).map(|i| (i % 5) as f32 - 2.0).collect());
+    let product = compute::matrix_multiply(&a, &b).expect("Failed to multiply");
+
+    let n = n as usize;
+    for row in 0..n {
+        for col in 0..n {
+            let expected: f32 = (0..n)
+                .map(|k| a.values[row * n + k] * b.values[k * n + col])
+                .sum();
+            assert_eq!(
+                product.values[row * n + col],
+                expected,
+                "At {}x{}",
+                row,
+                col
+            );
+        }
+    }
+}
+
+#[test]
+fn test_numeric_requests_over_the_wire() {
+    let server = TestServer::start();
+    let mut client = client::Client::new(&server.ip(), server.port(), 1000);
+    assert!(client.connect().is_ok(), "Failed to connect to the server");
+
+    let message = client_message::Message::DotProductRequest(DotProductRequest {
+        a: vec![1.0, 2.0],
+        b: vec![3.0, 4.0],
+    });
+    assert!(client.send(message).is_ok(), "Failed to send message");
+    match client.receive().map(|response| response.message) {
+        Ok(Some(server_message::Message::DotProductResponse(response))) => {
+            assert_eq!(response.result, 11.0);
+            assert!(response.error.is_empty(), "{}", response.error);
+        }
+        _ => panic!("Expected DotProductResponse, but received a different message"),
+    }
+
+    // Invalid requests are answered with the reason they were rejected
+    let message = client_message::Message::MatrixMultiplyRequest(MatrixMultiplyRequest {
+        a: Some(matrix(1, 2, vec![1.0, 2.0])),
+        b: Some(matrix(1, 1, vec![3.0])),
+    });
+    assert!(client.send(message).is_ok(), "Failed to send message");
+    match client.receive().map(|response| response.message) {
+        Ok(Some(server_message::Message::MatrixMultiplyResponse(response))) => {
+            assert_eq!(response.result, None);
+            assert_eq!(
+                response.error,
+                "Cannot multiply a matrix with 2 columns by one with 1 rows"
+            );
+        }
+        _ => panic!("Expected MatrixMultiplyResponse, but received a different message"),
+    }
+
+    assert!(
+        client.disconnect().is_ok(),
+        "Failed to disconnect from the server"
+    );
+    server.stop();
+}
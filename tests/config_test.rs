@@ -0,0 +1,139 @@
+#![cfg(feature = "config")]
+
+use embedded_recruitment_task::{
+    client::Client,
+    config::{Config, ConfigError},
+    server::Server,
+};
+use log::LevelFilter;
+use std::{fs, io::ErrorKind, time::Duration};
+
+mod test_server;
+
+use test_server::TestServer;
+
+// Variables named as in the environment
+fn vars(pairs: &[(&str, &str)]) -> Vec<(String, String)> {
+    pairs
+        .iter()
+        .map(|(name, value)| (name.to_string(), value.to_string()))
+        .collect()
+}
+
+#[test]
+fn test_settings_are_read_from_toml() {
+    let config = Config::from_toml(
+        r#"
+        address = "127.0.0.1:0"
+        log_level = "debug"
+
+        [limits]
+        max_connections = 10
+        kv_capacity = 5
+        max_headers = 4
+
+        [timeouts]
+        read = 1.5
+        close = 2
+        "#,
+    )
+    .expect("Failed to parse the settings");
+    assert_eq!(config.address.as_deref(), Some("127.0.0.1:0"));
+    assert_eq!(config.log_level, Some(LevelFilter::Debug));
+    assert_eq!(config.max_connections, Some(10));
+    assert_eq!(config.read_timeout, Some(Duration::from_millis(1500)));
+    assert_eq!(config.close_timeout, Some(Duration::from_secs(2)));
+    assert_eq!(config.write_timeout, None);
+
+    let server_config = config.server_config();
+    assert_eq!(server_config.max_connections, Some(10));
+    assert_eq!(server_config.kv_capacity, 5);
+    assert_eq!(server_config.header_limits.max_count, 4);
+    assert_eq!(
+        server_config.read_timeout,
+        Some(Duration::from_millis(1500))
+    );
+}
+
+#[test]
+fn test_invalid_settings_are_rejected() {
+    let error = |contents: &str| Config::from_toml(contents).expect_err(contents);
+    assert!(matches!(error("address = "), ConfigError::Parse { .. }));
+    assert!(matches!(
+        error("adress = \"127.0.0.1:0\""),
+        ConfigError::UnknownSetting { key } if key == "adress"
+    ));
+    assert!(matches!(
+        error("[limits]\nmax_connections = -1"),
+        ConfigError::InvalidValue { key, .. } if key == "limits.max_connections"
+    ));
+    assert!(matches!(
+        error("log_level = \"loud\""),
+        ConfigError::InvalidValue { .. }
+    ));
+    assert!(matches!(
+        error("limits = 3"),
+        ConfigError::InvalidValue { .. }
+    ));
+}
+
+#[test]
+fn test_environment_overrides_the_file() {
+    let mut config =
+        Config::from_toml("address = \"0.0.0.0:8080\"\n[limits]\nkv_capacity = 5").unwrap();
+    config
+        .apply_vars(vars(&[
+            ("PATH", "/usr/bin"),
+            ("SERVER_SERVICE_HOST", "10.0.0.1"),
+            ("EMBEDDED_TASK_ADDRESS", "127.0.0.1:0"),
+            ("EMBEDDED_TASK_LIMITS_KV_CAPACITY", "7"),
+            ("EMBEDDED_TASK_TIMEOUTS_HEARTBEAT", "0.25"),
+        ]))
+        .expect("Failed to apply the overrides");
+    assert_eq!(config.address.as_deref(), Some("127.0.0.1:0"));
+    assert_eq!(config.kv_capacity, Some(7));
+    assert_eq!(config.heartbeat_interval, Some(Duration::from_millis(250)));
+
+    let error = config
+        .apply_vars(vars(&[("EMBEDDED_TASK_LIMIT_KV_CAPACITY", "7")]))
+        .expect_err("A misspelt variable was ignored");
+    assert!(matches!(error, ConfigError::UnknownSetting { .. }));
+    let error = config
+        .apply_vars(vars(&[("EMBEDDED_TASK_LIMITS_KV_CAPACITY", "many")]))
+        .expect_err("A malformed value was accepted");
+    assert!(matches!(error, ConfigError::InvalidValue { .. }));
+}
+
+#[test]
+fn test_server_starts_from_a_config_file() {
+    let path = std::env::temp_dir().join(format!("config-test-{}.toml", std::process::id()));
+    fs::write(
+        &path,
+        "address = \"127.0.0.1:0\"\n[limits]\nkv_capacity = 3\n",
+    )
+    .unwrap();
+    let config = Config::load(&path).expect("Failed to load the settings");
+    fs::remove_file(&path).unwrap();
+    let missing = Config::load(&path).expect_err("A missing file was loaded");
+    assert!(matches!(missing, ConfigError::Read { .. }));
+
+    let server = TestServer::run(Server::from_config(config.clone()).expect("Failed to start"));
+    let mut client = Client::connect(("127.0.0.1", server.port() as u16))
+        .expect("Failed to connect to the server");
+    client.ping().expect("Server does not answer");
+    drop(client);
+    server.stop();
+
+    let unaddressed = Config {
+        address: None,
+        ..config.clone()
+    };
+    let error = Server::from_config(unaddressed).err().unwrap();
+    assert_eq!(error.kind(), ErrorKind::InvalidInput);
+    let tls = Config {
+        tls_cert: Some("server.pem".into()),
+        ..config
+    };
+    let error = Server::from_config(tls).err().unwrap();
+    assert_eq!(error.kind(), ErrorKind::Unsupported);
+}
@@ -0,0 +1,81 @@
+#![cfg(feature = "server")]
+
+use embedded_recruitment_task::{
+    client::Client,
+    message::server_message,
+    server::{OverflowPolicy, Server},
+};
+use std::{
+    io::Read,
+    net::TcpStream,
+    thread,
+    time::{Duration, Instant},
+};
+
+mod test_server;
+
+use test_server::TestServer;
+
+#[test]
+fn test_connections_beyond_the_limit_are_told_busy() {
+    let server = Server::builder()
+        .max_connections(1, OverflowPolicy::SendBusy)
+        .bind("127.0.0.1:0")
+        .expect("Failed to start server");
+    let server = TestServer::run(server);
+    let addr = ("127.0.0.1", server.port() as u16);
+
+    let mut first = Client::connect(addr).expect("Failed to connect to the server");
+    assert_eq!(first.add(1, 2).unwrap(), 3);
+    assert_eq!(server.server().connection_count(), 1);
+
+    // The second client is told why, then disconnected
+    let mut second = Client::connect(addr).expect("Failed to connect to the server");
+    match second.receive().unwrap().message {
+        Some(server_message::Message::ServerBusy(busy)) => assert_eq!(busy.max_connections, 1),
+        _ => panic!("Expected ServerBusy, but received a different message"),
+    }
+    assert!(second.receive().is_err());
+    assert_eq!(server.server().connection_count(), 1);
+
+    // Once the first client leaves there is room again
+    drop(first);
+    let deadline = Instant::now() + Duration::from_secs(5);
+    while server.server().connection_count() > 0 {
+        assert!(Instant::now() < deadline, "Connection was not torn down");
+        thread::sleep(Duration::from_millis(10));
+    }
+    let mut third = Client::connect(addr).expect("Failed to connect to the server");
+    assert_eq!(third.add(3, 4).unwrap(), 7);
+
+    drop(third);
+    server.stop();
+}
+
+#[test]
+fn test_connections_beyond_the_limit_can_be_refused() {
+    let server = Server::builder()
+        .max_connections(1, OverflowPolicy::Refuse)
+        .bind("127.0.0.1:0")
+        .expect("Failed to start server");
+    let server = TestServer::run(server);
+    let addr = ("127.0.0.1", server.port() as u16);
+
+    let mut first = Client::connect(addr).expect("Failed to connect to the server");
+    assert_eq!(first.add(1, 2).unwrap(), 3);
+
+    // The second connection is closed without a word
+    let mut second = TcpStream::connect(addr).expect("Failed to connect to the server");
+    second
+        .set_read_timeout(Some(Duration::from_secs(5)))
+        .unwrap();
+    let mut received = Vec::new();
+    let _ = second.read_to_end(&mut received);
+    assert!(received.is_empty());
+
+    // The first one is unaffected
+    assert_eq!(first.add(3, 4).unwrap(), 7);
+
+    drop(first);
+    server.stop();
+}
@@ -0,0 +1,66 @@
+#![cfg(feature = "console")]
+
+use embedded_recruitment_task::{
+    message::{client_message, EchoMessage},
+    server::Server,
+};
+use std::{
+    io::Read,
+    net::{TcpListener, TcpStream},
+    time::Duration,
+};
+
+mod client;
+mod test_server;
+
+use test_server::TestServer;
+
+#[test]
+fn test_console_shows_connected_clients() {
+    // Stand in for the terminal the console draws on
+    let terminal = TcpListener::bind("127.0.0.1:0").expect("Failed to bind terminal");
+    let writer =
+        TcpStream::connect(terminal.local_addr().unwrap()).expect("Failed to connect to terminal");
+    let (mut screen, _) = terminal.accept().expect("Failed to accept console");
+
+    let server = Server::new("127.0.0.1:0").expect("Failed to start server");
+    let console = server
+        .console(writer, Duration::from_millis(50))
+        .expect("Failed to spawn the console");
+    assert_eq!(console.thread().name(), Some("server-console"));
+    let server = TestServer::run(server);
+
+    let mut client = client::Client::new(&server.ip(), server.port(), 1000);
+    assert!(client.connect().is_ok(), "Failed to connect to the server");
+    let message = client_message::Message::EchoMessage(EchoMessage {
+        content: "Hello, console!".to_string(),
+    });
+    assert!(client.send(message).is_ok(), "Failed to send message");
+    assert!(client.receive().is_ok(), "Failed to receive response");
+
+    // Redrawn frames eventually show the client and the message it sent
+    let mut output = String::new();
+    let mut chunk = [0; 4096];
+    while !(output.contains("1 connected") && output.contains("echo")) {
+        let read = screen.read(&mut chunk).expect("Failed to read console");
+        assert!(read > 0, "Console stopped early: {}", output);
+        output.push_str(&String::from_utf8_lossy(&chunk[..read]));
+    }
+    assert!(
+        output.contains("\x1b[2J"),
+        "Console does not clear the screen"
+    );
+    assert!(
+        output.contains("ADDRESS") && output.contains("RATE/S"),
+        "Missing table header: {}",
+        output
+    );
+
+    // Dropping the server ends the console
+    assert!(
+        client.disconnect().is_ok(),
+        "Failed to disconnect from the server"
+    );
+    server.stop();
+    assert!(console.join().unwrap().is_ok(), "Console failed");
+}
@@ -0,0 +1,44 @@
+#![cfg(feature = "daemon")]
+
+use embedded_recruitment_task::daemon::PidFile;
+use std::{fs, io::ErrorKind, path::PathBuf, process};
+
+// A pid file path of its own for every test
+fn pid_path(name: &str) -> PathBuf {
+    let path = std::env::temp_dir().join(format!("daemon-test-{}-{}.pid", process::id(), name));
+    let _ = fs::remove_file(&path);
+    path
+}
+
+#[test]
+fn test_pid_file_holds_the_process_id() {
+    let path = pid_path("holds");
+    let pid_file = PidFile::create(&path).expect("Failed to create pid file");
+    assert_eq!(pid_file.path(), path);
+    assert_eq!(
+        fs::read_to_string(&path).unwrap().trim(),
+        process::id().to_string()
+    );
+
+    // The process is still running, so a second server is turned away
+    let error = PidFile::create(&path).expect_err("Pid file was taken over");
+    assert_eq!(error.kind(), ErrorKind::AlreadyExists);
+
+    drop(pid_file);
+    assert!(!path.exists(), "Pid file was left behind");
+}
+
+#[test]
+fn test_stale_pid_file_is_replaced() {
+    let path = pid_path("stale");
+    // No process has this id, pids stay far below it
+    fs::write(&path, format!("{}\n", i32::MAX)).unwrap();
+
+    let pid_file = PidFile::create(&path).expect("Stale pid file was not replaced");
+    assert_eq!(
+        fs::read_to_string(&path).unwrap().trim(),
+        process::id().to_string()
+    );
+    drop(pid_file);
+    assert!(!path.exists());
+}
@@ -0,0 +1,63 @@
+#![cfg(feature = "server")]
+
+use embedded_recruitment_task::{
+    client::Client,
+    clock::MockClock,
+    message::{client_message, server_message, HistoryRequest},
+    server::Server,
+};
+use std::time::Duration;
+
+mod test_server;
+
+use test_server::TestServer;
+
+// Returns the contents of the echoes the server recorded in its history
+fn history(client: &mut Client) -> Vec<String> {
+    let message = client_message::Message::HistoryRequest(HistoryRequest::default());
+    match client.request(message).unwrap().message {
+        Some(server_message::Message::HistoryResponse(history)) => history
+            .entries
+            .into_iter()
+            .map(|entry| entry.content)
+            .collect(),
+        _ => panic!("Expected HistoryResponse, but received a different message"),
+    }
+}
+
+#[test]
+fn test_repeated_echoes_are_answered_once_handled() {
+    let clock = MockClock::new();
+    let server = Server::builder()
+        .history_capacity(10)
+        .echo_dedup(Duration::from_millis(500))
+        .clock(clock.clone())
+        .bind("127.0.0.1:0")
+        .expect("Failed to start server");
+    let server = TestServer::run(server);
+    let addr = ("127.0.0.1", server.port() as u16);
+
+    // Retries still get their answer, but only the first one is handled
+    let mut client = Client::connect(addr).expect("Failed to connect to the server");
+    for _ in 0..3 {
+        assert_eq!(client.send_echo("retry").unwrap(), "retry");
+    }
+    assert_eq!(client.send_echo("other").unwrap(), "other");
+    let mut recorded = history(&mut client);
+    recorded.sort();
+    assert_eq!(recorded, ["other", "retry"]);
+
+    // The same echo from another client is not a retry
+    let mut another = Client::connect(addr).expect("Failed to connect to the server");
+    assert_eq!(another.send_echo("retry").unwrap(), "retry");
+    assert_eq!(history(&mut another).len(), 3);
+
+    // Nor is one arriving after the window
+    clock.advance(Duration::from_millis(600));
+    assert_eq!(client.send_echo("retry").unwrap(), "retry");
+    assert_eq!(history(&mut client).len(), 4);
+
+    drop(client);
+    drop(another);
+    server.stop();
+}
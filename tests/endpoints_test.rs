@@ -0,0 +1,95 @@
+#![cfg(feature = "server")]
+
+use embedded_recruitment_task::{
+    endpoints::{EndpointOptions, Endpoints},
+    extract::from_fn,
+    message::{PingRequest, PongResponse},
+    server::Server,
+};
+use std::{
+    net::{SocketAddr, TcpListener},
+    thread,
+    time::Duration,
+};
+
+mod test_server;
+
+use test_server::TestServer;
+
+// Starts a server answering pings after `delay`
+fn start(delay: Duration) -> TestServer {
+    let server = Server::builder()
+        .handler(
+            "ping",
+            from_fn(move |ping: PingRequest| {
+                thread::sleep(delay);
+                PongResponse { nonce: ping.nonce }
+            }),
+        )
+        .bind("127.0.0.1:0")
+        .expect("Failed to start server");
+    TestServer::run(server)
+}
+
+// Address of a server
+fn addr(server: &TestServer) -> SocketAddr {
+    ([127, 0, 0, 1], server.port() as u16).into()
+}
+
+// Address nothing listens on
+fn closed_addr() -> SocketAddr {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    listener.local_addr().unwrap()
+}
+
+#[test]
+fn test_preference_changes_only_by_the_margin() {
+    let a: SocketAddr = ([127, 0, 0, 1], 1).into();
+    let b: SocketAddr = ([127, 0, 0, 1], 2).into();
+    let mut endpoints = Endpoints::new([a, b]);
+    assert_eq!(endpoints.preferred(), None);
+
+    endpoints.record(a, Some(Duration::from_millis(100)));
+    endpoints.record(b, Some(Duration::from_millis(90)));
+    assert_eq!(endpoints.preferred(), Some(a), "Switched for a 10% gain");
+
+    endpoints.record(b, Some(Duration::from_millis(50)));
+    assert_eq!(endpoints.preferred(), Some(b));
+    assert_eq!(endpoints.rtt(b), Some(Duration::from_millis(50)));
+
+    // An unhealthy endpoint is left regardless of the margin
+    endpoints.record(a, Some(Duration::from_millis(55)));
+    endpoints.record(b, None);
+    assert_eq!(endpoints.preferred(), Some(a));
+    endpoints.record(a, None);
+    assert_eq!(endpoints.preferred(), None);
+}
+
+#[test]
+fn test_connects_to_the_fastest_healthy_endpoint() {
+    let slow = start(Duration::from_millis(200));
+    let fast = start(Duration::ZERO);
+    let closed = closed_addr();
+    let options = EndpointOptions {
+        probe_interval: Duration::from_secs(60),
+        ..EndpointOptions::default()
+    };
+    let mut endpoints = Endpoints::with_options([closed, addr(&slow), addr(&fast)], options);
+
+    // The first connection probes every endpoint
+    let mut client = endpoints.connect().expect("Failed to connect");
+    assert_eq!(endpoints.preferred(), Some(addr(&fast)));
+    assert_eq!(endpoints.rtt(closed), None);
+    assert!(endpoints.rtt(addr(&slow)).unwrap() >= Duration::from_millis(200));
+    client.ping().expect("Connected to an unusable endpoint");
+    drop(client);
+
+    // Once the fast endpoint is gone the slow one takes over
+    fast.stop();
+    let client = endpoints.connect().expect("Failed to fall back");
+    assert_eq!(endpoints.preferred(), Some(addr(&slow)));
+    drop(client);
+
+    slow.stop();
+    assert!(endpoints.connect().is_err());
+}
@@ -0,0 +1,120 @@
+#![cfg(feature = "server")]
+
+use embedded_recruitment_task::{
+    client::{Client, ClientError},
+    framing,
+    handler::RequestContext,
+    message::{client_message, server_message, EchoMessage, ErrorCode, ErrorResponse},
+    server::Server,
+};
+use prost::Message;
+
+mod test_server;
+
+use test_server::TestServer;
+
+// Sends a raw payload and returns the ErrorResponse it is answered with
+fn send_raw(client: &mut Client, payload: &[u8]) -> ErrorResponse {
+    framing::write_frame(&mut client.stream(), payload).expect("Failed to send payload");
+    match client.receive().unwrap().message {
+        Some(server_message::Message::ErrorResponse(error)) => error,
+        _ => panic!("Expected ErrorResponse, but received a different message"),
+    }
+}
+
+#[test]
+fn test_undecodable_payload_is_answered() {
+    let server = TestServer::start();
+    let mut client = Client::connect(("127.0.0.1", server.port() as u16))
+        .expect("Failed to connect to the server");
+
+    let error = send_raw(&mut client, &[0xff; 4]);
+    assert_eq!(error.code(), ErrorCode::DecodeFailed);
+    assert!(!error.description.is_empty());
+
+    // The connection stays usable
+    assert_eq!(client.add(1, 2).unwrap(), 3);
+
+    drop(client);
+    server.stop();
+}
+
+#[test]
+fn test_unknown_message_type_is_answered() {
+    let server = TestServer::start();
+    let mut client = Client::connect(("127.0.0.1", server.port() as u16))
+        .expect("Failed to connect to the server");
+
+    // An empty message in field 1000 of the oneof, which this server does not know
+    let error = send_raw(&mut client, &[0xc2, 0x3e, 0]);
+    assert_eq!(error.code(), ErrorCode::UnknownMessageType);
+    assert_eq!(client.add(1, 2).unwrap(), 3);
+
+    drop(client);
+    server.stop();
+}
+
+#[test]
+fn test_unwrapped_echo_is_rejected() {
+    let server = TestServer::start();
+    let mut client = Client::connect(("127.0.0.1", server.port() as u16))
+        .expect("Failed to connect to the server");
+
+    // An EchoMessage that is not wrapped in a ClientMessage is not echoed
+    let echo = EchoMessage {
+        content: "Hello".to_string(),
+    };
+    let error = send_raw(&mut client, &echo.encode_to_vec());
+    assert_eq!(error.code(), ErrorCode::DecodeFailed);
+
+    // Wrapped, echoes of any length come back
+    let content = "x".repeat(1000);
+    assert_eq!(client.send_echo(&content).unwrap(), content);
+
+    drop(client);
+    server.stop();
+}
+
+#[test]
+fn test_handler_failure_is_answered() {
+    let server = Server::builder()
+        .handler(
+            "dot_product",
+            |_: client_message::Message, _: &RequestContext<'_>| panic!("Handler failed"),
+        )
+        .bind("127.0.0.1:0")
+        .expect("Failed to start server");
+    let server = TestServer::run(server);
+    let mut client = Client::connect(("127.0.0.1", server.port() as u16))
+        .expect("Failed to connect to the server");
+
+    let request = client_message::Message::DotProductRequest(Default::default());
+    match client.request(request) {
+        Err(ClientError::Rejected(error)) => assert_eq!(error.code(), ErrorCode::HandlerFailed),
+        other => panic!("Expected the request to be rejected, got {:?}", other),
+    }
+    assert_eq!(client.add(1, 2).unwrap(), 3);
+
+    drop(client);
+    server.stop();
+}
+
+#[test]
+fn test_overflowing_addition_is_rejected() {
+    let server = TestServer::start();
+    let mut client = Client::connect(("127.0.0.1", server.port() as u16))
+        .expect("Failed to connect to the server");
+
+    for (a, b) in [(i32::MAX, 1), (i32::MIN, -1)] {
+        match client.add(a, b) {
+            Err(ClientError::Rejected(error)) => {
+                assert_eq!(error.code(), ErrorCode::ArithmeticOverflow)
+            }
+            other => panic!("Expected the addition to be rejected, got {:?}", other),
+        }
+    }
+    assert_eq!(client.add(i32::MAX, -1).unwrap(), i32::MAX - 1);
+
+    drop(client);
+    server.stop();
+}
@@ -0,0 +1,96 @@
+#![cfg(feature = "server")]
+
+use embedded_recruitment_task::{
+    events::{CloseReason, ServerEvent},
+    message::{client_message, EchoMessage},
+};
+use std::time::Duration;
+
+mod client;
+mod test_server;
+
+use test_server::TestServer;
+
+#[test]
+fn test_close_reasons() {
+    // Set up the server and subscribe to its events before any client connects
+    let server = TestServer::start();
+    let events = server.server().subscribe_events();
+    let next_event = || {
+        events
+            .recv_timeout(Duration::from_secs(5))
+            .expect("Timed out waiting for a server event")
+    };
+
+    // A client that hangs up on its own is reported as EOF
+    let mut client = client::Client::new(&server.ip(), server.port(), 1000);
+    assert!(client.connect().is_ok(), "Failed to connect to the server");
+    assert!(matches!(next_event(), ServerEvent::Connected { .. }));
+    let message = client_message::Message::EchoMessage(EchoMessage {
+        content: "Hello, World!".to_string(),
+    });
+    assert!(client.send(message).is_ok(), "Failed to send message");
+    assert!(client.receive().is_ok(), "Failed to receive echo");
+    assert!(
+        client.disconnect().is_ok(),
+        "Failed to disconnect from the server"
+    );
+    match next_event() {
+        ServerEvent::Disconnected { reason, .. } => assert_eq!(reason, CloseReason::ClientEof),
+        event => panic!("Expected Disconnected event, got {:?}", event),
+    }
+
+    // A client that is still connected when the server stops is reported as shutdown
+    let mut client = client::Client::new(&server.ip(), server.port(), 1000);
+    assert!(client.connect().is_ok(), "Failed to connect to the server");
+    assert!(matches!(next_event(), ServerEvent::Connected { .. }));
+    server.server().stop();
+    match next_event() {
+        ServerEvent::Disconnected { reason, .. } => assert_eq!(reason, CloseReason::Shutdown),
+        event => panic!("Expected Disconnected event, got {:?}", event),
+    }
+
+    // Every close was counted under its reason
+    let metrics = server.server().metrics();
+    assert_eq!(metrics.connections_accepted(), 2);
+    assert_eq!(metrics.connections_closed(CloseReason::ClientEof), 1);
+    assert_eq!(metrics.connections_closed(CloseReason::Shutdown), 1);
+    assert_eq!(metrics.connections_closed(CloseReason::ReadError), 0);
+
+    // Wait for the server thread to finish
+    server.stop();
+}
+
+#[test]
+fn test_dropped_client_says_goodbye() {
+    // Set up the server and subscribe to its events before any client connects
+    let server = TestServer::start();
+    let events = server.server().subscribe_events();
+    let next_event = || {
+        events
+            .recv_timeout(Duration::from_secs(5))
+            .expect("Timed out waiting for a server event")
+    };
+
+    // A client going out of scope is an intentional disconnect, not a crash
+    let mut client = client::Client::new(&server.ip(), server.port(), 1000);
+    assert!(client.connect().is_ok(), "Failed to connect to the server");
+    assert!(matches!(next_event(), ServerEvent::Connected { .. }));
+    drop(client);
+    match next_event() {
+        ServerEvent::Disconnected { reason, .. } => {
+            assert_eq!(reason, CloseReason::ClientGoodbye)
+        }
+        event => panic!("Expected Disconnected event, got {:?}", event),
+    }
+    assert_eq!(
+        server
+            .server()
+            .metrics()
+            .connections_closed(CloseReason::ClientGoodbye),
+        1
+    );
+
+    // Stop the server and wait for thread to finish
+    server.stop();
+}
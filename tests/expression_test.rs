@@ -0,0 +1,103 @@
+#![cfg(feature = "server")]
+
+use embedded_recruitment_task::{
+    client::Client,
+    expression::{self, ExpressionError, MAX_DEPTH, MAX_EXPRESSION_LEN},
+    message::{client_message, server_message, CalculateRequest, CalculateResponse},
+};
+
+mod test_server;
+
+use test_server::TestServer;
+
+#[test]
+fn test_evaluation_follows_precedence() {
+    assert_eq!(expression::evaluate("1 + 2 * 3"), Ok(7.0));
+    assert_eq!(expression::evaluate("(1 + 2) * 3"), Ok(9.0));
+    assert_eq!(expression::evaluate("10 - 4 - 3"), Ok(3.0));
+    assert_eq!(expression::evaluate("8 / 4 / 2"), Ok(1.0));
+    assert_eq!(expression::evaluate("-(2.5 * -2) + +1"), Ok(6.0));
+    assert_eq!(expression::evaluate(" 7 / 2 "), Ok(3.5));
+}
+
+#[test]
+fn test_invalid_expressions_are_rejected() {
+    assert_eq!(
+        expression::evaluate("1 + x"),
+        Err(ExpressionError::Unexpected {
+            position: 4,
+            found: 'x'
+        })
+    );
+    assert_eq!(
+        expression::evaluate("(1 + 2"),
+        Err(ExpressionError::UnexpectedEnd)
+    );
+    assert_eq!(
+        expression::evaluate("1 2"),
+        Err(ExpressionError::Unexpected {
+            position: 2,
+            found: '2'
+        })
+    );
+    assert_eq!(
+        expression::evaluate(""),
+        Err(ExpressionError::UnexpectedEnd)
+    );
+    assert_eq!(
+        expression::evaluate("1.2.3"),
+        Err(ExpressionError::InvalidNumber { position: 0 })
+    );
+    assert_eq!(
+        expression::evaluate("1 / (2 - 2)"),
+        Err(ExpressionError::DivisionByZero { position: 4 })
+    );
+    assert_eq!(
+        expression::evaluate(&format!("1{} * 10", "0".repeat(308))),
+        Err(ExpressionError::NotFinite)
+    );
+}
+
+#[test]
+fn test_expression_limits() {
+    let long = "1+".repeat(MAX_EXPRESSION_LEN / 2) + "1";
+    assert!(matches!(
+        expression::evaluate(&long),
+        Err(ExpressionError::TooLong { .. })
+    ));
+
+    let nested = |depth: usize| format!("{}1{}", "(".repeat(depth), ")".repeat(depth));
+    assert_eq!(expression::evaluate(&nested(MAX_DEPTH)), Ok(1.0));
+    assert!(matches!(
+        expression::evaluate(&nested(MAX_DEPTH + 1)),
+        Err(ExpressionError::TooDeep { .. })
+    ));
+}
+
+// Has the server evaluate `expression`
+fn calculate(client: &mut Client, expression: &str) -> CalculateResponse {
+    let request = client_message::Message::CalculateRequest(CalculateRequest {
+        expression: expression.to_string(),
+    });
+    match client.request(request).unwrap().message {
+        Some(server_message::Message::CalculateResponse(response)) => response,
+        other => panic!("Expected CalculateResponse, got {:?}", other),
+    }
+}
+
+#[test]
+fn test_calculate_request() {
+    let server = TestServer::start();
+    let mut client = Client::connect(("127.0.0.1", server.port() as u16))
+        .expect("Failed to connect to the server");
+
+    let response = calculate(&mut client, "2 * (3 + 4) - 1");
+    assert_eq!(response.error, "");
+    assert_eq!(response.result, 13.0);
+
+    let response = calculate(&mut client, "2 *");
+    assert_eq!(response.error, ExpressionError::UnexpectedEnd.to_string());
+
+    drop(client);
+    server.stop();
+}
@@ -0,0 +1,123 @@
+#![cfg(feature = "server")]
+
+use embedded_recruitment_task::{
+    client::{Client, ClientError},
+    extract::{self, from_fn, Headers, Peer, State, StateMap},
+    message::{
+        client_message, server_message, AddRequest, AddResponse, ClientMessage, EchoMessage,
+        ErrorCode, ErrorResponse, SubtractRequest,
+    },
+    server::Server,
+};
+use std::sync::{
+    atomic::{AtomicU64, Ordering},
+    Arc,
+};
+
+mod test_server;
+
+use test_server::TestServer;
+
+// State of the handlers below, remembering the session of the last addition
+#[derive(Default)]
+struct Ledger {
+    last_session: Arc<AtomicU64>,
+}
+
+// Adds like the built-in handler, but refuses negative numbers
+fn add(
+    request: AddRequest,
+    State(ledger): State<Ledger>,
+    peer: Peer,
+) -> extract::Result<AddResponse> {
+    if request.a < 0 || request.b < 0 {
+        return Err(ErrorResponse {
+            code: ErrorCode::HandlerFailed as i32,
+            description: "Negative numbers are not allowed".to_string(),
+        });
+    }
+    ledger.last_session.store(peer.session_id, Ordering::SeqCst);
+    Ok(AddResponse {
+        result: request.a + request.b,
+    })
+}
+
+#[test]
+fn test_state_map_holds_one_value_per_type() {
+    let mut state = StateMap::new();
+    assert!(state.get::<Ledger>().is_none());
+    state.insert(1u32);
+    state.insert(2u32);
+    state.insert(String::from("value"));
+    assert_eq!(*state.get::<u32>().unwrap(), 2);
+    assert_eq!(state.get::<String>().unwrap().as_str(), "value");
+}
+
+#[test]
+fn test_handler_functions_get_their_parameters() {
+    let ledger = Ledger::default();
+    let last_session = Arc::clone(&ledger.last_session);
+    let server = Server::builder()
+        .state(ledger)
+        .handler("add", from_fn(add))
+        .handler(
+            "echo",
+            from_fn(|mut echo: EchoMessage, Headers(headers): Headers| {
+                echo.content = headers.get("greeting").cloned().unwrap_or_default();
+                echo
+            }),
+        )
+        .bind("127.0.0.1:0")
+        .expect("Failed to start server");
+    let server = TestServer::run(server);
+    let mut client = Client::connect(("127.0.0.1", server.port() as u16))
+        .expect("Failed to connect to the server");
+
+    assert_eq!(client.add(1, 2).unwrap(), 3);
+    let session_id = client.session_info().unwrap().connection_id;
+    assert_eq!(last_session.load(Ordering::SeqCst), session_id);
+    match client.add(-1, 2) {
+        Err(ClientError::Rejected(error)) => assert_eq!(error.code(), ErrorCode::HandlerFailed),
+        other => panic!("Expected the addition to be rejected, got {:?}", other),
+    }
+
+    let echo = ClientMessage {
+        message: Some(client_message::Message::EchoMessage(EchoMessage::default())),
+        headers: [("greeting".to_string(), "Hello".to_string())].into(),
+    };
+    client.send(echo).unwrap();
+    match client.receive().unwrap().message {
+        Some(server_message::Message::EchoMessage(echo)) => {
+            assert_eq!(echo.content, "Hello")
+        }
+        other => panic!("Expected EchoMessage, got {:?}", other),
+    }
+
+    drop(client);
+    server.stop();
+}
+
+#[test]
+fn test_missing_state_rejects_the_request() {
+    let server = Server::builder()
+        .handler(
+            "subtract",
+            from_fn(|_: SubtractRequest, _: State<Ledger>| AddResponse::default()),
+        )
+        .bind("127.0.0.1:0")
+        .expect("Failed to start server");
+    let server = TestServer::run(server);
+    let mut client = Client::connect(("127.0.0.1", server.port() as u16))
+        .expect("Failed to connect to the server");
+
+    match client.subtract(2, 1) {
+        Err(ClientError::Rejected(error)) => {
+            assert_eq!(error.code(), ErrorCode::HandlerFailed);
+            assert!(error.description.contains("Ledger"));
+        }
+        other => panic!("Expected the request to be rejected, got {:?}", other),
+    }
+
+    drop(client);
+    server.stop();
+}
@@ -0,0 +1,62 @@
+use embedded_recruitment_task::framing::{
+    self, FrameDecoder, FrameError, DEFAULT_MAX_FRAME_SIZE, HEADER_LEN,
+};
+use proptest::prelude::*;
+use std::io::{self, Cursor};
+
+#[test]
+fn test_frame_layout() {
+    let frame = framing::encode_frame(b"hello");
+    assert_eq!(frame.len(), HEADER_LEN + 5);
+    assert_eq!(
+        &frame[..HEADER_LEN],
+        &[0, 0, 0, 5],
+        "Length is not big-endian"
+    );
+    assert_eq!(&frame[HEADER_LEN..], b"hello");
+
+    let mut reader = Cursor::new(frame);
+    let payload = framing::read_frame(&mut reader, DEFAULT_MAX_FRAME_SIZE).unwrap();
+    assert_eq!(payload, b"hello");
+
+    // Nothing left to read
+    let error = framing::read_frame(&mut reader, DEFAULT_MAX_FRAME_SIZE).unwrap_err();
+    assert_eq!(error.kind(), io::ErrorKind::UnexpectedEof);
+}
+
+#[test]
+fn test_oversized_frames_are_rejected() {
+    let frame = framing::encode_frame(&[0; 100]);
+
+    let mut decoder = FrameDecoder::new(99);
+    decoder.extend(&frame[..HEADER_LEN]);
+    assert_eq!(
+        decoder.next_frame(),
+        Err(FrameError::TooLarge { len: 100, max: 99 }),
+        "The limit has to apply before the payload arrived"
+    );
+
+    let error = framing::read_frame(&mut Cursor::new(frame), 99).unwrap_err();
+    assert_eq!(error.kind(), io::ErrorKind::InvalidData);
+}
+
+proptest! {
+    #[test]
+    fn decoder_handles_any_fragmentation(
+        payloads in proptest::collection::vec(proptest::collection::vec(any::<u8>(), 0..300), 0..8),
+        chunk in 1usize..64,
+    ) {
+        // Coalesce all frames into one stream, then deliver it in arbitrary pieces
+        let stream: Vec<u8> = payloads.iter().flat_map(|payload| framing::encode_frame(payload)).collect();
+        let mut decoder = FrameDecoder::default();
+        let mut decoded = Vec::new();
+        for piece in stream.chunks(chunk) {
+            decoder.extend(piece);
+            while let Some(frame) = decoder.next_frame().unwrap() {
+                decoded.push(frame);
+            }
+        }
+        prop_assert_eq!(decoded, payloads);
+        prop_assert_eq!(decoder.buffered(), 0);
+    }
+}
@@ -0,0 +1,46 @@
+#![cfg(feature = "server")]
+
+use embedded_recruitment_task::{
+    client::Client,
+    server::{Server, ServerError},
+};
+use std::time::Duration;
+
+#[test]
+fn test_started_server_runs_until_stopped() {
+    let handle = Server::new("127.0.0.1:0")
+        .unwrap()
+        .start()
+        .expect("Failed to start server");
+    assert!(handle.server().is_running());
+
+    let mut client = Client::connect(handle.local_addr()).expect("Failed to connect");
+    assert_eq!(client.add(1, 2).unwrap(), 3);
+    drop(client);
+
+    handle.stop();
+    handle.join().expect("Server failed");
+}
+
+#[test]
+fn test_dropping_the_handle_stops_the_server() {
+    let handle = Server::new("127.0.0.1:0").unwrap().start().unwrap();
+    let addr = handle.local_addr();
+    drop(handle);
+
+    // The listener is closed once the server is gone
+    assert!(Client::connect_timeout(addr, Duration::from_secs(1)).is_err());
+}
+
+#[test]
+fn test_start_reports_why_the_server_did_not_run() {
+    let server = Server::builder()
+        .user("no-such-user-for-this-test")
+        .bind("127.0.0.1:0")
+        .expect("Failed to bind");
+    match server.start() {
+        Err(ServerError::Io(_)) => {}
+        Err(e) => panic!("Expected a setup error, got {}", e),
+        Ok(_) => panic!("Server started without dropping privileges"),
+    }
+}
@@ -0,0 +1,150 @@
+#![cfg(feature = "server")]
+
+use embedded_recruitment_task::{
+    client::Client,
+    handler::{AddHandler, Handler, RequestContext, Router},
+    message::{client_message, server_message, AddResponse, DotProductRequest, DotProductResponse},
+    server::Server,
+};
+use std::{
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc,
+    },
+    time::Duration,
+};
+
+mod test_server;
+
+use test_server::TestServer;
+
+#[test]
+fn test_router_registers_handlers() {
+    let mut router = Router::new();
+    assert!(router.get("add").is_none());
+
+    router.register("add", AddHandler);
+    router.register(
+        "echo",
+        |_: client_message::Message, _: &RequestContext<'_>| None,
+    );
+    assert_eq!(router.message_types(), ["add", "echo"]);
+
+    // Merged handlers replace the ones already registered
+    let mut other = Router::new();
+    other.register(
+        "add",
+        |_: client_message::Message, _: &RequestContext<'_>| None,
+    );
+    router.merge(other);
+    assert_eq!(router.message_types(), ["add", "echo"]);
+}
+
+#[test]
+fn test_registered_handler_replaces_builtin() {
+    // Answer additions with the product instead
+    let server = Server::builder()
+        .handler(
+            "add",
+            |request: client_message::Message, _: &RequestContext<'_>| match request {
+                client_message::Message::AddRequest(add) => {
+                    Some(server_message::Message::AddResponse(AddResponse {
+                        result: add.a * add.b,
+                    }))
+                }
+                _ => None,
+            },
+        )
+        .bind("127.0.0.1:0")
+        .expect("Failed to start server");
+    let server = TestServer::run(server);
+    let mut client = Client::connect(("127.0.0.1", server.port() as u16))
+        .expect("Failed to connect to the server");
+
+    assert_eq!(client.add(3, 4).unwrap(), 12);
+
+    // The other built-in handlers are untouched
+    assert_eq!(client.send_echo("Hello").unwrap(), "Hello");
+
+    drop(client);
+    server.stop();
+}
+
+#[test]
+fn test_handler_sees_request_context() {
+    let calls = Arc::new(AtomicUsize::new(0));
+    let handler = {
+        let calls = Arc::clone(&calls);
+        move |request: client_message::Message, context: &RequestContext<'_>| {
+            calls.fetch_add(1, Ordering::SeqCst);
+            let client_message::Message::DotProductRequest(request) = request else {
+                return None;
+            };
+            let error = if context.message_type == "dot_product" && context.session.id() > 0 {
+                String::new()
+            } else {
+                "Unexpected context".to_string()
+            };
+            Some(server_message::Message::DotProductResponse(
+                DotProductResponse {
+                    result: request.a.len() as f32,
+                    error,
+                },
+            ))
+        }
+    };
+    let server = Server::builder()
+        .handler("dot_product", handler)
+        .bind("127.0.0.1:0")
+        .expect("Failed to start server");
+    let server = TestServer::run(server);
+    let mut client = Client::connect(("127.0.0.1", server.port() as u16))
+        .expect("Failed to connect to the server");
+
+    let request = client_message::Message::DotProductRequest(DotProductRequest {
+        a: vec![1.0, 2.0],
+        b: vec![3.0, 4.0],
+    });
+    match client.request(request).unwrap().message {
+        Some(server_message::Message::DotProductResponse(response)) => {
+            assert_eq!(response.error, "");
+            assert_eq!(response.result, 2.0);
+        }
+        other => panic!("Expected DotProductResponse, got {:?}", other),
+    }
+    assert_eq!(calls.load(Ordering::SeqCst), 1);
+
+    drop(client);
+    server.stop();
+}
+
+#[test]
+fn test_registered_handler_responses_are_cached() {
+    let calls = Arc::new(AtomicUsize::new(0));
+    let handler = {
+        let calls = Arc::clone(&calls);
+        move |request: client_message::Message, context: &RequestContext<'_>| {
+            calls.fetch_add(1, Ordering::SeqCst);
+            AddHandler.handle(request, context)
+        }
+    };
+    let server = Server::builder()
+        .cacheable_handler("add", handler)
+        .cache_responses("add", Duration::from_secs(60))
+        .bind("127.0.0.1:0")
+        .expect("Failed to start server");
+    let server = TestServer::run(server);
+    let mut client = Client::connect(("127.0.0.1", server.port() as u16))
+        .expect("Failed to connect to the server");
+
+    assert_eq!(client.add(1, 2).unwrap(), 3);
+    assert_eq!(client.add(1, 2).unwrap(), 3);
+    assert_eq!(
+        calls.load(Ordering::SeqCst),
+        1,
+        "Cached request was handled again"
+    );
+
+    drop(client);
+    server.stop();
+}
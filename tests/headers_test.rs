@@ -0,0 +1,138 @@
+#![cfg(feature = "server")]
+
+use embedded_recruitment_task::{
+    admin::ADMIN_TOKEN_HEADER,
+    client::PUSHED_HEADER,
+    message::{client_message, server_message, AddRequest, ErrorCode},
+    server::Server,
+};
+use std::collections::HashMap;
+
+mod client;
+mod test_server;
+
+use test_server::TestServer;
+
+#[test]
+fn test_headers_are_propagated() {
+    // Set up the server in a separate thread
+    let server = TestServer::start();
+
+    // Create and connect the client
+    let mut client = client::Client::new(&server.ip(), server.port(), 1000);
+    assert!(client.connect().is_ok(), "Failed to connect to the server");
+
+    // Send a request carrying trace context and a tenant
+    let headers = HashMap::from([
+        ("trace-id".to_string(), "4bf92f3577b34da6".to_string()),
+        ("tenant".to_string(), "acme".to_string()),
+    ]);
+    let message = client_message::Message::AddRequest(AddRequest { a: 1, b: 2 });
+    assert!(
+        client.send_with_headers(message, headers.clone()).is_ok(),
+        "Failed to send message"
+    );
+
+    // The response carries the same headers
+    let response = client.receive();
+    assert!(
+        response.is_ok(),
+        "Failed to receive response for AddRequest"
+    );
+    let response = response.unwrap();
+    assert_eq!(response.headers, headers, "Headers were not propagated");
+    assert!(matches!(
+        response.message,
+        Some(server_message::Message::AddResponse(_))
+    ));
+
+    // Disconnect the client
+    assert!(
+        client.disconnect().is_ok(),
+        "Failed to disconnect from the server"
+    );
+
+    // Stop the server and wait for thread to finish
+    server.stop();
+}
+
+#[test]
+fn test_server_headers_are_not_reflected() {
+    let server = TestServer::start();
+    let mut client = client::Client::new(&server.ip(), server.port(), 1000);
+    assert!(client.connect().is_ok(), "Failed to connect to the server");
+
+    // A response carrying the pushed header would be mistaken for a pushed message
+    let headers = HashMap::from([
+        ("trace-id".to_string(), "4bf92f3577b34da6".to_string()),
+        (PUSHED_HEADER.to_string(), "true".to_string()),
+        (ADMIN_TOKEN_HEADER.to_string(), "secret".to_string()),
+    ]);
+    let message = client_message::Message::AddRequest(AddRequest { a: 1, b: 2 });
+    assert!(
+        client.send_with_headers(message, headers).is_ok(),
+        "Failed to send message"
+    );
+    let response = client.receive().expect("Failed to receive response");
+    assert_eq!(
+        response.headers,
+        HashMap::from([("trace-id".to_string(), "4bf92f3577b34da6".to_string())])
+    );
+
+    assert!(
+        client.disconnect().is_ok(),
+        "Failed to disconnect from the server"
+    );
+    server.stop();
+}
+
+#[test]
+fn test_oversized_headers_are_rejected() {
+    let server = Server::builder()
+        .header_limits(2, 16)
+        .bind("127.0.0.1:0")
+        .expect("Failed to start server");
+    let server = TestServer::run(server);
+    let mut client = client::Client::new(&server.ip(), server.port(), 1000);
+    assert!(client.connect().is_ok(), "Failed to connect to the server");
+    let mut add = |headers: &[(&str, &str)]| {
+        let headers = headers
+            .iter()
+            .map(|(name, value)| (name.to_string(), value.to_string()))
+            .collect();
+        let message = client_message::Message::AddRequest(AddRequest { a: 1, b: 2 });
+        assert!(
+            client.send_with_headers(message, headers).is_ok(),
+            "Failed to send message"
+        );
+        client
+            .receive()
+            .expect("Failed to receive response")
+            .message
+    };
+
+    // Too many headers, then too many bytes of them
+    for headers in [
+        &[("a", "1"), ("b", "2"), ("c", "3")][..],
+        &[("tenant", "a-rather-long-name")][..],
+    ] {
+        match add(headers) {
+            Some(server_message::Message::ErrorResponse(response)) => {
+                assert_eq!(response.code(), ErrorCode::HeadersTooLarge)
+            }
+            message => panic!("Expected ErrorResponse, got {:?}", message),
+        }
+    }
+
+    // The connection is still served, requests within the limits are handled
+    assert!(matches!(
+        add(&[("tenant", "acme"), ("trace", "1")]),
+        Some(server_message::Message::AddResponse(_))
+    ));
+
+    assert!(
+        client.disconnect().is_ok(),
+        "Failed to disconnect from the server"
+    );
+    server.stop();
+}
@@ -0,0 +1,176 @@
+#![cfg(feature = "server")]
+
+use embedded_recruitment_task::{
+    client::Client,
+    clock::MockClock,
+    events::{CloseReason, ServerEvent},
+    framing::{self, DEFAULT_MAX_FRAME_SIZE},
+    message::{
+        client_message, server_message, ClientMessage, PongResponse, PublishRequest, ServerMessage,
+        SubscribeRequest,
+    },
+    server::Server,
+};
+use prost::Message;
+use std::{
+    io::Read,
+    net::TcpStream,
+    sync::mpsc::Receiver,
+    thread,
+    time::{Duration, Instant},
+};
+
+mod test_server;
+
+use test_server::TestServer;
+
+// Interval after which the servers under test ping a silent client
+const HEARTBEAT: Duration = Duration::from_millis(300);
+
+// Waits for the next message on a raw connection
+fn receive(stream: &mut TcpStream) -> ServerMessage {
+    let payload = framing::read_frame(stream, DEFAULT_MAX_FRAME_SIZE).expect("Failed to read");
+    ServerMessage::decode(payload.as_slice()).expect("Failed to decode ServerMessage")
+}
+
+// Starts a server pinging clients silent for HEARTBEAT
+fn start() -> (TestServer, Receiver<ServerEvent>) {
+    let server = Server::builder()
+        .heartbeat(HEARTBEAT)
+        .bind("127.0.0.1:0")
+        .expect("Failed to start server");
+    let events = server.subscribe_events();
+    (TestServer::run(server), events)
+}
+
+#[test]
+fn test_unresponsive_client_is_disconnected() {
+    let (server, events) = start();
+    let started = Instant::now();
+    let mut stream = TcpStream::connect(("127.0.0.1", server.port() as u16))
+        .expect("Failed to connect to the server");
+    stream
+        .set_read_timeout(Some(Duration::from_secs(5)))
+        .unwrap();
+
+    // The silent client is pinged, then hung up on when it does not answer
+    match receive(&mut stream).message {
+        Some(server_message::Message::PingRequest(_)) => {}
+        other => panic!("Expected PingRequest, got {:?}", other),
+    }
+    assert!(started.elapsed() >= HEARTBEAT);
+    let mut rest = Vec::new();
+    stream
+        .read_to_end(&mut rest)
+        .expect("Connection was not closed");
+    assert!(started.elapsed() >= HEARTBEAT * 2);
+
+    let reason = events
+        .iter()
+        .find_map(|event| match event {
+            ServerEvent::Disconnected { reason, .. } => Some(reason),
+            _ => None,
+        })
+        .expect("No connection was closed");
+    assert_eq!(reason, CloseReason::PeerUnresponsive);
+
+    server.stop();
+}
+
+#[test]
+fn test_answering_pings_keeps_the_connection() {
+    let (server, _events) = start();
+    let addr = ("127.0.0.1", server.port() as u16);
+    let mut stream = TcpStream::connect(addr).expect("Failed to connect to the server");
+    stream
+        .set_read_timeout(Some(Duration::from_secs(5)))
+        .unwrap();
+
+    for _ in 0..3 {
+        let nonce = match receive(&mut stream).message {
+            Some(server_message::Message::PingRequest(ping)) => ping.nonce,
+            other => panic!("Expected PingRequest, got {:?}", other),
+        };
+        let pong = ClientMessage {
+            message: Some(client_message::Message::PongResponse(PongResponse {
+                nonce,
+            })),
+            ..ClientMessage::default()
+        };
+        framing::write_frame(&mut stream, &pong.encode_to_vec()).expect("Failed to answer");
+    }
+    assert_eq!(server.server().active_sessions().len(), 1);
+
+    drop(stream);
+    server.stop();
+}
+
+#[test]
+fn test_client_answers_pings_while_waiting() {
+    let (server, _events) = start();
+    let addr = ("127.0.0.1", server.port() as u16);
+    let mut subscriber = Client::connect(addr).expect("Failed to connect to the server");
+    let request = client_message::Message::SubscribeRequest(SubscribeRequest {
+        topic: "news".to_string(),
+        from_offset: None,
+    });
+    subscriber.request(request).unwrap();
+
+    // Waiting for a publication, the subscriber answers the pings in between
+    let publisher = thread::spawn(move || {
+        thread::sleep(HEARTBEAT * 4);
+        let mut publisher = Client::connect(addr).expect("Failed to connect to the server");
+        let request = client_message::Message::PublishRequest(PublishRequest {
+            topic: "news".to_string(),
+            payload: b"Hello".to_vec(),
+            ..PublishRequest::default()
+        });
+        publisher.request(request).unwrap();
+    });
+    match subscriber.receive().unwrap().message {
+        Some(server_message::Message::Publication(publication)) => {
+            assert_eq!(publication.payload, b"Hello");
+        }
+        other => panic!("Expected Publication, got {:?}", other),
+    }
+    publisher.join().unwrap();
+
+    // Clients can ping the server too
+    assert!(subscriber.ping().unwrap() < Duration::from_secs(5));
+
+    drop(subscriber);
+    server.stop();
+}
+
+#[test]
+fn test_heartbeats_follow_the_server_clock() {
+    let clock = MockClock::new();
+    let server = Server::builder()
+        .heartbeat(Duration::from_secs(60 * 60))
+        .clock(clock.clone())
+        .bind("127.0.0.1:0")
+        .expect("Failed to start server");
+    let server = TestServer::run(server);
+    let mut stream = TcpStream::connect(("127.0.0.1", server.port() as u16))
+        .expect("Failed to connect to the server");
+    stream
+        .set_read_timeout(Some(Duration::from_secs(5)))
+        .unwrap();
+    let deadline = Instant::now() + Duration::from_secs(5);
+    while server.server().clients().is_empty() {
+        assert!(Instant::now() < deadline, "Client was never listed");
+        thread::sleep(Duration::from_millis(10));
+    }
+
+    // An hour of silence passes on the server clock, without waiting for it
+    let started = Instant::now();
+    clock.advance(Duration::from_secs(60 * 60));
+    match receive(&mut stream).message {
+        Some(server_message::Message::PingRequest(_)) => {}
+        other => panic!("Expected PingRequest, got {:?}", other),
+    }
+    assert!(started.elapsed() < Duration::from_secs(5));
+
+    drop(stream);
+    server.stop();
+}
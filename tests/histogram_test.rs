@@ -0,0 +1,82 @@
+#![cfg(feature = "metrics")]
+
+use embedded_recruitment_task::histogram::{
+    bucket_index, bucket_lower_bound, ExponentialHistogram, MAX_BUCKETS,
+};
+use proptest::prelude::*;
+
+#[test]
+fn test_bucket_index_on_powers_of_two() {
+    // With scale 0 bucket i covers (2^i, 2^(i+1)]
+    assert_eq!(bucket_index(1, 0), -1);
+    assert_eq!(bucket_index(2, 0), 0);
+    assert_eq!(bucket_index(3, 0), 1);
+    assert_eq!(bucket_index(4, 0), 1);
+    assert_eq!(bucket_index(5, 0), 2);
+
+    // Finer and coarser scales split or merge those buckets
+    assert_eq!(bucket_index(4, 1), 3);
+    assert_eq!(bucket_index(5, 1), 4);
+    assert_eq!(bucket_index(4, -1), 0);
+    assert_eq!(bucket_index(5, -1), 1);
+}
+
+#[test]
+fn test_histogram_counts() {
+    let mut histogram = ExponentialHistogram::new(0);
+    for value in [0, 1, 3, 4, 100] {
+        histogram.record(value);
+    }
+
+    let message = histogram.to_message();
+    assert_eq!(message.scale, 0);
+    assert_eq!(message.count, 5);
+    assert_eq!(message.sum, 108);
+    assert_eq!(message.zero_count, 1);
+    assert_eq!(message.min, 0);
+    assert_eq!(message.max, 100);
+
+    // 1 is in bucket -1, 3 and 4 in bucket 1 and 100 in bucket 6
+    assert_eq!(message.offset, -1);
+    assert_eq!(message.bucket_counts, [1, 0, 2, 0, 0, 0, 0, 1]);
+}
+
+#[test]
+fn test_histogram_downscales_to_bound_buckets() {
+    let mut histogram = ExponentialHistogram::new(20);
+    for value in [1, 10, 1_000, 1_000_000, u64::MAX] {
+        histogram.record(value);
+    }
+
+    let message = histogram.to_message();
+    assert!(message.scale < 20, "Histogram was not downscaled");
+    assert!(message.bucket_counts.len() <= MAX_BUCKETS);
+    assert_eq!(message.bucket_counts.iter().sum::<u64>(), 5);
+}
+
+#[test]
+fn test_histogram_quantiles() {
+    let mut histogram = ExponentialHistogram::new(0);
+    assert_eq!(histogram.quantile(0.5), None);
+
+    for value in [0, 3, 3, 100] {
+        histogram.record(value);
+    }
+
+    // Estimates are the upper bucket bounds, capped by the recorded extremes
+    assert_eq!(histogram.quantile(0.0), Some(0.0));
+    assert_eq!(histogram.quantile(0.25), Some(0.0));
+    assert_eq!(histogram.quantile(0.5), Some(4.0));
+    assert_eq!(histogram.quantile(1.0), Some(100.0));
+}
+
+proptest! {
+    #[test]
+    fn prop_value_falls_inside_its_bucket(value in 1u64..1 << 53, scale in -10i32..=10) {
+        let index = bucket_index(value, scale);
+        let value = value as f64;
+        let tolerance = value * 1e-9;
+        prop_assert!(bucket_lower_bound(index, scale) < value + tolerance);
+        prop_assert!(value <= bucket_lower_bound(index + 1, scale) + tolerance);
+    }
+}
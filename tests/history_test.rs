@@ -0,0 +1,153 @@
+#![cfg(feature = "server")]
+
+use embedded_recruitment_task::{
+    message::{client_message, server_message, EchoMessage, HistoryRequest},
+    server::ServerConfig,
+};
+
+mod client;
+mod test_server;
+
+use test_server::TestServer;
+
+#[test]
+fn test_history_request() {
+    // Set up a server that keeps the last two echo messages
+    let config = ServerConfig {
+        history_capacity: 2,
+        ..ServerConfig::default()
+    };
+    let server = TestServer::with_config(config);
+
+    // Create and connect the client
+    let mut client = client::Client::new(&server.ip(), server.port(), 1000);
+    assert!(client.connect().is_ok(), "Failed to connect to the server");
+
+    // Send three echo messages, the first one should be evicted
+    let messages = ["first", "second", "third"];
+    for content in messages {
+        let echo_message = EchoMessage {
+            content: content.to_string(),
+        };
+        assert!(
+            client
+                .send(client_message::Message::EchoMessage(echo_message))
+                .is_ok(),
+            "Failed to send message"
+        );
+        assert!(client.receive().is_ok(), "Failed to receive echo");
+    }
+
+    // Query the whole history, then only the most recent entry
+    for (limit, expected) in [(0, &messages[1..]), (1, &messages[2..])] {
+        let message = client_message::Message::HistoryRequest(HistoryRequest {
+            limit,
+            ..HistoryRequest::default()
+        });
+        assert!(client.send(message).is_ok(), "Failed to send message");
+
+        let response = client.receive();
+        assert!(
+            response.is_ok(),
+            "Failed to receive response for HistoryRequest"
+        );
+
+        match response.unwrap().message {
+            Some(server_message::Message::HistoryResponse(history)) => {
+                assert_eq!(history.total, 2, "Unexpected number of retained entries");
+                let contents: Vec<_> = history.entries.iter().map(|e| e.content.as_str()).collect();
+                assert_eq!(contents, expected, "History entries do not match");
+            }
+            _ => panic!("Expected HistoryResponse, but received a different message"),
+        }
+    }
+
+    // Disconnect the client
+    assert!(
+        client.disconnect().is_ok(),
+        "Failed to disconnect from the server"
+    );
+
+    // Stop the server and wait for thread to finish
+    server.stop();
+}
+
+#[test]
+fn test_history_pagination() {
+    // Set up a server that keeps the last five echo messages
+    let config = ServerConfig {
+        history_capacity: 5,
+        ..ServerConfig::default()
+    };
+    let server = TestServer::with_config(config);
+
+    // Create and connect the client
+    let mut client = client::Client::new(&server.ip(), server.port(), 1000);
+    assert!(client.connect().is_ok(), "Failed to connect to the server");
+
+    let messages = ["1", "2", "3", "4", "5"];
+    for content in messages {
+        let echo_message = EchoMessage {
+            content: content.to_string(),
+        };
+        assert!(
+            client
+                .send(client_message::Message::EchoMessage(echo_message))
+                .is_ok(),
+            "Failed to send message"
+        );
+        assert!(client.receive().is_ok(), "Failed to receive echo");
+    }
+
+    // Walk the history two entries at a time, newest page first
+    let mut continuation_token = String::new();
+    let mut pages = Vec::new();
+    loop {
+        let message = client_message::Message::HistoryRequest(HistoryRequest {
+            limit: 2,
+            continuation_token,
+        });
+        assert!(client.send(message).is_ok(), "Failed to send message");
+
+        match client.receive().map(|response| response.message) {
+            Ok(Some(server_message::Message::HistoryResponse(history))) => {
+                assert_eq!(history.total, 5, "Unexpected number of retained entries");
+                let contents: Vec<_> = history.entries.into_iter().map(|e| e.content).collect();
+                pages.push(contents);
+                continuation_token = history.continuation_token;
+            }
+            _ => panic!("Expected HistoryResponse, but received a different message"),
+        }
+
+        if continuation_token.is_empty() {
+            break;
+        }
+    }
+    assert_eq!(pages, [vec!["4", "5"], vec!["2", "3"], vec!["1"]]);
+
+    // An unknown token yields an empty last page
+    let message = client_message::Message::HistoryRequest(HistoryRequest {
+        limit: 2,
+        continuation_token: "bogus".to_string(),
+    });
+    assert!(client.send(message).is_ok(), "Failed to send message");
+    match client.receive().map(|response| response.message) {
+        Ok(Some(server_message::Message::HistoryResponse(history))) => {
+            assert!(history.entries.is_empty(), "Expected an empty page");
+            assert!(
+                history.continuation_token.is_empty(),
+                "Expected the last page"
+            );
+        }
+        _ => panic!("Expected HistoryResponse, but received a different message"),
+    }
+
+    // Disconnect the client
+    assert!(
+        client.disconnect().is_ok(),
+        "Failed to disconnect from the server"
+    );
+
+    // Stop the server and wait for thread to finish
+    server.stop();
+}
@@ -0,0 +1,167 @@
+#[cfg(feature = "server")]
+use embedded_recruitment_task::client::{Client, ClientError};
+use embedded_recruitment_task::impairment::{ImpairedStream, Impairment};
+use std::{
+    io::{ErrorKind, Read, Write},
+    net::{TcpListener, TcpStream},
+    time::{Duration, Instant},
+};
+
+#[cfg(feature = "server")]
+mod test_server;
+
+#[cfg(feature = "server")]
+use test_server::TestServer;
+
+// Returns both ends of a loopback connection
+fn connection() -> (TcpStream, TcpStream) {
+    let listener = TcpListener::bind("127.0.0.1:0").expect("Failed to bind listener");
+    let client = TcpStream::connect(listener.local_addr().unwrap()).expect("Failed to connect");
+    let (server, _) = listener.accept().expect("Failed to accept connection");
+    (client, server)
+}
+
+#[test]
+fn test_partial_writes() {
+    let (client, mut server) = connection();
+    let impairment = Impairment {
+        max_write_size: Some(3),
+        ..Impairment::default()
+    };
+    let mut client = ImpairedStream::new(client, impairment);
+
+    // Single writes are cut short, but write_all still delivers everything
+    assert_eq!(client.write(b"Hello, World!").unwrap(), 3);
+    client.write_all(b"lo, World!").unwrap();
+
+    let mut received = [0; 13];
+    server.read_exact(&mut received).unwrap();
+    assert_eq!(&received, b"Hello, World!");
+}
+
+#[test]
+fn test_latency_and_bandwidth() {
+    let (client, mut server) = connection();
+    let impairment = Impairment {
+        latency: Duration::from_millis(20),
+        bytes_per_second: Some(1000),
+        ..Impairment::default()
+    };
+    let mut client = ImpairedStream::new(client, impairment);
+
+    // 100 bytes at 1000 bytes per second take at least 100ms, plus the latency
+    let started = Instant::now();
+    client.write_all(&[0; 100]).unwrap();
+    assert!(started.elapsed() >= Duration::from_millis(120));
+
+    let mut received = [1; 100];
+    server.read_exact(&mut received).unwrap();
+    assert_eq!(received, [0; 100]);
+}
+
+#[test]
+fn test_disconnect_mid_message() {
+    let (client, mut server) = connection();
+    let impairment = Impairment {
+        disconnect_after: Some(5),
+        ..Impairment::default()
+    };
+    let mut client = ImpairedStream::new(client, impairment);
+
+    let error = client.write_all(b"Hello, World!").unwrap_err();
+    assert_eq!(error.kind(), ErrorKind::NotConnected);
+    assert!(client.is_disconnected());
+
+    // The peer sees the first bytes, then the connection going away
+    let mut received = Vec::new();
+    server.read_to_end(&mut received).unwrap();
+    assert_eq!(received, b"Hello");
+}
+
+#[test]
+fn test_disconnect_mid_read() {
+    let (client, mut server) = connection();
+    let impairment = Impairment {
+        disconnect_after_read: Some(5),
+        ..Impairment::default()
+    };
+    let mut client = ImpairedStream::new(client, impairment);
+
+    server.write_all(b"Hello, World!").unwrap();
+    let mut received = [0; 13];
+    let error = client.read_exact(&mut received).unwrap_err();
+    assert_eq!(error.kind(), ErrorKind::NotConnected);
+    assert_eq!(&received[..5], b"Hello");
+    assert!(client.is_disconnected());
+}
+
+// Connects to `server` over a transport degraded by `impairment`
+#[cfg(feature = "server")]
+fn impaired_client(
+    server: &TestServer,
+    impairment: Impairment,
+) -> Client<ImpairedStream<TcpStream>> {
+    let stream = TcpStream::connect(("127.0.0.1", server.port() as u16))
+        .expect("Failed to connect to the server");
+    stream
+        .set_read_timeout(Some(Duration::from_secs(5)))
+        .unwrap();
+    Client::over(ImpairedStream::new(stream, impairment))
+}
+
+#[cfg(feature = "server")]
+#[test]
+fn test_server_reassembles_trickling_requests() {
+    let server = TestServer::start();
+    let impairment = Impairment {
+        latency: Duration::from_millis(1),
+        max_write_size: Some(1),
+        ..Impairment::default()
+    };
+    let mut client = impaired_client(&server, impairment);
+
+    // Every request arrives a byte at a time, and is still answered
+    assert_eq!(client.add(1, 2).unwrap(), 3);
+    assert_eq!(client.send_echo("Hello, World!").unwrap(), "Hello, World!");
+
+    drop(client);
+    server.stop();
+}
+
+#[cfg(feature = "server")]
+#[test]
+fn test_connections_cut_mid_frame() {
+    let server = TestServer::start();
+
+    // The request is cut off after its frame header, the server drops the connection
+    let impairment = Impairment {
+        disconnect_after: Some(6),
+        ..Impairment::default()
+    };
+    let mut client = impaired_client(&server, impairment);
+    assert!(matches!(
+        client.add(1, 2),
+        Err(ClientError::ConnectionLost(_))
+    ));
+    assert!(client.transport().is_disconnected());
+
+    // The response is cut off in the middle, the client notices
+    let impairment = Impairment {
+        disconnect_after_read: Some(6),
+        ..Impairment::default()
+    };
+    let mut client = impaired_client(&server, impairment);
+    assert!(matches!(
+        client.add(1, 2),
+        Err(ClientError::ConnectionLost(_))
+    ));
+    assert!(client.transport().is_disconnected());
+
+    // Neither costs anyone else their connection
+    let mut client = Client::connect(("127.0.0.1", server.port() as u16))
+        .expect("Failed to connect to the server");
+    assert_eq!(client.add(1, 2).unwrap(), 3);
+
+    drop(client);
+    server.stop();
+}
@@ -0,0 +1,96 @@
+#![cfg(feature = "server")]
+
+use embedded_recruitment_task::{
+    client::Client,
+    framing::DEFAULT_MAX_FRAME_SIZE,
+    info::{self, GIT_HASH, PROTOCOL_VERSION, VERSION},
+    message::{client_message, server_message, ServerInfoRequest, ServerInfoResponse},
+};
+use std::{
+    thread,
+    time::{Duration, Instant},
+};
+
+mod test_server;
+
+use test_server::TestServer;
+
+#[test]
+fn test_banner_describes_the_build() {
+    let banner = info::banner();
+    assert!(banner.contains(VERSION));
+    assert!(banner.contains(GIT_HASH));
+    assert!(info::features().contains(&"server"));
+    assert!(!GIT_HASH.is_empty());
+}
+
+#[test]
+fn test_server_info_is_answered() {
+    let server = TestServer::start();
+    let mut client = Client::connect(("127.0.0.1", server.port() as u16))
+        .expect("Failed to connect to the server");
+
+    let request = client_message::Message::ServerInfoRequest(ServerInfoRequest {});
+    let response = match client.request(request).unwrap().message {
+        Some(server_message::Message::ServerInfoResponse(response)) => response,
+        other => panic!("Expected ServerInfoResponse, got {:?}", other),
+    };
+    assert_eq!(response.version, env!("CARGO_PKG_VERSION"));
+    assert_eq!(response.git_hash, GIT_HASH);
+    assert_eq!(response.protocol_version, PROTOCOL_VERSION);
+    assert!(response.features.iter().any(|feature| feature == "server"));
+    assert!(response.started_at_ms > 0, "Start time missing");
+    assert!(response.session_id > 0, "Connection id missing");
+    // Uptime keeps growing between the two answers
+    let info = server.server().server_info();
+    assert!(info.uptime_ms >= response.uptime_ms);
+    assert_eq!(
+        ServerInfoResponse {
+            session_id: 0,
+            uptime_ms: info.uptime_ms,
+            ..response
+        },
+        info
+    );
+
+    drop(client);
+    server.stop();
+}
+
+#[test]
+fn test_client_reports_session_info() {
+    let server = TestServer::start();
+    let mut client = Client::connect(("127.0.0.1", server.port() as u16))
+        .expect("Failed to connect to the server");
+    let mut other = Client::connect(("127.0.0.1", server.port() as u16))
+        .expect("Failed to connect to the server");
+
+    let info = client.session_info().unwrap().clone();
+    assert_eq!(info.protocol_version, PROTOCOL_VERSION);
+    assert_eq!(info.max_frame_size, DEFAULT_MAX_FRAME_SIZE);
+    assert_eq!(info.server.git_hash, GIT_HASH);
+    assert!(info.connection_id > 0);
+    assert_ne!(
+        other.session_info().unwrap().connection_id,
+        info.connection_id
+    );
+
+    // Asked once, the server is not bothered again
+    assert_eq!(client.session_info().unwrap(), &info);
+    let session = server
+        .server()
+        .active_sessions()
+        .into_iter()
+        .find(|session| session.id() == info.connection_id)
+        .expect("Connection is not known to the server");
+
+    // The request is counted once its response is on the way
+    let deadline = Instant::now() + Duration::from_secs(5);
+    while session.messages() == 0 && Instant::now() < deadline {
+        thread::sleep(Duration::from_millis(10));
+    }
+    assert_eq!(session.messages(), 1);
+
+    drop((client, other));
+    server.stop();
+}
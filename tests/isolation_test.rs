@@ -0,0 +1,50 @@
+#![cfg(feature = "server")]
+
+use embedded_recruitment_task::message::{client_message, server_message, AddRequest};
+use std::thread;
+
+mod client;
+mod test_server;
+
+use test_server::TestServer;
+
+// Number of servers running side by side in the stress test
+const SERVER_COUNT: usize = 50;
+
+#[test]
+fn test_parallel_servers_are_isolated() {
+    let servers: Vec<TestServer> = (0..SERVER_COUNT).map(|_| TestServer::start()).collect();
+
+    // Every server gets exactly one client, all of them talking at the same time
+    thread::scope(|scope| {
+        for (i, server) in servers.iter().enumerate() {
+            scope.spawn(move || {
+                let mut client = client::Client::new(&server.ip(), server.port(), 1000);
+                assert!(client.connect().is_ok(), "Failed to connect to the server");
+
+                let add_request = AddRequest { a: i as i32, b: 1 };
+                assert!(
+                    client
+                        .send(client_message::Message::AddRequest(add_request))
+                        .is_ok(),
+                    "Failed to send message"
+                );
+
+                let response = client.receive().expect("Failed to receive response");
+                match response.message {
+                    Some(server_message::Message::AddResponse(add_response)) => {
+                        assert_eq!(add_response.result, i as i32 + 1);
+                    }
+                    _ => panic!("Expected AddResponse, but received a different message"),
+                }
+
+                assert!(client.disconnect().is_ok(), "Failed to disconnect");
+            });
+        }
+    });
+
+    // No server saw anybody else's client
+    for server in &servers {
+        assert_eq!(server.server().metrics().connections_accepted(), 1);
+    }
+}
@@ -0,0 +1,69 @@
+#![cfg(all(feature = "server", target_os = "linux"))]
+
+use embedded_recruitment_task::server::{KeepalivePolicy, Server};
+use std::{
+    fs, thread,
+    time::{Duration, Instant},
+};
+
+mod client;
+mod test_server;
+
+use test_server::TestServer;
+
+// Returns the pending timer of the established connection the server holds on `port`, as
+// listed by the kernel: 0 for none, 2 for a keepalive probe
+fn server_side_timer(port: u32) -> Option<u32> {
+    let table = fs::read_to_string("/proc/net/tcp").expect("Failed to read socket table");
+    table.lines().skip(1).find_map(|line| {
+        let fields: Vec<&str> = line.split_whitespace().collect();
+        let local_port = fields[1].rsplit(':').next()?;
+        let established = fields[3] == "01";
+        if !established || u32::from_str_radix(local_port, 16).ok()? != port {
+            return None;
+        }
+        let timer = fields[5].split(':').next()?;
+        u32::from_str_radix(timer, 16).ok()
+    })
+}
+
+// Connects a client to `server` and waits until its connection shows the expected timer
+fn wait_for_timer(server: &TestServer, expected: u32) {
+    let mut client = client::Client::new(&server.ip(), server.port(), 1000);
+    assert!(client.connect().is_ok(), "Failed to connect to the server");
+
+    let deadline = Instant::now() + Duration::from_secs(5);
+    let mut timer = server_side_timer(server.port());
+    while timer != Some(expected) && Instant::now() < deadline {
+        thread::sleep(Duration::from_millis(10));
+        timer = server_side_timer(server.port());
+    }
+    assert_eq!(timer, Some(expected), "Unexpected server-side timer");
+
+    assert!(
+        client.disconnect().is_ok(),
+        "Failed to disconnect from the server"
+    );
+}
+
+#[test]
+fn test_idle_connections_are_probed() {
+    // Without a policy idle connections are left alone
+    let server = TestServer::start();
+    wait_for_timer(&server, 0);
+    server.stop();
+
+    // With one, the kernel schedules a keepalive probe for the idle connection
+    let policy = KeepalivePolicy {
+        idle: Duration::from_secs(60),
+        interval: Duration::from_secs(5),
+        retries: 2,
+    };
+    let server = Server::builder()
+        .keepalive(policy)
+        .bind("127.0.0.1:0")
+        .expect("Failed to start server");
+    let server = TestServer::run(server);
+    wait_for_timer(&server, 2);
+    server.stop();
+}
@@ -0,0 +1,177 @@
+#![cfg(feature = "server")]
+
+use embedded_recruitment_task::{
+    client::Client,
+    kv::{KvError, KvStore, MAX_KEY_LEN},
+    message::{client_message, server_message, DeleteRequest, GetRequest, GetResponse, SetRequest},
+    server::Server,
+};
+use std::{fs, io::ErrorKind, path::PathBuf, time::Duration};
+
+mod test_server;
+
+use test_server::TestServer;
+
+#[test]
+fn test_store_sets_gets_and_deletes() {
+    let store = KvStore::new(2);
+    assert_eq!(store.get("a"), Ok(None));
+    assert_eq!(store.set("a", b"1".to_vec()), Ok(None));
+    assert_eq!(store.set("a", b"2".to_vec()), Ok(Some(b"1".to_vec())));
+    assert_eq!(store.get("a"), Ok(Some(b"2".to_vec())));
+
+    // A full store still replaces values, but takes no new keys
+    assert_eq!(store.set("b", Vec::new()), Ok(None));
+    assert_eq!(
+        store.set("c", Vec::new()),
+        Err(KvError::Full { capacity: 2 })
+    );
+    assert_eq!(store.set("b", b"3".to_vec()), Ok(Some(Vec::new())));
+
+    assert_eq!(store.delete("a"), Ok(Some(b"2".to_vec())));
+    assert_eq!(store.delete("a"), Ok(None));
+    assert_eq!(store.len(), 1);
+
+    assert_eq!(store.get(""), Err(KvError::EmptyKey));
+    assert_eq!(
+        store.set(&"k".repeat(MAX_KEY_LEN + 1), Vec::new()),
+        Err(KvError::KeyTooLong {
+            length: MAX_KEY_LEN + 1
+        })
+    );
+}
+
+// Connects a new client to the server
+fn connect(server: &TestServer) -> Client {
+    Client::connect(("127.0.0.1", server.port() as u16)).expect("Failed to connect to the server")
+}
+
+// Reads `key` through the server
+fn get(client: &mut Client, key: &str) -> GetResponse {
+    let request = client_message::Message::GetRequest(GetRequest {
+        key: key.to_string(),
+    });
+    match client.request(request).unwrap().message {
+        Some(server_message::Message::GetResponse(response)) => response,
+        other => panic!("Expected GetResponse, got {:?}", other),
+    }
+}
+
+#[test]
+fn test_values_are_shared_between_clients() {
+    let server = Server::builder()
+        .kv_capacity(1)
+        .bind("127.0.0.1:0")
+        .expect("Failed to start server");
+    let server = TestServer::run(server);
+    let mut writer = connect(&server);
+    let mut reader = connect(&server);
+
+    let set = |client: &mut Client, key: &str| {
+        let request = client_message::Message::SetRequest(SetRequest {
+            key: key.to_string(),
+            value: b"value".to_vec(),
+        });
+        match client.request(request).unwrap().message {
+            Some(server_message::Message::SetResponse(response)) => response,
+            other => panic!("Expected SetResponse, got {:?}", other),
+        }
+    };
+    let response = set(&mut writer, "greeting");
+    assert_eq!(response.error, "");
+    assert!(!response.replaced);
+    assert!(set(&mut writer, "greeting").replaced);
+
+    // Written by one client, read by the other
+    let response = get(&mut reader, "greeting");
+    assert!(response.found);
+    assert_eq!(response.value, b"value");
+    assert!(!get(&mut reader, "missing").found);
+    assert!(!get(&mut reader, "").error.is_empty());
+
+    // The store is full
+    assert!(!set(&mut writer, "other").error.is_empty());
+    assert_eq!(server.server().kv_store().len(), 1);
+
+    let request = client_message::Message::DeleteRequest(DeleteRequest {
+        key: "greeting".to_string(),
+    });
+    match reader.request(request).unwrap().message {
+        Some(server_message::Message::DeleteResponse(response)) => assert!(response.deleted),
+        other => panic!("Expected DeleteResponse, got {:?}", other),
+    }
+    assert!(!get(&mut writer, "greeting").found);
+
+    drop((writer, reader));
+    server.stop();
+}
+
+// A snapshot path of its own for every test
+fn snapshot_path(name: &str) -> PathBuf {
+    let path =
+        std::env::temp_dir().join(format!("kv-test-{}-{}.snapshot", std::process::id(), name));
+    let _ = fs::remove_file(&path);
+    path
+}
+
+#[test]
+fn test_store_snapshots_round_trip() {
+    let path = snapshot_path("round-trip");
+    let store = KvStore::load(&path, 10).expect("Missing snapshot was not an empty store");
+    assert!(store.is_empty());
+
+    store.set("a", b"1".to_vec()).unwrap();
+    store.set("b", Vec::new()).unwrap();
+    let version = store.version();
+    store.delete("missing").unwrap();
+    assert_eq!(
+        store.version(),
+        version,
+        "Deleting nothing changed the store"
+    );
+    store.save(&path).unwrap();
+
+    let loaded = KvStore::load(&path, 10).unwrap();
+    assert_eq!(loaded.len(), 2);
+    assert_eq!(loaded.get("a"), Ok(Some(b"1".to_vec())));
+    assert_eq!(loaded.get("b"), Ok(Some(Vec::new())));
+
+    // A corrupt snapshot is not silently replaced by an empty store
+    fs::write(&path, [0xff; 4]).unwrap();
+    let error = KvStore::load(&path, 10).expect_err("Corrupt snapshot was loaded");
+    assert_eq!(error.kind(), ErrorKind::InvalidData);
+    fs::remove_file(&path).unwrap();
+}
+
+#[test]
+fn test_stored_values_survive_a_restart() {
+    let path = snapshot_path("restart");
+    let start = || {
+        let server = Server::builder()
+            .kv_persistence(&path, Duration::from_secs(60))
+            .bind("127.0.0.1:0")
+            .expect("Failed to start server");
+        TestServer::run(server)
+    };
+
+    let server = start();
+    let mut client = connect(&server);
+    let request = client_message::Message::SetRequest(SetRequest {
+        key: "greeting".to_string(),
+        value: b"Hello".to_vec(),
+    });
+    client.request(request).unwrap();
+    drop(client);
+    // Stopping writes the snapshot, long before the interval is up
+    server.stop();
+
+    let server = start();
+    let mut client = connect(&server);
+    let response = get(&mut client, "greeting");
+    assert!(response.found, "Value was lost in the restart");
+    assert_eq!(response.value, b"Hello");
+
+    drop(client);
+    server.stop();
+    fs::remove_file(&path).unwrap();
+}
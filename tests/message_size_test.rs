@@ -0,0 +1,78 @@
+#![cfg(feature = "server")]
+
+use embedded_recruitment_task::{
+    client::{Client, ClientError},
+    compute::MAX_ELEMENTS,
+    message::{client_message, server_message, DotProductRequest, ErrorCode},
+    server::Server,
+};
+
+mod test_server;
+
+use test_server::TestServer;
+
+// A dot product of two vectors as long as the server allows, well over 64 KiB encoded
+fn large_request() -> client_message::Message {
+    client_message::Message::DotProductRequest(DotProductRequest {
+        a: vec![1.0; MAX_ELEMENTS],
+        b: vec![2.0; MAX_ELEMENTS],
+    })
+}
+
+#[test]
+fn test_large_messages_within_the_limit_are_handled() {
+    let server = Server::builder()
+        .max_message_size(256 * 1024)
+        .bind("127.0.0.1:0")
+        .expect("Failed to start server");
+    let server = TestServer::run(server);
+    let mut client = Client::connect(("127.0.0.1", server.port() as u16))
+        .expect("Failed to connect to the server");
+
+    match client.request(large_request()).unwrap().message {
+        Some(server_message::Message::DotProductResponse(response)) => {
+            assert_eq!(response.error, "");
+            assert_eq!(response.result, 2.0 * MAX_ELEMENTS as f32);
+        }
+        other => panic!("Expected DotProductResponse, got {:?}", other),
+    }
+    assert_eq!(
+        server.server().server_info().max_frame_size,
+        256 * 1024,
+        "The limit is not advertised"
+    );
+
+    drop(client);
+    server.stop();
+}
+
+#[test]
+fn test_messages_over_the_limit_are_answered_with_an_error() {
+    let server = Server::builder()
+        .max_message_size(64)
+        .bind("127.0.0.1:0")
+        .expect("Failed to start server");
+    let server = TestServer::run(server);
+    let addr = ("127.0.0.1", server.port() as u16);
+
+    // Small messages are unaffected
+    let mut client = Client::connect(addr).expect("Failed to connect to the server");
+    assert_eq!(client.add(1, 2).unwrap(), 3);
+
+    match client.request(large_request()) {
+        Err(ClientError::Rejected(error)) => {
+            assert_eq!(error.code(), ErrorCode::MessageTooLarge);
+            assert!(error.description.contains("64"), "{}", error.description);
+        }
+        other => panic!("Expected a rejection, got {:?}", other),
+    }
+
+    // The connection is closed afterwards
+    assert!(matches!(
+        client.add(1, 2),
+        Err(ClientError::ConnectionLost(_))
+    ));
+
+    drop(client);
+    server.stop();
+}
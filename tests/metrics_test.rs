@@ -0,0 +1,135 @@
+#![cfg(feature = "server")]
+
+use embedded_recruitment_task::{
+    events::{CloseReason, ServerEvent},
+    message::{client_message, AddRequest},
+    metrics::Metrics,
+};
+use std::{thread, time::Duration};
+
+mod client;
+mod test_server;
+
+use test_server::TestServer;
+
+#[test]
+fn test_connection_establishment_metrics() {
+    // Set up the server and subscribe to its events before any client connects
+    let server = TestServer::start();
+    let events = server.server().subscribe_events();
+    let next_event = || {
+        events
+            .recv_timeout(Duration::from_secs(5))
+            .expect("Timed out waiting for a server event")
+    };
+
+    // Nothing was measured yet
+    let metrics = server.server().metrics();
+    assert_eq!(metrics.time_to_first_byte_p50(), None);
+    assert_eq!(metrics.establishment_error_rate(), 0.0);
+
+    // A client that takes its time before sending its first request, measured from the
+    // moment the server accepted it
+    let mut client = client::Client::new(&server.ip(), server.port(), 1000);
+    assert!(client.connect().is_ok(), "Failed to connect to the server");
+    assert!(matches!(next_event(), ServerEvent::Connected { .. }));
+    thread::sleep(Duration::from_millis(50));
+    let message = client_message::Message::AddRequest(AddRequest { a: 1, b: 2 });
+    assert!(client.send(message).is_ok(), "Failed to send message");
+    assert!(client.receive().is_ok(), "Failed to receive response");
+    assert!(
+        client.disconnect().is_ok(),
+        "Failed to disconnect from the server"
+    );
+    assert!(matches!(next_event(), ServerEvent::Disconnected { .. }));
+
+    // A client that hangs up without sending anything
+    let mut client = client::Client::new(&server.ip(), server.port(), 1000);
+    assert!(client.connect().is_ok(), "Failed to connect to the server");
+    assert!(
+        client.disconnect().is_ok(),
+        "Failed to disconnect from the server"
+    );
+    assert!(matches!(next_event(), ServerEvent::Connected { .. }));
+    assert!(matches!(next_event(), ServerEvent::Disconnected { .. }));
+
+    assert_eq!(metrics.time_to_first_byte().count(), 1);
+    let p50 = metrics
+        .time_to_first_byte_p50()
+        .expect("Time to first byte was not measured");
+    assert!(
+        p50 >= Duration::from_millis(50),
+        "Unexpected p50: {:?}",
+        p50
+    );
+    assert_eq!(metrics.time_to_first_byte_p99(), Some(p50));
+    assert_eq!(metrics.connections_abandoned(), 1);
+    assert_eq!(metrics.establishment_error_rate(), 0.5);
+
+    // Stop the server and wait for thread to finish
+    server.stop();
+}
+
+#[test]
+fn test_scrape_reports_cumulative_and_delta() {
+    let metrics = Metrics::default();
+    metrics.record_connect();
+    metrics.record_connect();
+    metrics.record_close(CloseReason::ClientEof);
+    metrics.record_first_byte(Duration::from_millis(1));
+
+    // The first scrape covers everything recorded so far
+    let scrape = metrics.scrape();
+    assert_eq!(scrape.cumulative.connections_accepted, 2);
+    assert_eq!(scrape.delta.connections_accepted, 2);
+    assert_eq!(scrape.delta.connections_closed(CloseReason::ClientEof), 1);
+    assert_eq!(scrape.delta.time_to_first_byte.count(), 1);
+
+    // Later scrapes only report what changed in between
+    metrics.record_connect();
+    metrics.record_close(CloseReason::ClientEof);
+    metrics.record_close(CloseReason::IdleTimeout);
+    let scrape = metrics.scrape();
+    assert_eq!(scrape.cumulative.connections_accepted, 3);
+    assert_eq!(scrape.delta.connections_accepted, 1);
+    assert_eq!(
+        scrape.cumulative.connections_closed(CloseReason::ClientEof),
+        2
+    );
+    assert_eq!(scrape.delta.connections_closed(CloseReason::ClientEof), 1);
+    assert_eq!(scrape.delta.connections_closed(CloseReason::IdleTimeout), 1);
+    assert_eq!(scrape.cumulative.time_to_first_byte.count(), 1);
+    assert_eq!(scrape.delta.time_to_first_byte.count(), 0);
+
+    // Nothing happened since
+    let scrape = metrics.scrape();
+    assert_eq!(scrape.delta.connections_accepted, 0);
+    assert_eq!(scrape.cumulative.epoch, 0);
+}
+
+#[test]
+fn test_reset_starts_a_new_epoch() {
+    let metrics = Metrics::default();
+    for _ in 0..5 {
+        metrics.record_connect();
+    }
+    metrics.record_first_byte(Duration::from_millis(1));
+    assert_eq!(metrics.scrape().delta.connections_accepted, 5);
+
+    // After a reset counters restart from zero, without negative or doubled deltas
+    metrics.reset();
+    assert_eq!(metrics.connections_accepted(), 0);
+    assert_eq!(metrics.time_to_first_byte_p50(), None);
+    metrics.record_connect();
+    let scrape = metrics.scrape();
+    assert_eq!(scrape.cumulative.epoch, 1);
+    assert_eq!(scrape.delta.epoch, 1);
+    assert_eq!(scrape.cumulative.connections_accepted, 1);
+    assert_eq!(scrape.delta.connections_accepted, 1);
+    assert_eq!(scrape.delta.time_to_first_byte.count(), 0);
+
+    // A snapshot does not disturb the deltas of the next scrape
+    metrics.record_connect();
+    assert_eq!(metrics.snapshot().connections_accepted, 2);
+    assert_eq!(metrics.scrape().delta.connections_accepted, 1);
+}
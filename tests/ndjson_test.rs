@@ -0,0 +1,135 @@
+#![cfg(feature = "server")]
+
+use embedded_recruitment_task::{
+    client::Client,
+    clock::{Clock, MockClock},
+    events::ServerEvent,
+    ndjson,
+    server::Server,
+};
+use std::{
+    io::{BufRead, BufReader},
+    net::{TcpListener, TcpStream},
+    time::{Duration, UNIX_EPOCH},
+};
+
+mod client;
+mod test_server;
+
+use test_server::TestServer;
+
+#[test]
+fn test_events_exported_as_ndjson() {
+    // Stand in for a log shipper listening on a socket
+    let collector = TcpListener::bind("127.0.0.1:0").expect("Failed to bind collector");
+    let sink = TcpStream::connect(collector.local_addr().unwrap())
+        .expect("Failed to connect to collector");
+    let (collected, _) = collector.accept().expect("Failed to accept sink");
+    let mut lines = BufReader::new(collected).lines();
+
+    let server = Server::new("127.0.0.1:0").expect("Failed to start server");
+    let exporter = server
+        .export_events(sink)
+        .expect("Failed to spawn the exporter");
+    assert_eq!(exporter.thread().name(), Some("server-events"));
+    let server = TestServer::run(server);
+
+    let mut client = client::Client::new(&server.ip(), server.port(), 1000);
+    assert!(client.connect().is_ok(), "Failed to connect to the server");
+    assert!(
+        client.disconnect().is_ok(),
+        "Failed to disconnect from the server"
+    );
+
+    let mut next_line = || {
+        lines
+            .next()
+            .expect("Exporter stopped early")
+            .expect("Failed to read exported event")
+    };
+    let connected = next_line();
+    assert!(connected.starts_with("{\"timestamp_ms\":"), "{}", connected);
+    assert!(
+        connected.contains("\"event\":\"connected\""),
+        "{}",
+        connected
+    );
+    let disconnected = next_line();
+    assert!(
+        disconnected.contains("\"event\":\"disconnected\"")
+            && disconnected.contains("\"reason\":\"client_eof\""),
+        "{}",
+        disconnected
+    );
+    assert!(disconnected.ends_with('}'), "{}", disconnected);
+
+    // Dropping the server ends the export
+    server.stop();
+    assert!(exporter.join().unwrap().is_ok(), "Export failed");
+}
+
+#[test]
+fn test_message_events_are_exported() {
+    // Far from the wall clock, so a timestamp taken while serializing would stand out
+    let clock = MockClock::new();
+    clock.advance(Duration::from_secs(3600));
+    let server = Server::builder()
+        .clock(clock.clone())
+        .bind("127.0.0.1:0")
+        .expect("Failed to start server");
+    let events = server.subscribe_all_events();
+    let lifecycle = server.subscribe_events();
+    let server = TestServer::run(server);
+
+    let mut client = Client::connect(("127.0.0.1", server.port() as u16))
+        .expect("Failed to connect to the server");
+    assert_eq!(client.add(1, 2).unwrap(), 3);
+    assert!(client.divide(1, 0).is_err(), "Division by zero succeeded");
+    let addr = client.stream().local_addr().unwrap();
+
+    let mut messages = events
+        .iter()
+        .filter_map(|event| match event {
+            ServerEvent::MessageHandled { message, timestamp } => Some((message, timestamp)),
+            _ => None,
+        })
+        .take(2);
+    let (add, timestamp) = messages.next().unwrap();
+    assert_eq!(
+        (add.addr, add.message_type, add.failed),
+        (addr, "add", false)
+    );
+    assert_eq!(timestamp, clock.system_time());
+    let (divide, _) = messages.next().unwrap();
+    assert_eq!((divide.message_type, divide.failed), ("divide", true));
+
+    let json = ndjson::to_json(&ServerEvent::MessageHandled {
+        message: add,
+        timestamp,
+    });
+    let timestamp_ms = timestamp.duration_since(UNIX_EPOCH).unwrap().as_millis();
+    assert!(
+        json.starts_with(&format!("{{\"timestamp_ms\":{},", timestamp_ms)),
+        "{}",
+        json
+    );
+    for member in [
+        "\"event\":\"message\"".to_string(),
+        format!("\"addr\":\"{}\"", addr),
+        "\"message_type\":\"add\"".to_string(),
+        format!("\"size\":{}", add.size),
+        "\"failed\":false".to_string(),
+    ] {
+        assert!(json.contains(&member), "{} lacks {}", json, member);
+    }
+
+    // Subscribers that did not ask for them see no message events
+    assert!(matches!(
+        lifecycle.try_recv(),
+        Ok(ServerEvent::Connected { .. })
+    ));
+    assert!(lifecycle.try_recv().is_err());
+
+    drop(client);
+    server.stop();
+}
@@ -0,0 +1,95 @@
+#![cfg(feature = "server")]
+
+use embedded_recruitment_task::{
+    events::{CloseReason, ConnectionObserver, HandledMessage},
+    message::{client_message, EchoMessage},
+    server::Server,
+};
+use std::{
+    net::SocketAddr,
+    sync::{Arc, Mutex},
+    thread,
+    time::{Duration, Instant},
+};
+
+mod client;
+mod test_server;
+
+use test_server::TestServer;
+
+// Writes down every callback it gets
+#[derive(Debug, Default)]
+struct Recorder {
+    calls: Arc<Mutex<Vec<String>>>, // Callbacks in the order they ran
+}
+
+impl ConnectionObserver for Recorder {
+    fn on_connect(&self, addr: SocketAddr) {
+        self.calls.lock().unwrap().push(format!("connect {}", addr));
+    }
+
+    fn on_disconnect(&self, addr: SocketAddr, reason: CloseReason) {
+        let call = format!("disconnect {} {}", addr, reason);
+        self.calls.lock().unwrap().push(call);
+    }
+
+    fn on_message(&self, message: &HandledMessage) {
+        let call = format!("message {} {}", message.addr, message.message_type);
+        self.calls.lock().unwrap().push(call);
+    }
+}
+
+// Panics on every message
+#[derive(Debug)]
+struct Faulty;
+
+impl ConnectionObserver for Faulty {
+    fn on_message(&self, _message: &HandledMessage) {
+        panic!("Observer failed");
+    }
+}
+
+#[test]
+fn test_observers_see_connections_and_messages() {
+    let calls = Arc::new(Mutex::new(Vec::new()));
+    let server = Server::builder()
+        .observer(Faulty)
+        .observer(Recorder {
+            calls: Arc::clone(&calls),
+        })
+        .bind("127.0.0.1:0")
+        .expect("Failed to start server");
+    let server = TestServer::run(server);
+
+    let mut client = client::Client::new(&server.ip(), server.port(), 1000);
+    assert!(client.connect().is_ok(), "Failed to connect to the server");
+    let addr = client.stream().unwrap().local_addr().unwrap();
+    for content in ["one", "two"] {
+        let message = client_message::Message::EchoMessage(EchoMessage {
+            content: content.to_string(),
+        });
+        assert!(client.send(message).is_ok(), "Failed to send message");
+        // The panicking observer does not cost the client its answer
+        assert!(client.receive().is_ok(), "Failed to receive echo");
+    }
+    assert!(
+        client.disconnect().is_ok(),
+        "Failed to disconnect from the server"
+    );
+
+    let deadline = Instant::now() + Duration::from_secs(5);
+    while calls.lock().unwrap().len() < 4 {
+        assert!(Instant::now() < deadline, "Disconnect was not observed");
+        thread::sleep(Duration::from_millis(10));
+    }
+    assert_eq!(
+        *calls.lock().unwrap(),
+        [
+            format!("connect {}", addr),
+            format!("message {} echo", addr),
+            format!("message {} echo", addr),
+            format!("disconnect {} client_eof", addr),
+        ]
+    );
+    server.stop();
+}
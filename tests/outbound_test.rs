@@ -0,0 +1,78 @@
+#![cfg(feature = "server")]
+
+use embedded_recruitment_task::{
+    events::ServerEvent,
+    framing::{self, DEFAULT_MAX_FRAME_SIZE},
+    message::{client_message, server_message, AddRequest, ClientMessage, ServerMessage},
+    run_history::RunHistory,
+    server::Server,
+};
+use prost::Message;
+use std::{fs, net::TcpListener, sync::Arc, thread, time::Duration};
+
+#[test]
+fn test_outbound_connection() {
+    // The test plays the remote endpoint the server dials out to
+    let remote = TcpListener::bind("127.0.0.1:0").expect("Failed to bind remote endpoint");
+    let remote_addr = remote.local_addr().unwrap().to_string();
+
+    // The server's own listener is unused in outbound mode
+    let history = std::env::temp_dir().join(format!("outbound-test-{}.runs", std::process::id()));
+    let _ = fs::remove_file(&history);
+    let server = Server::builder()
+        .run_history(&history)
+        .bind("127.0.0.1:0")
+        .expect("Failed to start server");
+    let server = Arc::new(server);
+    let events = server.subscribe_events();
+    let handle = {
+        let server = server.clone();
+        thread::spawn(move || {
+            server
+                .run_outbound(&remote_addr)
+                .expect("Server encountered an error")
+        })
+    };
+
+    // Accept the connection dialed by the server and send it a request
+    let (mut stream, _) = remote.accept().expect("Server did not dial out");
+    stream
+        .set_read_timeout(Some(Duration::from_secs(5)))
+        .unwrap();
+    let request = ClientMessage {
+        message: Some(client_message::Message::AddRequest(AddRequest {
+            a: 2,
+            b: 3,
+        })),
+        ..Default::default()
+    };
+    framing::write_frame(&mut stream, &request.encode_to_vec()).unwrap();
+
+    // The server answers over the connection it opened
+    let payload =
+        framing::read_frame(&mut stream, DEFAULT_MAX_FRAME_SIZE).expect("Failed to read response");
+    match ServerMessage::decode(payload.as_slice()).unwrap().message {
+        Some(server_message::Message::AddResponse(add_response)) => {
+            assert_eq!(add_response.result, 5, "AddResponse result does not match");
+        }
+        _ => panic!("Expected AddResponse, but received a different message"),
+    }
+    assert!(matches!(
+        events.recv_timeout(Duration::from_secs(5)),
+        Ok(ServerEvent::Connected { .. })
+    ));
+
+    // Stop the server and wait for thread to finish
+    server.stop();
+    assert!(
+        handle.join().is_ok(),
+        "Server thread panicked or failed to join"
+    );
+
+    // The run was recorded like any other
+    let runs = RunHistory::load(&history).unwrap().recent(0);
+    assert_eq!(runs.len(), 1);
+    assert_eq!(runs[0].started_at_ms, server.server_info().started_at_ms);
+    assert!(runs[0].stopped_at_ms.is_some(), "Stop was not recorded");
+    fs::remove_file(&history).unwrap();
+}
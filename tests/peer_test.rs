@@ -0,0 +1,79 @@
+#![cfg(feature = "server")]
+
+use embedded_recruitment_task::{
+    client::Client,
+    peer::{normalize, PeerIdentity},
+    server::Server,
+    session::Session,
+};
+use std::{
+    net::SocketAddr,
+    thread,
+    time::{Duration, Instant},
+};
+
+mod test_server;
+
+use test_server::TestServer;
+
+#[test]
+fn test_mapped_addresses_are_normalized() {
+    let mapped: SocketAddr = "[::ffff:192.0.2.7]:4000".parse().unwrap();
+    assert_eq!(normalize(mapped), "192.0.2.7:4000".parse().unwrap());
+
+    // Genuine IPv6 and IPv4 addresses are left alone
+    for addr in ["[2001:db8::1]:4000", "[::1]:4000", "192.0.2.7:4000"] {
+        let addr: SocketAddr = addr.parse().unwrap();
+        assert_eq!(normalize(addr), addr);
+    }
+
+    // Sessions only ever show the normalized address
+    let session = Session::new(1, mapped);
+    assert_eq!(session.addr(), "192.0.2.7:4000".parse().unwrap());
+    assert_eq!(session.identity().to_string(), "192.0.2.7:4000");
+}
+
+#[cfg(unix)]
+#[test]
+fn test_identity_resolves_names() {
+    let identity = PeerIdentity::new("[::ffff:127.0.0.1]:4000".parse().unwrap());
+    assert!(!identity.is_resolved());
+    assert_eq!(identity.hostname(), None);
+
+    let hostname = identity
+        .resolve()
+        .expect("Loopback has no name")
+        .to_string();
+    assert!(identity.is_resolved());
+    assert_eq!(identity.hostname(), Some(hostname.as_str()));
+    assert_eq!(
+        identity.to_string(),
+        format!("{} (127.0.0.1:4000)", hostname)
+    );
+}
+
+#[cfg(unix)]
+#[test]
+fn test_server_resolves_peer_names_in_background() {
+    let server = Server::builder()
+        .resolve_peer_names(true)
+        .bind("127.0.0.1:0")
+        .expect("Failed to start server");
+    let server = TestServer::run(server);
+
+    let mut client = Client::connect(("127.0.0.1", server.port() as u16))
+        .expect("Failed to connect to the server");
+    assert_eq!(client.add(1, 2).unwrap(), 3);
+
+    let sessions = server.server().active_sessions();
+    let identity = sessions[0].identity();
+    let deadline = Instant::now() + Duration::from_secs(5);
+    while !identity.is_resolved() {
+        assert!(Instant::now() < deadline, "Peer name was not resolved");
+        thread::sleep(Duration::from_millis(10));
+    }
+    assert!(identity.hostname().is_some());
+
+    drop(client);
+    server.stop();
+}
@@ -0,0 +1,131 @@
+#![cfg(feature = "server")]
+
+use embedded_recruitment_task::{
+    client::Client,
+    message::{client_message, AddRequest, ClientMessage},
+    pool::WorkerPool,
+    server::Server,
+};
+use std::{
+    sync::{
+        atomic::{AtomicBool, AtomicUsize, Ordering},
+        mpsc, Arc,
+    },
+    thread,
+    time::{Duration, Instant},
+};
+
+mod test_server;
+
+use test_server::TestServer;
+
+#[test]
+fn test_pool_bounds_concurrent_jobs() {
+    let pool = WorkerPool::new(2);
+    assert_eq!(pool.size(), 2);
+
+    let running = Arc::new(AtomicUsize::new(0));
+    let peak = Arc::new(AtomicUsize::new(0));
+    let (done_tx, done_rx) = mpsc::channel();
+    for _ in 0..8 {
+        let (running, peak, done_tx) = (Arc::clone(&running), Arc::clone(&peak), done_tx.clone());
+        pool.execute(Box::new(move || {
+            let now = running.fetch_add(1, Ordering::SeqCst) + 1;
+            peak.fetch_max(now, Ordering::SeqCst);
+            thread::sleep(Duration::from_millis(20));
+            running.fetch_sub(1, Ordering::SeqCst);
+            done_tx.send(()).unwrap();
+        }));
+    }
+
+    // Dropping the pool runs every queued job before the workers exit
+    drop(pool);
+    assert_eq!(done_rx.try_iter().count(), 8);
+    assert_eq!(peak.load(Ordering::SeqCst), 2);
+}
+
+#[test]
+fn test_idle_connections_share_the_pool() {
+    let server = Server::new_with_pool("127.0.0.1:0", 1).expect("Failed to start server");
+    let server = TestServer::run(server);
+    let addr = ("127.0.0.1", server.port() as u16);
+
+    let mut first = Client::connect(addr).expect("Failed to connect to the server");
+    assert_eq!(first.add(1, 2).unwrap(), 3);
+
+    // The only worker serves the first client, which gives it up once it stays silent
+    let mut second = Client::connect(addr).expect("Failed to connect to the server");
+    assert_eq!(second.add(3, 4).unwrap(), 7);
+
+    // Both connections keep taking turns
+    assert_eq!(first.add(5, 6).unwrap(), 11);
+    assert_eq!(second.add(7, 8).unwrap(), 15);
+
+    drop(first);
+    drop(second);
+    server.stop();
+}
+
+#[test]
+fn test_flooding_client_does_not_starve_others() {
+    let server = Server::new_with_pool("127.0.0.1:0", 1).expect("Failed to start server");
+    let server = TestServer::run(server);
+    let addr = ("127.0.0.1", server.port() as u16);
+
+    // The flooder keeps its connection busy with requests for as long as the test runs
+    let flooder = Client::connect(addr).expect("Failed to connect to the server");
+    let (mut reader, writer) = flooder.split().unwrap();
+    let stop = Arc::new(AtomicBool::new(false));
+    let answered = Arc::new(AtomicUsize::new(0));
+    let sending = {
+        let stop = Arc::clone(&stop);
+        thread::spawn(move || {
+            while !stop.load(Ordering::SeqCst) {
+                let message = ClientMessage {
+                    message: Some(client_message::Message::AddRequest(AddRequest {
+                        a: 1,
+                        b: 2,
+                    })),
+                    ..ClientMessage::default()
+                };
+                if writer.send(message).is_err() {
+                    break;
+                }
+            }
+        })
+    };
+    let receiving = {
+        let (stop, answered) = (Arc::clone(&stop), Arc::clone(&answered));
+        thread::spawn(move || {
+            while !stop.load(Ordering::SeqCst) {
+                if reader.receive().is_ok() {
+                    answered.fetch_add(1, Ordering::SeqCst);
+                }
+            }
+        })
+    };
+    while answered.load(Ordering::SeqCst) < 100 {
+        thread::sleep(Duration::from_millis(10));
+    }
+
+    // The pinger still gets its turn on the only worker
+    let mut pinger = Client::connect(addr).expect("Failed to connect to the server");
+    for i in 0..5 {
+        let started = Instant::now();
+        let content = format!("ping {}", i);
+        assert_eq!(pinger.send_echo(&content).unwrap(), content);
+        assert!(started.elapsed() < Duration::from_secs(2));
+        thread::sleep(Duration::from_millis(50));
+    }
+
+    // And the flooder keeps being served in between
+    let before = answered.load(Ordering::SeqCst);
+    thread::sleep(Duration::from_millis(200));
+    assert!(answered.load(Ordering::SeqCst) > before);
+
+    stop.store(true, Ordering::SeqCst);
+    drop(pinger);
+    sending.join().unwrap();
+    receiving.join().unwrap();
+    server.stop();
+}
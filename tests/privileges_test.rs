@@ -0,0 +1,21 @@
+#![cfg(feature = "server")]
+
+use embedded_recruitment_task::server::{Server, ServerError};
+use std::io::ErrorKind;
+
+#[test]
+fn test_unknown_user_fails_before_serving() {
+    // Binding works, but the server must refuse to run as it cannot drop to the user
+    let server = Server::builder()
+        .user("no-such-user-for-this-test")
+        .bind("localhost:0")
+        .expect("Failed to bind server");
+
+    let error = server
+        .run()
+        .expect_err("Server ran without dropping privileges");
+    match error {
+        ServerError::Io(e) => assert_eq!(e.kind(), ErrorKind::NotFound),
+        error => panic!("Unexpected error: {}", error),
+    }
+}
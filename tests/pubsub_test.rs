@@ -0,0 +1,462 @@
+#![cfg(feature = "server")]
+
+use embedded_recruitment_task::{
+    client::Client,
+    message::{
+        client_message, server_message, Publication, PublishAck, PublishRequest, PublishResponse,
+        ServerMessage, SubscribeRequest, UnsubscribeRequest,
+    },
+    pubsub::{Published, Subscribed, Topics, OUTBOUND_CAPACITY},
+    server::Server,
+};
+use std::{
+    sync::mpsc,
+    thread,
+    time::{Duration, Instant},
+};
+
+mod test_server;
+
+use test_server::TestServer;
+
+// Connects a new client to the server
+fn connect(server: &TestServer) -> Client {
+    Client::connect(("127.0.0.1", server.port() as u16)).expect("Failed to connect to the server")
+}
+
+// Subscribes the client to `topic`
+fn subscribe(client: &mut Client, topic: &str) {
+    let request = client_message::Message::SubscribeRequest(SubscribeRequest {
+        topic: topic.to_string(),
+        from_offset: None,
+    });
+    match client.request(request).unwrap().message {
+        Some(server_message::Message::SubscribeResponse(response)) => {
+            assert_eq!(response.error, "", "Subscribing failed");
+        }
+        other => panic!("Expected SubscribeResponse, got {:?}", other),
+    }
+}
+
+// Publishes `payload` to `topic`
+fn publish(client: &mut Client, topic: &str, payload: &[u8]) -> PublishResponse {
+    let request = client_message::Message::PublishRequest(PublishRequest {
+        topic: topic.to_string(),
+        payload: payload.to_vec(),
+        ..PublishRequest::default()
+    });
+    match client.request(request).unwrap().message {
+        Some(server_message::Message::PublishResponse(response)) => response,
+        other => panic!("Expected PublishResponse, got {:?}", other),
+    }
+}
+
+// Waits for the next publication pushed to the client
+fn next_publication(client: &mut Client) -> Publication {
+    match client.receive().unwrap().message {
+        Some(server_message::Message::Publication(publication)) => publication,
+        other => panic!("Expected Publication, got {:?}", other),
+    }
+}
+
+// A publication of `payload` to `topic`
+fn publication(topic: &str, payload: &[u8]) -> Publication {
+    Publication {
+        topic: topic.to_string(),
+        payload: payload.to_vec(),
+        publisher_id: 1,
+        offset: 0,
+    }
+}
+
+// A publication of `payload`, as it is queued for subscribers
+fn queued(payload: &[u8], offset: u64) -> ServerMessage {
+    ServerMessage {
+        message: Some(server_message::Message::Publication(Publication {
+            offset,
+            ..publication("news", payload)
+        })),
+        ..ServerMessage::default()
+    }
+}
+
+#[test]
+fn test_topics_track_subscribers() {
+    let topics = Topics::new();
+    let (sender, receiver) = mpsc::sync_channel(4);
+
+    assert!(topics.subscribe("news", 1, sender.clone()));
+    assert!(
+        !topics.subscribe("news", 1, sender.clone()),
+        "Subscribed twice"
+    );
+    assert!(topics.subscribe("sport", 1, sender));
+    assert_eq!(topics.topics(), ["news", "sport"]);
+
+    assert_eq!(topics.publish(publication("news", b"a")), 1);
+    assert_eq!(topics.publish(publication("weather", b"b")), 0);
+    assert_eq!(receiver.try_recv().unwrap(), queued(b"a", 0));
+    assert!(receiver.try_recv().is_err());
+
+    assert!(topics.unsubscribe("news", 1));
+    assert!(!topics.unsubscribe("news", 1));
+    topics.unsubscribe_all(1);
+    assert!(topics.topics().is_empty());
+}
+
+#[test]
+fn test_full_and_dropped_subscribers() {
+    let topics = Topics::new();
+    let (full, _full) = mpsc::sync_channel(1);
+    let (gone, dropped) = mpsc::sync_channel(1);
+    drop(dropped);
+    topics.subscribe("news", 1, full);
+    topics.subscribe("news", 2, gone);
+
+    // A full queue misses the message but stays subscribed, a dropped one is forgotten
+    assert_eq!(topics.publish(publication("news", b"a")), 1);
+    assert_eq!(topics.publish(publication("news", b"b")), 0);
+    assert_eq!(topics.subscribers("news"), 1);
+}
+
+// The acknowledgement queued for a publisher
+fn ack(receiver: &mpsc::Receiver<ServerMessage>) -> Option<PublishAck> {
+    match receiver.try_recv().ok()?.message {
+        Some(server_message::Message::PublishAck(ack)) => Some(ack),
+        other => panic!("Expected PublishAck, got {:?}", other),
+    }
+}
+
+#[test]
+fn test_receipts_wait_for_every_copy() {
+    let topics = Topics::new();
+    let (reader, read) = mpsc::sync_channel(4);
+    let (full, _full) = mpsc::sync_channel(1);
+    let (gone, dropped) = mpsc::sync_channel(1);
+    drop(dropped);
+    let (publisher, receipts) = mpsc::sync_channel(4);
+    topics.subscribe("news", 1, reader.clone());
+    topics.subscribe("news", 2, full);
+    topics.subscribe("news", 3, gone);
+    // Session 4's copy is still waiting to be written
+    topics.subscribe("news", 4, reader);
+    // Fills session 2's queue, and forgets session 3
+    topics.publish(publication("news", b"a"));
+    read.try_iter().count();
+
+    let published = topics.publish_with_receipt(publication("news", b"b"), Some(publisher));
+    assert_eq!(
+        published,
+        Published {
+            queued: 2,
+            offset: 1
+        }
+    );
+    let copy = match read.try_recv().unwrap().message {
+        Some(server_message::Message::Publication(publication)) => publication,
+        other => panic!("Expected Publication, got {:?}", other),
+    };
+    topics.settle(&copy, 1, true);
+    assert_eq!(
+        ack(&receipts),
+        None,
+        "Acknowledged before every copy was settled"
+    );
+
+    // Copies nobody waits for, such as replayed ones, change nothing
+    topics.settle(&copy, 5, true);
+    topics.settle(
+        &Publication {
+            offset: 0,
+            ..copy.clone()
+        },
+        4,
+        true,
+    );
+    topics.settle(&copy, 4, false);
+    assert_eq!(
+        ack(&receipts),
+        Some(PublishAck {
+            topic: "news".to_string(),
+            offset: 1,
+            delivered: 1,
+            failed: 2,
+        })
+    );
+    topics.settle(&copy, 4, true);
+    assert_eq!(ack(&receipts), None, "Acknowledged twice");
+
+    // Without subscribers there is nothing to wait for
+    let (publisher, receipts) = mpsc::sync_channel(4);
+    topics.publish_with_receipt(publication("weather", b"c"), Some(publisher));
+    let ack = ack(&receipts).expect("Publication without subscribers was not acknowledged");
+    assert_eq!((ack.delivered, ack.failed), (0, 0));
+}
+
+#[test]
+fn test_offsets_survive_the_last_subscriber() {
+    let topics = Topics::new();
+    let (reader, read) = mpsc::sync_channel(4);
+    let (publisher, receipts) = mpsc::sync_channel(4);
+    topics.subscribe("news", 1, reader.clone());
+    let first = topics.publish_with_receipt(publication("news", b"a"), Some(publisher.clone()));
+    assert_eq!(first.offset, 0);
+
+    // The copy is still queued when the topic loses its only subscriber
+    assert!(topics.unsubscribe("news", 1));
+    assert!(topics.topics().is_empty());
+    let second = topics.publish_with_receipt(publication("news", b"b"), Some(publisher));
+    assert_eq!(second.offset, 1, "Offset was handed out again");
+    assert_eq!(ack(&receipts).map(|ack| ack.offset), Some(1));
+
+    // Settling the first copy still acknowledges the first publication
+    let copy = match read.try_recv().unwrap().message {
+        Some(server_message::Message::Publication(publication)) => publication,
+        other => panic!("Expected Publication, got {:?}", other),
+    };
+    topics.settle(&copy, 1, true);
+    let ack = ack(&receipts).expect("First publication was never acknowledged");
+    assert_eq!((ack.offset, ack.delivered), (0, 1));
+
+    topics.subscribe("news", 1, reader);
+    assert_eq!(topics.publish(publication("news", b"c")), 1);
+    assert_eq!(read.try_recv().unwrap(), queued(b"c", 2));
+}
+
+#[test]
+fn test_publishers_are_acknowledged() {
+    let server = TestServer::start();
+    let mut subscriber = connect(&server);
+    let mut publisher = connect(&server);
+    subscribe(&mut subscriber, "news");
+    subscribe(&mut publisher, "news");
+
+    let request = client_message::Message::PublishRequest(PublishRequest {
+        topic: "news".to_string(),
+        payload: b"Hello".to_vec(),
+        ack: true,
+    });
+    let response = match publisher.request(request).unwrap().message {
+        Some(server_message::Message::PublishResponse(response)) => response,
+        other => panic!("Expected PublishResponse, got {:?}", other),
+    };
+    assert_eq!(response.delivered, 2);
+
+    // The publisher's own copy comes first, the acknowledgement once both were written
+    assert_eq!(next_publication(&mut publisher).offset, response.offset);
+    match publisher.receive().unwrap().message {
+        Some(server_message::Message::PublishAck(ack)) => {
+            assert_eq!(ack.offset, response.offset);
+            assert_eq!((ack.delivered, ack.failed), (2, 0));
+        }
+        other => panic!("Expected PublishAck, got {:?}", other),
+    }
+    assert_eq!(next_publication(&mut subscriber).payload, b"Hello");
+
+    drop((subscriber, publisher));
+    server.stop();
+}
+
+#[test]
+fn test_publications_reach_subscribers() {
+    let server = TestServer::start();
+    let mut subscriber = connect(&server);
+    let mut other = connect(&server);
+    let mut publisher = connect(&server);
+    subscribe(&mut subscriber, "news");
+    subscribe(&mut other, "sport");
+
+    let response = publish(&mut publisher, "news", b"Hello");
+    assert_eq!(response.error, "");
+    assert_eq!(response.delivered, 1);
+
+    let publication = next_publication(&mut subscriber);
+    assert_eq!(publication.topic, "news");
+    assert_eq!(publication.payload, b"Hello");
+    assert!(publication.publisher_id > 0);
+
+    // The subscriber keeps being served as usual
+    assert_eq!(subscriber.add(1, 2).unwrap(), 3);
+
+    // Nothing was published to the other topic
+    other
+        .stream()
+        .set_read_timeout(Some(Duration::from_millis(300)))
+        .unwrap();
+    assert!(
+        other.receive().is_err(),
+        "Received an unrelated publication"
+    );
+
+    drop((subscriber, other, publisher));
+    server.stop();
+}
+
+#[test]
+fn test_unsubscribed_clients_receive_nothing() {
+    let server = TestServer::start();
+    let mut subscriber = connect(&server);
+    let mut publisher = connect(&server);
+    subscribe(&mut subscriber, "news");
+
+    let request = client_message::Message::UnsubscribeRequest(UnsubscribeRequest {
+        topic: "news".to_string(),
+    });
+    match subscriber.request(request).unwrap().message {
+        Some(server_message::Message::UnsubscribeResponse(response)) => {
+            assert!(response.was_subscribed);
+        }
+        other => panic!("Expected UnsubscribeResponse, got {:?}", other),
+    }
+    assert_eq!(publish(&mut publisher, "news", b"Hello").delivered, 0);
+
+    // Empty topics are rejected
+    assert!(!publish(&mut publisher, "", b"Hello").error.is_empty());
+
+    drop((subscriber, publisher));
+    server.stop();
+}
+
+#[test]
+fn test_subscriptions_end_with_the_connection() {
+    let server = TestServer::start();
+    let mut subscriber = connect(&server);
+    let mut publisher = connect(&server);
+    subscribe(&mut subscriber, "news");
+    drop(subscriber);
+
+    let deadline = Instant::now() + Duration::from_secs(5);
+    while publish(&mut publisher, "news", b"Hello").delivered > 0 {
+        assert!(
+            Instant::now() < deadline,
+            "Subscription outlived its connection"
+        );
+        thread::sleep(Duration::from_millis(10));
+    }
+
+    drop(publisher);
+    server.stop();
+}
+
+#[test]
+fn test_outbound_queue_is_bounded() {
+    let topics = Topics::new();
+    let (sender, receiver) = mpsc::sync_channel(OUTBOUND_CAPACITY);
+    topics.subscribe("news", 1, sender);
+    for _ in 0..OUTBOUND_CAPACITY + 10 {
+        topics.publish(publication("news", b"a"));
+    }
+    assert_eq!(receiver.try_iter().count(), OUTBOUND_CAPACITY);
+}
+
+#[test]
+fn test_retained_publications_are_replayed() {
+    let topics = Topics::with_retention(3);
+    for payload in [b"a", b"b", b"c", b"d"] {
+        assert_eq!(topics.publish(publication("news", payload)), 0);
+    }
+    assert_eq!(topics.topics(), ["news"], "Retained topic was forgotten");
+
+    // Only the last three are retained, replay starts at the offset asked for
+    let (sender, receiver) = mpsc::sync_channel(OUTBOUND_CAPACITY);
+    let subscribed = topics.subscribe_from("news", 1, sender.clone(), Some(2));
+    assert_eq!(
+        subscribed,
+        Subscribed {
+            is_new: true,
+            replayed: 2,
+            next_offset: 4,
+        }
+    );
+    let replayed: Vec<ServerMessage> = receiver.try_iter().collect();
+    assert_eq!(replayed, [queued(b"c", 2), queued(b"d", 3)]);
+
+    // An offset that is no longer retained replays whatever is left
+    let subscribed = topics.subscribe_from("news", 1, sender, Some(0));
+    assert!(!subscribed.is_new);
+    assert_eq!(subscribed.replayed, 3);
+
+    // New publications continue the sequence
+    receiver.try_iter().count();
+    assert_eq!(topics.publish(publication("news", b"e")), 1);
+    assert_eq!(receiver.try_recv().unwrap(), queued(b"e", 4));
+}
+
+#[test]
+fn test_reconnecting_subscriber_catches_up() {
+    let server = Server::builder()
+        .topic_retention(16)
+        .bind("127.0.0.1:0")
+        .expect("Failed to start server");
+    let server = TestServer::run(server);
+    let mut publisher = connect(&server);
+
+    let mut subscriber = connect(&server);
+    subscribe(&mut subscriber, "news");
+    publish(&mut publisher, "news", b"first");
+    let seen = next_publication(&mut subscriber);
+    assert_eq!(seen.offset, 0);
+    drop(subscriber);
+
+    // Published while the subscriber was away
+    publish(&mut publisher, "news", b"second");
+    publish(&mut publisher, "news", b"third");
+
+    let mut subscriber = connect(&server);
+    let request = client_message::Message::SubscribeRequest(SubscribeRequest {
+        topic: "news".to_string(),
+        from_offset: Some(seen.offset + 1),
+    });
+    match subscriber.request(request).unwrap().message {
+        Some(server_message::Message::SubscribeResponse(response)) => {
+            assert_eq!(response.replayed, 2);
+            assert_eq!(response.next_offset, 3);
+        }
+        other => panic!("Expected SubscribeResponse, got {:?}", other),
+    }
+    let missed: Vec<Vec<u8>> = (0..2)
+        .map(|_| next_publication(&mut subscriber).payload)
+        .collect();
+    assert_eq!(missed, [b"second".to_vec(), b"third".to_vec()]);
+
+    drop((subscriber, publisher));
+    server.stop();
+}
+
+#[test]
+fn test_requests_skip_pushed_messages() {
+    let server = TestServer::start();
+    let mut subscriber = connect(&server);
+    let mut publisher = connect(&server);
+    let request = client_message::Message::SubscribeRequest(SubscribeRequest {
+        topic: "news".to_string(),
+        ..SubscribeRequest::default()
+    });
+    subscriber.request(request).unwrap();
+
+    let request = client_message::Message::PublishRequest(PublishRequest {
+        topic: "news".to_string(),
+        payload: b"hello".to_vec(),
+        ack: true,
+    });
+    publisher.request(request).unwrap();
+    assert!(publisher.ping().is_ok(), "Ping failed while an ack was due");
+    // Once acknowledged, the publication is waiting on the subscriber's connection
+    match publisher.next_push().unwrap().message {
+        Some(server_message::Message::PublishAck(ack)) => assert_eq!(ack.delivered, 1),
+        other => panic!("Expected PublishAck, got {:?}", other),
+    }
+
+    // Requests are answered as usual, the publication is kept for later
+    assert_eq!(subscriber.add(1, 2).unwrap(), 3);
+    assert!(subscriber.session_info().is_ok());
+    match subscriber.next_push().unwrap().message {
+        Some(server_message::Message::Publication(publication)) => {
+            assert_eq!(publication.payload, b"hello")
+        }
+        other => panic!("Expected Publication, got {:?}", other),
+    }
+
+    drop((subscriber, publisher));
+    server.stop();
+}
@@ -0,0 +1,70 @@
+#![cfg(all(feature = "server", target_os = "linux"))]
+
+use embedded_recruitment_task::{
+    events::{CloseReason, ServerEvent},
+    resources::{ResourceLimits, ResourceUsage},
+    server::Server,
+};
+use std::time::Duration;
+
+mod client;
+mod test_server;
+
+use test_server::TestServer;
+
+#[test]
+fn test_oldest_idle_connection_is_shed_under_pressure() {
+    // Leave room for the listener plus the first client and its server side, the second
+    // client crosses the limit. This is the only test of the binary, so nothing else opens
+    // descriptors meanwhile.
+    let open_fds = ResourceUsage::sample()
+        .open_fds
+        .expect("Failed to count file descriptors");
+    let limits = ResourceLimits {
+        max_open_fds: Some(open_fds + 4),
+        shed_idle: true,
+        check_interval: Duration::from_millis(10),
+        ..ResourceLimits::default()
+    };
+    let server = Server::builder()
+        .resource_limits(limits)
+        .bind("127.0.0.1:0")
+        .expect("Failed to start server");
+    let events = server.subscribe_events();
+    let server = TestServer::run(server);
+    let next_event = || {
+        events
+            .recv_timeout(Duration::from_secs(5))
+            .expect("Timed out waiting for a server event")
+    };
+
+    let mut first = client::Client::new(&server.ip(), server.port(), 1000);
+    assert!(first.connect().is_ok(), "Failed to connect to the server");
+    let first_addr = match next_event() {
+        ServerEvent::Connected { addr } => addr,
+        event => panic!("Expected Connected event, got {:?}", event),
+    };
+
+    let mut second = client::Client::new(&server.ip(), server.port(), 1000);
+    assert!(second.connect().is_ok(), "Failed to connect to the server");
+    assert!(matches!(next_event(), ServerEvent::Connected { .. }));
+
+    // The limit is crossed, the oldest connection goes and usage is back to normal
+    assert!(matches!(next_event(), ServerEvent::ResourcePressure { .. }));
+    match next_event() {
+        ServerEvent::Disconnected { addr, reason } => {
+            assert_eq!(addr, first_addr, "Shed the wrong connection");
+            assert_eq!(reason, CloseReason::ResourcePressure);
+        }
+        event => panic!("Expected Disconnected event, got {:?}", event),
+    }
+    drop(first);
+    assert!(matches!(
+        next_event(),
+        ServerEvent::ResourcePressureRelieved { .. }
+    ));
+    assert_eq!(server.server().active_sessions().len(), 1);
+
+    // Stop the server and wait for thread to finish
+    server.stop();
+}
@@ -0,0 +1,78 @@
+#![cfg(feature = "server")]
+
+use embedded_recruitment_task::{
+    rng::{stream_seed, Rng},
+    server::Server,
+};
+use std::{
+    thread,
+    time::{Duration, Instant},
+};
+
+mod client;
+mod test_server;
+
+use test_server::TestServer;
+
+#[test]
+fn test_rng_is_reproducible() {
+    let mut a = Rng::new(42);
+    let mut b = Rng::new(42);
+    let first: Vec<u64> = (0..8).map(|_| a.next_u64()).collect();
+    let second: Vec<u64> = (0..8).map(|_| b.next_u64()).collect();
+    assert_eq!(first, second, "Same seed produced different sequences");
+
+    // Streams of the same seed differ from each other, but not between runs
+    assert_eq!(stream_seed(Some(42), 1), stream_seed(Some(42), 1));
+    assert_ne!(stream_seed(Some(42), 1), stream_seed(Some(42), 2));
+
+    for _ in 0..1000 {
+        let value = a.next_f64();
+        assert!((0.0..1.0).contains(&value), "Out of range: {}", value);
+        assert!((10..20).contains(&a.gen_range(10..20)));
+    }
+    assert!(!a.chance(0.0));
+    assert!(a.chance(1.0));
+}
+
+// Connects a client to a server seeded with `seed` and draws from its session's stream
+fn first_session_draw(seed: u64) -> u64 {
+    let server = Server::builder()
+        .rng_seed(seed)
+        .bind("127.0.0.1:0")
+        .expect("Failed to start server");
+    let server = TestServer::run(server);
+
+    let mut client = client::Client::new(&server.ip(), server.port(), 1000);
+    assert!(client.connect().is_ok(), "Failed to connect to the server");
+
+    // Wait for the server to register the session
+    let started = Instant::now();
+    let session = loop {
+        if let Some(session) = server.server().active_sessions().pop() {
+            break session;
+        }
+        assert!(
+            started.elapsed() < Duration::from_secs(5),
+            "Session was never registered"
+        );
+        thread::sleep(Duration::from_millis(10));
+    };
+    let draw = session.rng().next_u64();
+
+    // Disconnect the client
+    assert!(
+        client.disconnect().is_ok(),
+        "Failed to disconnect from the server"
+    );
+
+    // Stop the server and wait for thread to finish
+    server.stop();
+    draw
+}
+
+#[test]
+fn test_seeded_servers_are_deterministic() {
+    assert_eq!(first_session_draw(7), first_session_draw(7));
+    assert_ne!(first_session_draw(7), first_session_draw(8));
+}
@@ -0,0 +1,146 @@
+#![cfg(feature = "server")]
+
+use embedded_recruitment_task::{
+    admin::ADMIN_TOKEN_HEADER,
+    message::{
+        client_message, server_message, RunHistoryRequest, RunHistoryResponse, RunLog, RunRecord,
+    },
+    run_history::{RunHistory, MAX_RUNS},
+    server::Server,
+};
+use prost::Message;
+use std::{collections::HashMap, fs, io::ErrorKind, path::PathBuf};
+
+mod client;
+mod test_server;
+
+use test_server::TestServer;
+
+// A history file of its own for every test
+fn history_path(name: &str) -> PathBuf {
+    let path = std::env::temp_dir().join(format!(
+        "run-history-test-{}-{}.runs",
+        std::process::id(),
+        name
+    ));
+    let _ = fs::remove_file(&path);
+    path
+}
+
+// Asks the server for its recent runs, presenting `token` if there is one
+fn run_history(client: &mut client::Client, limit: u32, token: Option<&str>) -> RunHistoryResponse {
+    let headers = token
+        .map(|token| HashMap::from([(ADMIN_TOKEN_HEADER.to_string(), token.to_string())]))
+        .unwrap_or_default();
+    let message = client_message::Message::RunHistoryRequest(RunHistoryRequest { limit });
+    assert!(
+        client.send_with_headers(message, headers).is_ok(),
+        "Failed to send message"
+    );
+    match client.receive().map(|response| response.message) {
+        Ok(Some(server_message::Message::RunHistoryResponse(response))) => response,
+        _ => panic!("Expected RunHistoryResponse, but received a different message"),
+    }
+}
+
+#[test]
+fn test_runs_that_never_stopped_are_marked_crashed() {
+    let mut history = RunHistory::new();
+    assert!(history.recent(0).is_empty());
+    assert!(!history.previous_run_crashed());
+
+    history.record_start(100).unwrap();
+    history.record_stop(200).unwrap();
+    history.record_start(300).unwrap();
+    assert!(!history.previous_run_crashed());
+
+    // The run starting at 300 never stopped
+    history.record_start(400).unwrap();
+    assert!(history.previous_run_crashed());
+    let runs = history.recent(0);
+    assert_eq!(
+        runs.iter().map(|run| run.started_at_ms).collect::<Vec<_>>(),
+        [400, 300, 100]
+    );
+    assert_eq!(runs[0].stopped_at_ms, None);
+    assert!(!runs[0].crashed);
+    assert!(runs[1].crashed);
+    assert_eq!(runs[2].stopped_at_ms, Some(200));
+    assert_eq!(history.recent(1).len(), 1);
+
+    // Only the newest runs are kept
+    for started_at_ms in 0..MAX_RUNS as u64 {
+        history.record_start(1000 + started_at_ms).unwrap();
+    }
+    assert_eq!(history.recent(0).len(), MAX_RUNS);
+}
+
+#[test]
+fn test_history_round_trips_through_its_file() {
+    let path = history_path("round-trip");
+    let mut history = RunHistory::load(&path).expect("Missing file was not an empty history");
+    assert!(history.recent(0).is_empty());
+    history.record_start(100).unwrap();
+    history.record_stop(200).unwrap();
+    history.record_start(300).unwrap();
+    drop(history);
+
+    let history = RunHistory::load(&path).unwrap();
+    let runs = history.recent(0);
+    assert_eq!(runs.len(), 2);
+    assert_eq!(runs[1].stopped_at_ms, Some(200));
+
+    // A corrupt file is not silently replaced by an empty history
+    fs::write(&path, [0xff; 4]).unwrap();
+    let error = RunHistory::load(&path).expect_err("Corrupt history was loaded");
+    assert_eq!(error.kind(), ErrorKind::InvalidData);
+    fs::remove_file(&path).unwrap();
+}
+
+#[test]
+fn test_server_reports_its_runs() {
+    let path = history_path("server");
+    // The last run recorded never stopped, as if the process had been killed
+    let log = RunLog {
+        runs: vec![RunRecord {
+            started_at_ms: 1,
+            stopped_at_ms: None,
+            crashed: false,
+        }],
+    };
+    fs::write(&path, log.encode_to_vec()).unwrap();
+
+    let start = || {
+        let server = Server::builder()
+            .admin_token("secret")
+            .run_history(&path)
+            .bind("127.0.0.1:0")
+            .expect("Failed to start server");
+        TestServer::run(server)
+    };
+    let server = start();
+    let info = server.server().server_info();
+    assert!(info.previous_run_crashed, "Crash was not reported");
+    assert!(info.started_at_ms > 1);
+
+    let mut client = client::Client::new(&server.ip(), server.port(), 1000);
+    assert!(client.connect().is_ok(), "Failed to connect to the server");
+    assert_eq!(run_history(&mut client, 0, None).error, "Unauthorized");
+    let response = run_history(&mut client, 0, Some("secret"));
+    assert!(response.error.is_empty());
+    assert_eq!(response.runs.len(), 2);
+    assert_eq!(response.runs[0].started_at_ms, info.started_at_ms);
+    assert!(response.runs[1].crashed);
+    assert!(client.disconnect().is_ok());
+    server.stop();
+
+    // This time the previous run stopped cleanly
+    let server = start();
+    assert!(!server.server().server_info().previous_run_crashed);
+    server.stop();
+
+    let runs = RunHistory::load(&path).unwrap().recent(0);
+    assert_eq!(runs.len(), 3);
+    assert!(runs.iter().take(2).all(|run| run.stopped_at_ms.is_some()));
+    fs::remove_file(&path).unwrap();
+}
@@ -0,0 +1,16 @@
+#![cfg(feature = "server")]
+
+use embedded_recruitment_task::server::Server;
+
+#[test]
+fn test_self_test_passes() {
+    let report = Server::self_test().expect("Failed to run the self-test");
+    assert!(report.passed(), "Self-test failed:\n{}", report);
+
+    let names: Vec<_> = report.checks.iter().map(|check| check.name).collect();
+    assert_eq!(names, ["connect", "echo", "add", "malformed_message"]);
+    assert!(
+        report.addr.ip().is_loopback(),
+        "Self-test left the loopback"
+    );
+}
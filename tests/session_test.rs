@@ -0,0 +1,260 @@
+#![cfg(feature = "server")]
+
+use embedded_recruitment_task::{
+    clock::MockClock,
+    events::{CloseReason, ServerEvent},
+    handler::RequestContext,
+    message::{client_message, server_message, AddRequest, AddResponse},
+    server::{Server, ServerConfig},
+};
+use std::{
+    thread,
+    time::{Duration, Instant, SystemTime},
+};
+
+mod client;
+mod test_server;
+
+use test_server::TestServer;
+
+#[test]
+fn test_session_cancelled_on_disconnect() {
+    // Set up the server and subscribe to its events before any client connects
+    let server = TestServer::start();
+    let events = server.server().subscribe_events();
+
+    // Create and connect the client
+    let mut client = client::Client::new(&server.ip(), server.port(), 1000);
+    assert!(client.connect().is_ok(), "Failed to connect to the server");
+
+    // Wait for the server to register the session
+    let started = Instant::now();
+    let session = loop {
+        if let Some(session) = server.server().active_sessions().pop() {
+            break session;
+        }
+        assert!(
+            started.elapsed() < Duration::from_secs(5),
+            "Session was never registered"
+        );
+        thread::sleep(Duration::from_millis(10));
+    };
+    assert!(
+        !session.is_cancelled(),
+        "Fresh session is already cancelled"
+    );
+    assert_eq!(
+        session.in_flight(),
+        0,
+        "Idle session has requests in flight"
+    );
+
+    // Once the client disconnects the session is cancelled and forgotten
+    assert!(
+        client.disconnect().is_ok(),
+        "Failed to disconnect from the server"
+    );
+    loop {
+        let event = events
+            .recv_timeout(Duration::from_secs(5))
+            .expect("Timed out waiting for a server event");
+        if matches!(event, ServerEvent::Disconnected { .. }) {
+            break;
+        }
+    }
+    assert!(session.is_cancelled(), "Session was not cancelled");
+    assert!(
+        server.server().active_sessions().is_empty(),
+        "Session is still registered"
+    );
+
+    // Stop the server and wait for thread to finish
+    server.stop();
+}
+
+#[test]
+fn test_clients_can_be_listed_and_kicked() {
+    let server = TestServer::start();
+    let events = server.server().subscribe_events();
+    let next_event = || {
+        events
+            .recv_timeout(Duration::from_secs(5))
+            .expect("Timed out waiting for a server event")
+    };
+
+    let mut first = client::Client::new(&server.ip(), server.port(), 1000);
+    assert!(first.connect().is_ok(), "Failed to connect to the server");
+    assert!(matches!(next_event(), ServerEvent::Connected { .. }));
+    let mut second = client::Client::new(&server.ip(), server.port(), 1000);
+    assert!(second.connect().is_ok(), "Failed to connect to the server");
+    assert!(matches!(next_event(), ServerEvent::Connected { .. }));
+
+    // Clients are listed oldest first, each under an id of its own
+    let clients = server.server().clients();
+    assert_eq!(clients.len(), 2);
+    assert!(clients[0].id < clients[1].id);
+    assert!(clients[0].connected_since <= clients[1].connected_since);
+    assert!(clients[0].age >= clients[1].age);
+    let since = clients[0].connected_since.elapsed().unwrap_or_default();
+    assert!(since < Duration::from_secs(60), "Connected {:?} ago", since);
+    let first_addr = first.stream().unwrap().local_addr().unwrap();
+    assert_eq!(clients[0].addr, first_addr);
+
+    assert!(server.server().disconnect(clients[0].id));
+    match next_event() {
+        ServerEvent::Disconnected { addr, reason } => {
+            assert_eq!(addr, first_addr);
+            assert_eq!(reason, CloseReason::Kicked);
+        }
+        event => panic!("Expected Disconnected event, got {:?}", event),
+    }
+    assert!(!server.server().disconnect(clients[0].id));
+    let remaining: Vec<_> = server
+        .server()
+        .clients()
+        .iter()
+        .map(|client| client.id)
+        .collect();
+    assert_eq!(remaining, [clients[1].id]);
+
+    // The other client is still served
+    let message = client_message::Message::AddRequest(AddRequest { a: 1, b: 2 });
+    assert!(second.send(message).is_ok(), "Failed to send message");
+    assert!(matches!(
+        second.receive().map(|response| response.message),
+        Ok(Some(server_message::Message::AddResponse(_)))
+    ));
+
+    assert!(
+        second.disconnect().is_ok(),
+        "Failed to disconnect from the server"
+    );
+    server.stop();
+}
+
+#[test]
+fn test_session_summary_on_shutdown() {
+    // Set up a server that reports to its clients before closing them
+    let config = ServerConfig {
+        session_summary: true,
+        ..ServerConfig::default()
+    };
+    let server = TestServer::with_config(config);
+
+    // Create and connect the client
+    let mut client = client::Client::new(&server.ip(), server.port(), 1000);
+    assert!(client.connect().is_ok(), "Failed to connect to the server");
+
+    // Handle one request so there is something to summarize
+    let add_request = AddRequest { a: 1, b: 2 };
+    assert!(
+        client
+            .send(client_message::Message::AddRequest(add_request))
+            .is_ok(),
+        "Failed to send message"
+    );
+    let response = client.receive().expect("Failed to receive response");
+    assert!(
+        matches!(
+            response.message,
+            Some(server_message::Message::AddResponse(_))
+        ),
+        "Expected AddResponse, but received a different message"
+    );
+
+    // Stopping the server closes the connection, after sending the summary
+    server.server().stop();
+    match client.receive().map(|response| response.message) {
+        Ok(Some(server_message::Message::SessionSummary(summary))) => {
+            assert_eq!(summary.messages, 1, "Unexpected number of messages");
+            assert_eq!(summary.errors, 0, "Unexpected number of errors");
+            assert!(summary.bytes_received > 0, "No bytes were received");
+            assert!(summary.bytes_sent > 0, "No bytes were sent");
+            assert_eq!(summary.close_reason, "shutdown");
+        }
+        _ => panic!("Expected SessionSummary, but received a different message"),
+    }
+
+    // Wait for the server thread to finish
+    server.stop();
+}
+
+#[test]
+fn test_clients_are_listed_by_the_server_clock() {
+    let clock = MockClock::new();
+    clock.advance(Duration::from_secs(60 * 60));
+    let server = Server::builder()
+        .clock(clock.clone())
+        .bind("127.0.0.1:0")
+        .expect("Failed to start server");
+    let server = TestServer::run(server);
+
+    let mut client = client::Client::new(&server.ip(), server.port(), 1000);
+    assert!(client.connect().is_ok(), "Failed to connect to the server");
+    let deadline = Instant::now() + Duration::from_secs(5);
+    let listed = loop {
+        if let Some(listed) = server.server().clients().pop() {
+            break listed;
+        }
+        assert!(Instant::now() < deadline, "Client was never listed");
+        thread::sleep(Duration::from_millis(10));
+    };
+    // An hour ahead of the system clock, as the server's clock is
+    let ahead = listed
+        .connected_since
+        .duration_since(SystemTime::now())
+        .expect("Connection time does not follow the server clock");
+    assert!(ahead > Duration::from_secs(59 * 60));
+
+    assert!(
+        client.disconnect().is_ok(),
+        "Failed to disconnect from the server"
+    );
+    server.stop();
+}
+
+#[test]
+fn test_kicked_clients_are_cut_off_after_the_close_timeout() {
+    // Additions take far longer than the close timeout
+    let server = Server::builder()
+        .handler(
+            "add",
+            |_: client_message::Message, _: &RequestContext<'_>| {
+                thread::sleep(Duration::from_secs(3));
+                Some(server_message::Message::AddResponse(AddResponse {
+                    result: 0,
+                }))
+            },
+        )
+        .close_timeout(Duration::from_millis(100))
+        .bind("127.0.0.1:0")
+        .expect("Failed to start server");
+    let server = TestServer::run(server);
+
+    let mut client = client::Client::new(&server.ip(), server.port(), 5000);
+    assert!(client.connect().is_ok(), "Failed to connect to the server");
+    let message = client_message::Message::AddRequest(AddRequest { a: 1, b: 2 });
+    assert!(client.send(message).is_ok(), "Failed to send message");
+    let started = Instant::now();
+    let id = loop {
+        if let Some(info) = server.server().clients().first() {
+            break info.id;
+        }
+        assert!(
+            started.elapsed() < Duration::from_secs(5),
+            "Client not listed"
+        );
+        thread::sleep(Duration::from_millis(10));
+    };
+
+    // The connection is cut off while its request is still being handled
+    let kicked = Instant::now();
+    assert!(server.server().disconnect(id));
+    assert!(client.receive().is_err(), "Kicked client got a response");
+    assert!(
+        kicked.elapsed() < Duration::from_secs(2),
+        "Kicked client was cut off after {:?}",
+        kicked.elapsed()
+    );
+    server.stop();
+}
@@ -0,0 +1,111 @@
+#![cfg(feature = "server")]
+
+use embedded_recruitment_task::{
+    events::{CloseReason, ServerEvent},
+    framing::FrameDecoder,
+    message::{client_message, server_message, AddRequest, ServerMessage},
+    server::ServerConfig,
+};
+use prost::Message;
+use std::{
+    io::{Read, Write},
+    time::{Duration, Instant},
+};
+
+mod client;
+mod test_server;
+
+use test_server::TestServer;
+
+#[test]
+fn test_shutdown_does_not_truncate_last_response() {
+    // The session summary is the last thing the server writes before closing
+    let config = ServerConfig {
+        session_summary: true,
+        ..ServerConfig::default()
+    };
+    let server = TestServer::with_config(config);
+
+    let mut client = client::Client::new(&server.ip(), server.port(), 1000);
+    assert!(client.connect().is_ok(), "Failed to connect to the server");
+    let message = client_message::Message::AddRequest(AddRequest { a: 1, b: 2 });
+    assert!(client.send(message).is_ok(), "Failed to send message");
+    assert!(client.receive().is_ok(), "Failed to receive response");
+
+    // Stop the server while the client keeps sending, leaving data the server never reads
+    server.server().stop();
+    let mut stream = client.stream().expect("Client is not connected");
+    for _ in 0..8 {
+        let _ = stream.write_all(&[0xff; 64]);
+    }
+
+    // Everything up to the server hanging up must arrive, without the connection being reset
+    let mut received = Vec::new();
+    stream
+        .read_to_end(&mut received)
+        .expect("Connection was reset instead of closed");
+    let mut frames = FrameDecoder::default();
+    frames.extend(&received);
+    let mut last = None;
+    while let Some(frame) = frames.next_frame().expect("Invalid frame") {
+        last = Some(frame);
+    }
+    assert_eq!(frames.buffered(), 0, "Last response was truncated");
+    let last = last.expect("Nothing was received");
+    let last = ServerMessage::decode(last.as_slice()).expect("Failed to decode response");
+    assert!(
+        matches!(
+            last.message,
+            Some(server_message::Message::SessionSummary(_))
+        ),
+        "Expected SessionSummary, but received a different message"
+    );
+
+    // Wait for the server thread to finish
+    server.stop();
+}
+
+#[test]
+fn test_stop_and_wait_tears_down_connections() {
+    // Closing gracefully would wait far longer for the client to hang up
+    let config = ServerConfig {
+        close_timeout: Duration::from_secs(30),
+        ..ServerConfig::default()
+    };
+    let server = TestServer::with_config(config);
+    let events = server.server().subscribe_events();
+
+    let mut client = client::Client::new(&server.ip(), server.port(), 1000);
+    assert!(client.connect().is_ok(), "Failed to connect to the server");
+    let message = client_message::Message::AddRequest(AddRequest { a: 1, b: 2 });
+    assert!(client.send(message).is_ok(), "Failed to send message");
+    assert!(client.receive().is_ok(), "Failed to receive response");
+
+    // The client never hangs up, so its connection has to be shut down
+    let started = Instant::now();
+    assert!(server.server().stop_and_wait(Duration::from_secs(2)));
+    assert!(started.elapsed() < Duration::from_secs(2));
+
+    // By the time it returns, the connection is reported closed and is gone
+    let disconnected = events.try_iter().any(|event| {
+        matches!(
+            event,
+            ServerEvent::Disconnected {
+                reason: CloseReason::Shutdown,
+                ..
+            }
+        )
+    });
+    assert!(disconnected, "Connection was not reported closed");
+    assert!(server.server().active_sessions().is_empty());
+    let mut stream = client.stream().expect("Client is not connected");
+    stream
+        .set_read_timeout(Some(Duration::from_secs(1)))
+        .unwrap();
+    let mut received = Vec::new();
+    let _ = stream.read_to_end(&mut received);
+    assert!(received.is_empty());
+
+    // Wait for the server thread to finish
+    server.stop();
+}
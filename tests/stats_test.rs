@@ -0,0 +1,129 @@
+#![cfg(feature = "server")]
+
+use embedded_recruitment_task::{
+    admin::ADMIN_TOKEN_HEADER,
+    message::{
+        client_message, server_message, AddRequest, DivideRequest, EchoMessage, TopMessagesRequest,
+        TopMessagesResponse,
+    },
+    server::Server,
+};
+use std::collections::HashMap;
+
+mod client;
+mod test_server;
+
+use test_server::TestServer;
+
+// Asks the server for its busiest message types, presenting `token` if there is one
+fn top_messages(
+    client: &mut client::Client,
+    limit: u32,
+    token: Option<&str>,
+) -> TopMessagesResponse {
+    let headers = token
+        .map(|token| HashMap::from([(ADMIN_TOKEN_HEADER.to_string(), token.to_string())]))
+        .unwrap_or_default();
+    let message = client_message::Message::TopMessagesRequest(TopMessagesRequest { limit });
+    assert!(
+        client.send_with_headers(message, headers).is_ok(),
+        "Failed to send message"
+    );
+    match client.receive().map(|response| response.message) {
+        Ok(Some(server_message::Message::TopMessagesResponse(response))) => response,
+        _ => panic!("Expected TopMessagesResponse, but received a different message"),
+    }
+}
+
+#[test]
+fn test_top_messages_request() {
+    // Set up the server in a separate thread
+    let server = Server::builder()
+        .admin_token("secret")
+        .bind("127.0.0.1:0")
+        .expect("Failed to start server");
+    let server = TestServer::run(server);
+
+    // Create and connect the client
+    let mut client = client::Client::new(&server.ip(), server.port(), 1000);
+    assert!(client.connect().is_ok(), "Failed to connect to the server");
+
+    // Generate some traffic: two additions and one echo
+    let messages = [
+        client_message::Message::AddRequest(AddRequest { a: 1, b: 2 }),
+        client_message::Message::AddRequest(AddRequest { a: 3, b: 4 }),
+        client_message::Message::EchoMessage(EchoMessage {
+            content: "Hello, World!".to_string(),
+        }),
+    ];
+    for message in messages {
+        assert!(client.send(message).is_ok(), "Failed to send message");
+        assert!(client.receive().is_ok(), "Failed to receive response");
+    }
+
+    // Statistics are for admins only
+    let refused = top_messages(&mut client, 1, None);
+    assert_eq!(refused.error, "Unauthorized");
+    assert!(refused.stats.is_empty());
+
+    // Query the statistics for the busiest message type only
+    let top_messages = top_messages(&mut client, 1, Some("secret"));
+    assert!(top_messages.error.is_empty());
+    assert_eq!(top_messages.window_secs, 60, "Unexpected statistics window");
+    assert_eq!(top_messages.stats.len(), 1, "Expected a single entry");
+
+    let add = &top_messages.stats[0];
+    assert_eq!(add.message_type, "add");
+    assert_eq!(add.count, 2);
+    assert_eq!(add.errors, 0);
+    assert_eq!(add.error_rate, 0.0);
+    assert!(add.p99_latency_us >= add.avg_latency_us);
+
+    let histogram = add
+        .latency_histogram_us
+        .as_ref()
+        .expect("Missing latency histogram");
+    assert_eq!(histogram.count, 2);
+    assert_eq!(histogram.max, add.p99_latency_us);
+
+    // Disconnect the client
+    assert!(
+        client.disconnect().is_ok(),
+        "Failed to disconnect from the server"
+    );
+
+    // Stop the server and wait for thread to finish
+    server.stop();
+}
+
+#[test]
+fn test_error_responses_count_as_errors() {
+    let server = Server::builder()
+        .admin_token("secret")
+        .bind("127.0.0.1:0")
+        .expect("Failed to start server");
+    let server = TestServer::run(server);
+
+    let mut client = client::Client::new(&server.ip(), server.port(), 1000);
+    assert!(client.connect().is_ok(), "Failed to connect to the server");
+
+    // One division that succeeds and one by zero, which the handler refuses
+    for b in [2, 0] {
+        let message = client_message::Message::DivideRequest(DivideRequest { a: 4, b });
+        assert!(client.send(message).is_ok(), "Failed to send message");
+        assert!(client.receive().is_ok(), "Failed to receive response");
+    }
+
+    let top_messages = top_messages(&mut client, 1, Some("secret"));
+    let divide = &top_messages.stats[0];
+    assert_eq!(divide.message_type, "divide");
+    assert_eq!(divide.count, 2);
+    assert_eq!(divide.errors, 1);
+    assert_eq!(divide.error_rate, 0.5);
+
+    assert!(
+        client.disconnect().is_ok(),
+        "Failed to disconnect from the server"
+    );
+    server.stop();
+}
@@ -0,0 +1,92 @@
+// Not every test uses every helper
+#![allow(dead_code)]
+#![cfg(feature = "server")]
+
+use embedded_recruitment_task::server::{Server, ServerConfig};
+use std::{
+    net::SocketAddr,
+    sync::Arc,
+    thread::{self, JoinHandle},
+};
+
+// Server running on its own ephemeral port in a background thread, so any number of
+// them can run side by side in one test process
+pub struct TestServer {
+    server: Arc<Server>,
+    addr: SocketAddr,
+    handle: Option<JoinHandle<()>>,
+}
+
+impl TestServer {
+    // start a server with the default configuration
+    pub fn start() -> Self {
+        Self::with_config(ServerConfig::default())
+    }
+
+    // start a server with the given configuration
+    pub fn with_config(config: ServerConfig) -> Self {
+        let server = Server::with_config("127.0.0.1:0", config).expect("Failed to start server");
+        Self::run(server)
+    }
+
+    // run an already bound server
+    pub fn run(server: Server) -> Self {
+        let server = Arc::new(server);
+        let addr = server.local_addr().expect("Failed to get server address");
+        let handle = {
+            let server = server.clone();
+            thread::spawn(move || server.run().expect("Server encountered an error"))
+        };
+
+        // Stopping a server that is not running yet does nothing, wait until it is
+        while !server.is_running() {
+            assert!(!handle.is_finished(), "Server thread exited before running");
+            thread::yield_now();
+        }
+
+        TestServer {
+            server,
+            addr,
+            handle: Some(handle),
+        }
+    }
+
+    // the server under test
+    pub fn server(&self) -> &Server {
+        &self.server
+    }
+
+    // the IP address clients should connect to
+    pub fn ip(&self) -> String {
+        self.addr.ip().to_string()
+    }
+
+    // the port clients should connect to
+    pub fn port(&self) -> u32 {
+        self.addr.port() as u32
+    }
+
+    // stop the server and wait for thread to finish
+    pub fn stop(mut self) {
+        self.shutdown();
+    }
+
+    fn shutdown(&mut self) {
+        if let Some(handle) = self.handle.take() {
+            self.server.stop();
+            assert!(
+                handle.join().is_ok(),
+                "Server thread panicked or failed to join"
+            );
+        }
+    }
+}
+
+impl Drop for TestServer {
+    fn drop(&mut self) {
+        // Don't turn a failing test into a double panic
+        if !thread::panicking() {
+            self.shutdown();
+        }
+    }
+}
@@ -0,0 +1,107 @@
+#![cfg(feature = "server")]
+
+use embedded_recruitment_task::{
+    client::Client,
+    handler::RequestContext,
+    message::{client_message, server_message, AddResponse},
+    pool::{ThreadOptions, WorkerPool},
+    server::{Server, ServerBuilder},
+};
+use std::{
+    sync::{mpsc, Mutex},
+    thread,
+    time::Duration,
+};
+
+mod test_server;
+
+use test_server::TestServer;
+
+// Starts a server answering additions with a marker, reporting the name of the thread that
+// handled each of them
+fn server_reporting_threads(builder: ServerBuilder) -> (TestServer, mpsc::Receiver<String>) {
+    let (sender, names) = mpsc::channel();
+    let sender = Mutex::new(sender);
+    let server = builder
+        .handler(
+            "add",
+            move |_: client_message::Message, _: &RequestContext<'_>| {
+                let name = thread::current().name().unwrap_or_default().to_string();
+                let _ = sender.lock().unwrap().send(name);
+                Some(server_message::Message::AddResponse(AddResponse {
+                    result: 0,
+                }))
+            },
+        )
+        .bind("127.0.0.1:0")
+        .expect("Failed to start server");
+    (TestServer::run(server), names)
+}
+
+#[test]
+fn test_connection_threads_are_named() {
+    let (server, names) = server_reporting_threads(
+        Server::builder()
+            .thread_name_prefix("echo")
+            .thread_stack_size(256 * 1024),
+    );
+    let mut client = Client::connect(("127.0.0.1", server.port() as u16))
+        .expect("Failed to connect to the server");
+
+    client.add(1, 2).unwrap();
+    let name = names.recv_timeout(Duration::from_secs(5)).unwrap();
+    assert!(
+        name.starts_with("echo-conn-"),
+        "Unexpected thread name {}",
+        name
+    );
+
+    drop(client);
+    server.stop();
+}
+
+#[test]
+fn test_worker_threads_are_named() {
+    let (server, names) = server_reporting_threads(
+        Server::builder()
+            .worker_threads(2)
+            .thread_name_prefix("echo")
+            .thread_stack_size(256 * 1024),
+    );
+    let mut client = Client::connect(("127.0.0.1", server.port() as u16))
+        .expect("Failed to connect to the server");
+
+    client.add(1, 2).unwrap();
+    let name = names.recv_timeout(Duration::from_secs(5)).unwrap();
+    assert!(
+        name.starts_with("echo-worker-"),
+        "Unexpected thread name {}",
+        name
+    );
+
+    drop(client);
+    server.stop();
+}
+
+#[test]
+fn test_pool_spawns_threads_as_configured() {
+    let threads = ThreadOptions {
+        name_prefix: "test".to_string(),
+        stack_size: Some(128 * 1024),
+    };
+    let pool = WorkerPool::with_threads(3, &threads).expect("Failed to start pool");
+    assert_eq!(pool.size(), 3);
+
+    let (sender, names) = mpsc::channel();
+    for _ in 0..3 {
+        let sender = sender.clone();
+        pool.execute(Box::new(move || {
+            let name = thread::current().name().unwrap_or_default().to_string();
+            sender.send(name).unwrap();
+        }));
+    }
+    drop(pool);
+    let names: Vec<String> = names.try_iter().collect();
+    assert_eq!(names.len(), 3);
+    assert!(names.iter().all(|name| name.starts_with("test-worker-")));
+}
@@ -0,0 +1,140 @@
+#![cfg(feature = "server")]
+
+use embedded_recruitment_task::{
+    client::Client,
+    clock::MockClock,
+    events::{CloseReason, ServerEvent},
+    framing,
+    message::{client_message, ClientMessage, EchoMessage},
+    server::Server,
+};
+use prost::Message;
+use std::{
+    io::{Read, Write},
+    net::TcpStream,
+    thread,
+    time::{Duration, Instant},
+};
+
+mod test_server;
+
+use test_server::TestServer;
+
+#[test]
+fn test_silent_client_is_disconnected() {
+    let server = Server::builder()
+        .read_timeout(Duration::from_millis(300))
+        .bind("127.0.0.1:0")
+        .expect("Failed to start server");
+    let events = server.subscribe_events();
+    let server = TestServer::run(server);
+    let addr = ("127.0.0.1", server.port() as u16);
+
+    // A client that keeps talking stays connected past the timeout
+    let mut talking = Client::connect(addr).expect("Failed to connect to the server");
+    for i in 0..6 {
+        assert_eq!(talking.add(i, 1).unwrap(), i + 1);
+        thread::sleep(Duration::from_millis(100));
+    }
+
+    // One that sends nothing is hung up on
+    let started = Instant::now();
+    let mut silent = TcpStream::connect(addr).expect("Failed to connect to the server");
+    silent
+        .set_read_timeout(Some(Duration::from_secs(5)))
+        .unwrap();
+    let mut received = Vec::new();
+    silent
+        .read_to_end(&mut received)
+        .expect("Connection was not closed");
+    assert!(started.elapsed() >= Duration::from_millis(300));
+
+    let reason = events
+        .iter()
+        .find_map(|event| match event {
+            ServerEvent::Disconnected { reason, .. } => Some(reason),
+            _ => None,
+        })
+        .expect("No connection was closed");
+    assert_eq!(reason, CloseReason::IdleTimeout);
+    assert_eq!(server.server().active_sessions().len(), 1);
+
+    drop(talking);
+    server.stop();
+}
+
+#[test]
+fn test_client_not_reading_responses_is_disconnected() {
+    let server = Server::builder()
+        .write_timeout(Duration::from_millis(200))
+        .bind("127.0.0.1:0")
+        .expect("Failed to start server");
+    let events = server.subscribe_events();
+    let server = TestServer::run(server);
+
+    // Request echoes without ever reading them, until the server gives up
+    let mut stream =
+        TcpStream::connect(("127.0.0.1", server.port() as u16)).expect("Failed to connect");
+    let message = ClientMessage {
+        message: Some(client_message::Message::EchoMessage(EchoMessage {
+            content: "x".repeat(100),
+        })),
+        ..ClientMessage::default()
+    };
+    let batch = framing::encode_frame(&message.encode_to_vec()).repeat(64);
+    let writer = thread::spawn(move || while stream.write_all(&batch).is_ok() {});
+
+    let reason = loop {
+        match events
+            .recv_timeout(Duration::from_secs(10))
+            .expect("Timed out waiting for the connection to be closed")
+        {
+            ServerEvent::Disconnected { reason, .. } => break reason,
+            _ => continue,
+        }
+    };
+    assert_eq!(reason, CloseReason::WriteError);
+
+    writer.join().unwrap();
+    server.stop();
+}
+
+#[test]
+fn test_read_timeout_follows_the_server_clock() {
+    let clock = MockClock::new();
+    let server = Server::builder()
+        .read_timeout(Duration::from_secs(60 * 60))
+        .clock(clock.clone())
+        .bind("127.0.0.1:0")
+        .expect("Failed to start server");
+    let events = server.subscribe_events();
+    let server = TestServer::run(server);
+    let mut silent = TcpStream::connect(("127.0.0.1", server.port() as u16))
+        .expect("Failed to connect to the server");
+    silent
+        .set_read_timeout(Some(Duration::from_secs(5)))
+        .unwrap();
+    let deadline = Instant::now() + Duration::from_secs(5);
+    while server.server().clients().is_empty() {
+        assert!(Instant::now() < deadline, "Client was never listed");
+        thread::sleep(Duration::from_millis(10));
+    }
+
+    // The silent client is hung up on as soon as the server clock passes the timeout
+    clock.advance(Duration::from_secs(60 * 60));
+    let mut received = Vec::new();
+    silent
+        .read_to_end(&mut received)
+        .expect("Connection was not closed");
+
+    let reason = events
+        .iter()
+        .find_map(|event| match event {
+            ServerEvent::Disconnected { reason, .. } => Some(reason),
+            _ => None,
+        })
+        .expect("No connection was closed");
+    assert_eq!(reason, CloseReason::IdleTimeout);
+
+    server.stop();
+}